@@ -10,7 +10,7 @@ use std::fmt;
 ///
 /// When creating ZIP files, you may choose the method to use with
 /// [`crate::write::FileOptions::compression_method`]
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(fuzzing, derive(arbitrary::Arbitrary))]
 #[non_exhaustive]
 pub enum CompressionMethod {
@@ -38,6 +38,12 @@ pub enum CompressionMethod {
     /// Compress the file using LZMA
     #[cfg(feature = "lzma")]
     Lzma,
+    /// Compress the file using Brotli. Only decompression is supported.
+    #[cfg(feature = "brotli")]
+    Brotli,
+    /// Compress the file using PPMd variant I. Only decompression is supported.
+    #[cfg(feature = "ppmd")]
+    Ppmd,
     /// Unsupported compression method
     #[cfg_attr(
         not(fuzzing),
@@ -83,11 +89,18 @@ impl CompressionMethod {
     pub const XZ: Self = CompressionMethod::Unsupported(95);
     pub const JPEG: Self = CompressionMethod::Unsupported(96);
     pub const WAVPACK: Self = CompressionMethod::Unsupported(97);
+    #[cfg(feature = "ppmd")]
+    pub const PPMD: Self = CompressionMethod::Ppmd;
+    #[cfg(not(feature = "ppmd"))]
     pub const PPMD: Self = CompressionMethod::Unsupported(98);
     #[cfg(feature = "aes-crypto")]
     pub const AES: Self = CompressionMethod::Aes;
     #[cfg(not(feature = "aes-crypto"))]
     pub const AES: Self = CompressionMethod::Unsupported(99);
+    #[cfg(feature = "brotli")]
+    pub const BROTLI: Self = CompressionMethod::Brotli;
+    #[cfg(not(feature = "brotli"))]
+    pub const BROTLI: Self = CompressionMethod::Unsupported(121);
 }
 impl CompressionMethod {
     pub(crate) const fn parse_from_u16(val: u16) -> Self {
@@ -103,8 +116,12 @@ impl CompressionMethod {
             14 => CompressionMethod::Lzma,
             #[cfg(feature = "zstd")]
             93 => CompressionMethod::Zstd,
+            #[cfg(feature = "ppmd")]
+            98 => CompressionMethod::Ppmd,
             #[cfg(feature = "aes-crypto")]
             99 => CompressionMethod::Aes,
+            #[cfg(feature = "brotli")]
+            121 => CompressionMethod::Brotli,
             #[allow(deprecated)]
             v => CompressionMethod::Unsupported(v),
         }
@@ -134,11 +151,42 @@ impl CompressionMethod {
             CompressionMethod::Zstd => 93,
             #[cfg(feature = "lzma")]
             CompressionMethod::Lzma => 14,
+            #[cfg(feature = "brotli")]
+            CompressionMethod::Brotli => 121,
+            #[cfg(feature = "ppmd")]
+            CompressionMethod::Ppmd => 98,
             #[allow(deprecated)]
             CompressionMethod::Unsupported(v) => v,
         }
     }
 
+    /// The name of a compression method number, as listed in APPNOTE 4.4.5.
+    pub(crate) const fn name_of(method: u16) -> Option<&'static str> {
+        Some(match method {
+            0 => "Stored",
+            1 => "Shrunk",
+            2..=5 => "Reduced",
+            6 => "Imploded",
+            8 => "Deflated",
+            9 => "Deflate64",
+            10 => "PKWARE DCL Imploded",
+            12 => "BZIP2",
+            14 => "LZMA",
+            16 => "IBM z/OS CMPSC",
+            18 => "IBM TERSE",
+            19 => "IBM LZ77 z Architecture",
+            93 => "Zstandard",
+            94 => "MP3",
+            95 => "XZ",
+            96 => "JPEG",
+            97 => "WavPack",
+            98 => "PPMd",
+            99 => "AE-x encryption",
+            121 => "Brotli",
+            _ => return None,
+        })
+    }
+
     /// Converts a CompressionMethod to a u16
     #[deprecated(
         since = "0.5.7",
@@ -166,6 +214,22 @@ impl fmt::Display for CompressionMethod {
     }
 }
 
+/// Serialized as the method's number in the ZIP format, which doesn't depend on which features
+/// are enabled.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CompressionMethod {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.serialize_to_u16())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CompressionMethod {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u16::deserialize(deserializer).map(CompressionMethod::parse_from_u16)
+    }
+}
+
 /// The compression methods which have been implemented.
 pub const SUPPORTED_COMPRESSION_METHODS: &[CompressionMethod] = &[
     CompressionMethod::Stored,
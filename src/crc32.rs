@@ -5,14 +5,19 @@ use std::io::prelude::*;
 
 use crc32fast::Hasher;
 
-/// Reader that validates the CRC32 when it reaches the EOF.
+use crate::result::ZipError;
+
+/// Reader that validates the CRC32, and optionally the size, when it reaches the EOF.
 pub struct Crc32Reader<R> {
     inner: R,
     hasher: Hasher,
     check: u32,
     /// Signals if `inner` stores aes encrypted data.
     /// AE-2 encrypted data doesn't use crc and sets the value to 0.
+    /// Also set when the caller checks the CRC-32 itself.
     ae2_encrypted: bool,
+    expected_size: Option<u64>,
+    bytes_read: u64,
 }
 
 impl<R> Crc32Reader<R> {
@@ -24,9 +29,22 @@ impl<R> Crc32Reader<R> {
             hasher: Hasher::new(),
             check: checksum,
             ae2_encrypted,
+            expected_size: None,
+            bytes_read: 0,
         }
     }
 
+    /// Also check that exactly `size` bytes are read before the EOF.
+    pub(crate) fn with_expected_size(mut self, size: Option<u64>) -> Crc32Reader<R> {
+        self.expected_size = size;
+        self
+    }
+
+    /// Stop checking the checksum at the EOF. The size is still checked.
+    pub(crate) fn disable_check(&mut self) {
+        self.ae2_encrypted = true;
+    }
+
     fn check_matches(&self) -> bool {
         self.check == self.hasher.clone().finalize()
     }
@@ -44,14 +62,40 @@ impl<R: Read> Read for Crc32Reader<R> {
             Ok(0) if invalid_check => {
                 return Err(io::Error::new(io::ErrorKind::Other, "Invalid checksum"))
             }
+            Ok(0)
+                if !buf.is_empty()
+                    && self
+                        .expected_size
+                        .is_some_and(|size| size != self.bytes_read) =>
+            {
+                return Err(ZipError::InvalidArchive("Decompressed size mismatch").into())
+            }
             Ok(n) => n,
             Err(e) => return Err(e),
         };
         self.hasher.update(&buf[0..count]);
+        self.bytes_read += count as u64;
         Ok(count)
     }
 }
 
+/// Compute the CRC-32 of everything read from `reader` until EOF.
+///
+/// This is the IEEE CRC-32 used by the ZIP format, so for a file in an archive, the result for its
+/// decompressed contents matches [`ZipFile::crc32`](crate::read::ZipFile::crc32).
+pub fn crc32_of<R: Read>(mut reader: R) -> io::Result<u32> {
+    let mut hasher = Hasher::new();
+    let mut buf = [0; 8192];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -72,6 +116,28 @@ mod test {
             .contains("Invalid checksum"));
     }
 
+    #[test]
+    fn test_crc32_of() {
+        assert_eq!(crc32_of(&b""[..]).unwrap(), 0);
+        assert_eq!(crc32_of(&b"1234"[..]).unwrap(), 0x9be3e0a3);
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let data: &[u8] = b"1234";
+        let mut buf = Vec::new();
+
+        let mut reader = Crc32Reader::new(data, 0x9be3e0a3, false).with_expected_size(Some(4));
+        reader.read_to_end(&mut buf).unwrap();
+
+        let mut reader = Crc32Reader::new(data, 0x9be3e0a3, false).with_expected_size(Some(5));
+        assert!(reader
+            .read_to_end(&mut buf)
+            .unwrap_err()
+            .to_string()
+            .contains("Decompressed size mismatch"));
+    }
+
     #[test]
     fn test_byte_by_byte() {
         let data: &[u8] = b"1234";
@@ -5,6 +5,7 @@ use std::io::Read;
 /// extended timestamp, as described in <https://libzip.org/specifications/extrafld.txt>
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtendedTimestamp {
     mod_time: Option<u32>,
     ac_time: Option<u32>,
@@ -70,6 +71,31 @@ impl ExtendedTimestamp {
         })
     }
 
+    /// Find and parse the first extended timestamp field in a raw extra field, if there is one.
+    pub(crate) fn find_in_extra_field(extra_field: &[u8]) -> ZipResult<Option<Self>> {
+        let mut reader = extra_field;
+        while reader.len() >= 4 {
+            let kind = reader.read_u16_le()?;
+            let len = reader.read_u16_le()?;
+            if kind == 0x5455 {
+                let mut payload = reader.take(len as u64);
+                return Self::try_from_reader(&mut payload, len).map(Some);
+            }
+            reader = reader.get(len as usize..).unwrap_or_default();
+        }
+        Ok(None)
+    }
+
+    /// Combine two copies of the field, such as the local and central ones, taking each time
+    /// from `self` if it's present there and from `other` otherwise.
+    pub(crate) fn or(self, other: Self) -> Self {
+        Self {
+            mod_time: self.mod_time.or(other.mod_time),
+            ac_time: self.ac_time.or(other.ac_time),
+            cr_time: self.cr_time.or(other.cr_time),
+        }
+    }
+
     /// returns the last modification timestamp
     pub fn mod_time(&self) -> Option<&u32> {
         self.mod_time.as_ref()
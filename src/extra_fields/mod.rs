@@ -17,12 +17,37 @@ impl ExtraFieldVersion for LocalHeaderVersion {}
 impl ExtraFieldVersion for CentralHeaderVersion {}
 
 mod extended_timestamp;
+mod zip64;
 
 pub use extended_timestamp::*;
+pub use zip64::*;
 
 /// contains one extra field
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum ExtraField {
     /// extended timestamp, as described in <https://libzip.org/specifications/extrafld.txt>
     ExtendedTimestamp(ExtendedTimestamp),
+
+    /// OS/2 extended attributes (header ID `0x0009`), as the raw payload of the field
+    Os2ExtendedAttributes(Vec<u8>),
+
+    /// Info-ZIP Unicode Path (header ID `0x7075`), as the raw payload of the field: a version
+    /// byte, the CRC-32 of the name in the header, and the name in UTF-8. When the CRC-32 matches,
+    /// [`ZipFile::name`](crate::read::ZipFile::name) returns the name from this field.
+    UnicodePath(Vec<u8>),
+
+    /// Info-ZIP New Unix (header ID `0x7875`): the Unix user and group IDs of the file's owner
+    UnixUidGid {
+        /// User ID
+        uid: u32,
+        /// Group ID
+        gid: u32,
+    },
+
+    /// JAR marker (header ID `0xCAFE`), which the `jar` tool writes on the first entry of a JAR
+    /// file. Its payload is normally empty, but some tools use it as padding to align the entry's
+    /// data; this holds the length of that padding.
+    JavaAlignment(u16),
 }
@@ -0,0 +1,35 @@
+/// ZIP64 extended information (header ID `0x0001`), with each value that was present in the
+/// field.
+///
+/// Which values the field holds depends on which of the 32-bit values in the header overflowed,
+/// unless the archiver wrote them all.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zip64ExtendedInformation {
+    pub(crate) uncompressed_size: Option<u64>,
+    pub(crate) compressed_size: Option<u64>,
+    pub(crate) header_start: Option<u64>,
+    pub(crate) disk_start: Option<u32>,
+}
+
+impl Zip64ExtendedInformation {
+    /// returns the size of the file when extracted, if present
+    pub fn uncompressed_size(&self) -> Option<u64> {
+        self.uncompressed_size
+    }
+
+    /// returns the size of the file in the archive, if present
+    pub fn compressed_size(&self) -> Option<u64> {
+        self.compressed_size
+    }
+
+    /// returns the offset of the local file header, if present
+    pub fn header_start(&self) -> Option<u64> {
+        self.header_start
+    }
+
+    /// returns the number of the disk on which the file starts, if present
+    pub fn disk_start(&self) -> Option<u32> {
+        self.disk_start
+    }
+}
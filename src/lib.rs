@@ -21,6 +21,9 @@
 //! | Deflate64 | ✅ | |
 //! | Bzip2 | ✅ | ✅ |
 //! | LZMA | ✅ | |
+//! | Brotli | ✅ | |
+//! | LZ4 | ✅ | |
+//! | PPMd | ✅ | |
 //! | AES encryption | ✅ | ✅ |
 //! | ZipCrypto deprecated encryption | ✅ | ✅ |
 //!
@@ -29,7 +32,8 @@
 #![warn(missing_docs)]
 #![allow(unexpected_cfgs)] // Needed for cfg(fuzzing) on nightly as of 2024-05-06
 pub use crate::compression::{CompressionMethod, SUPPORTED_COMPRESSION_METHODS};
-pub use crate::read::ZipArchive;
+pub use crate::crc32::crc32_of;
+pub use crate::read::{extract_file, open, ConcatReader, ZipArchive};
 pub use crate::types::{AesMode, DateTime};
 pub use crate::write::ZipWriter;
 
@@ -41,9 +45,10 @@ mod compression;
 mod cp437;
 mod crc32;
 pub mod extra_fields;
+pub mod path;
 pub mod read;
 pub mod result;
-mod spec;
+pub mod spec;
 mod types;
 pub mod write;
 mod zipcrypto;
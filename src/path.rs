@@ -0,0 +1,95 @@
+//! Rules for entry names: how directories are recognized, and how filesystem paths are
+//! converted to names.
+
+use std::borrow::Cow;
+use std::path::{Component, Path, MAIN_SEPARATOR};
+
+/// Whether an entry name is that of a directory, which is the case if and only if it ends with
+/// `/`. This is the rule behind [`ZipFile::is_dir`](crate::read::ZipFile::is_dir).
+///
+/// The ZIP specification only allows `/` as a separator, so a trailing `\` is just part of a
+/// file name, even though some Windows tools write such names.
+///
+/// ```
+/// assert!(zip::path::is_dir("dir/"));
+/// assert!(!zip::path::is_dir("dir"));
+/// assert!(!zip::path::is_dir("dir\\"));
+/// ```
+pub fn is_dir(filename: &str) -> bool {
+    filename.ends_with('/')
+}
+
+/// Convert a path to an entry name, as methods such as
+/// [`ZipWriter::start_file_from_path`](crate::ZipWriter::start_file_from_path) and
+/// [`ZipArchive::index_for_path`](crate::ZipArchive::index_for_path) do.
+///
+/// The path is split into components using the platform's rules, so `\` is a separator on
+/// Windows but part of a file name elsewhere. Then:
+///
+/// - a leading separator, or on Windows a drive or UNC prefix, is stripped, so the name is
+///   relative;
+/// - `.` components and repeated or trailing separators are dropped;
+/// - `..` removes the component before it, and is dropped if there's nothing left to remove, so
+///   the name can't escape the archive root;
+/// - components that aren't valid UTF-8 are converted lossily;
+/// - the remaining components are joined with `/`.
+///
+/// ```
+/// assert_eq!(&*zip::path::path_to_string("/foo/../bar/./baz/"), "bar/baz");
+/// assert_eq!(&*zip::path::path_to_string("../../etc/passwd"), "etc/passwd");
+/// assert_eq!(&*zip::path::path_to_string("a//b/./c/"), "a/b/c");
+/// assert_eq!(&*zip::path::path_to_string(".config/app"), ".config/app");
+/// assert_eq!(&*zip::path::path_to_string(""), "");
+///
+/// // `\` only separates components on Windows
+/// #[cfg(windows)]
+/// assert_eq!(&*zip::path::path_to_string(r"é\.\x"), "é/x");
+/// #[cfg(not(windows))]
+/// assert_eq!(&*zip::path::path_to_string(r"é\.\x"), r"é\.\x");
+/// ```
+pub fn path_to_string<T: AsRef<Path>>(path: T) -> Box<str> {
+    let mut maybe_original = None;
+    if let Some(original) = path.as_ref().to_str() {
+        // The path can be used as it is if `/` is its only separator, and it has no leading,
+        // trailing or repeated separators and no `.` or `..` components. Windows also splits on
+        // `\`, so a path containing that is always rebuilt.
+        if (MAIN_SEPARATOR == '/' || !original.contains(MAIN_SEPARATOR))
+            && !original.starts_with('/')
+            && !original.starts_with("./")
+            && !original.starts_with("../")
+            && !original.ends_with('/')
+            && !original.ends_with('.')
+            && !original.contains("//")
+            && !original.contains("/./")
+            && !original.contains("/../")
+        {
+            maybe_original = Some(original);
+        }
+    }
+    let mut recreate = maybe_original.is_none();
+    let mut normalized_components = Vec::new();
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::Normal(os_str) => match os_str.to_str() {
+                Some(valid_str) => normalized_components.push(Cow::Borrowed(valid_str)),
+                None => {
+                    recreate = true;
+                    normalized_components.push(os_str.to_string_lossy());
+                }
+            },
+            Component::ParentDir => {
+                recreate = true;
+                normalized_components.pop();
+            }
+            _ => {
+                recreate = true;
+            }
+        }
+    }
+    if recreate {
+        normalized_components.join("/").into()
+    } else {
+        maybe_original.unwrap().into()
+    }
+}
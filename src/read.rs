@@ -5,23 +5,24 @@ use crate::aes::{AesReader, AesReaderValid};
 use crate::compression::CompressionMethod;
 use crate::cp437::FromCp437;
 use crate::crc32::Crc32Reader;
-use crate::extra_fields::{ExtendedTimestamp, ExtraField};
-use crate::read::zip_archive::Shared;
+use crate::extra_fields::{ExtendedTimestamp, ExtraField, Zip64ExtendedInformation};
+use crate::read::zip_archive::{LazyDirectory, Shared};
 use crate::result::{ZipError, ZipResult};
 use crate::spec::{self, Block};
 use crate::types::{
     AesMode, AesVendorVersion, DateTime, System, ZipCentralEntryBlock, ZipFileData,
-    ZipLocalEntryBlock,
+    ZipLocalEntryBlock, MIN_VERSION,
 };
 use crate::zipcrypto::{ZipCryptoReader, ZipCryptoReaderValid, ZipCryptoValidator};
 use indexmap::IndexMap;
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::ffi::OsString;
 use std::fs::create_dir_all;
 use std::io::{self, copy, prelude::*, sink};
 use std::mem;
 use std::ops::Deref;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, OnceLock};
 
 #[cfg(any(
@@ -40,22 +41,83 @@ use bzip2::read::BzDecoder;
 #[cfg(feature = "zstd")]
 use zstd::stream::read::Decoder as ZstdDecoder;
 
+#[cfg(feature = "brotli")]
+use brotli::Decompressor as BrotliDecoder;
+
+#[cfg(feature = "lz4")]
+use lz4_flex::frame::FrameDecoder as Lz4Decoder;
+
 /// Provides high level API for reading from a stream.
 pub(crate) mod stream;
 
 #[cfg(feature = "lzma")]
 pub(crate) mod lzma;
 
+#[cfg(feature = "ppmd")]
+pub(crate) mod ppmd;
+
+mod auto;
+mod concat;
+mod glob;
+mod seekable;
+
+pub use auto::{open, Archive, Unseekable};
+pub use concat::ConcatReader;
+pub use seekable::SeekableZipFile;
+
 // Put the struct declaration in a private module to convince rustdoc to display ZipArchive nicely
 pub(crate) mod zip_archive {
     use std::sync::Arc;
 
     /// Extract immutable data from `ZipArchive` to make it cheap to clone
-    #[derive(Debug)]
+    #[derive(Clone, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub(crate) struct Shared {
+        #[cfg_attr(feature = "serde", serde(with = "serde_files"))]
         pub(crate) files: super::IndexMap<Box<str>, super::ZipFileData>,
         pub(super) offset: u64,
         pub(super) dir_start: u64,
+        // Position of the (ZIP32) end-of-central-directory record
+        pub(super) cde_start_pos: u64,
+        // Central directory entries not parsed yet, for archives opened with `new_lazy`
+        pub(super) lazy: Option<LazyDirectory>,
+        // Whether the archive comment was cut short by the end of the file
+        pub(super) comment_truncated: bool,
+    }
+
+    /// Where to resume parsing a lazily-read central directory
+    #[derive(Clone, Copy, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub(crate) struct LazyDirectory {
+        pub(super) next_header: u64,
+        pub(super) remaining: usize,
+        // Metadata size of the entries parsed so far, and the limit on it
+        pub(super) metadata_size: u64,
+        pub(super) max_metadata_size: u64,
+    }
+
+    /// Serializes the entries as a list, since each is keyed by its own name
+    #[cfg(feature = "serde")]
+    mod serde_files {
+        use super::super::{IndexMap, ZipFileData};
+        use serde::Deserialize;
+
+        pub(super) fn serialize<S: serde::Serializer>(
+            files: &IndexMap<Box<str>, ZipFileData>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(files.values())
+        }
+
+        pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<IndexMap<Box<str>, ZipFileData>, D::Error> {
+            let files = Vec::<ZipFileData>::deserialize(deserializer)?;
+            Ok(files
+                .into_iter()
+                .map(|file| (file.file_name.clone(), file))
+                .collect())
+        }
     }
 
     /// ZIP archive reader
@@ -83,16 +145,20 @@ pub(crate) mod zip_archive {
         pub(super) reader: R,
         pub(super) shared: Arc<Shared>,
         pub(super) comment: Arc<[u8]>,
+        pub(super) decoders: super::CustomDecoders,
+        pub(super) limits: super::ArchiveLimits,
     }
 }
 
 #[cfg(feature = "aes-crypto")]
 use crate::aes::PWD_VERIFY_LENGTH;
+use crate::path::{is_dir, path_to_string};
 #[cfg(feature = "lzma")]
 use crate::read::lzma::LzmaDecoder;
-use crate::result::ZipError::{InvalidPassword, UnsupportedArchive};
-use crate::spec::{is_dir, path_to_string};
-use crate::types::ffi::S_IFLNK;
+#[cfg(feature = "ppmd")]
+use crate::read::ppmd::PpmdDecoder;
+use crate::result::ZipError::InvalidPassword;
+use crate::types::ffi::{S_IFLNK, S_IFMT};
 use crate::unstable::LittleEndianReadExt;
 pub use zip_archive::ZipArchive;
 
@@ -150,6 +216,13 @@ pub(crate) enum ZipFileReader<'a> {
     Stored(Crc32Reader<CryptoReader<'a>>),
     #[cfg(feature = "_deflate-any")]
     Deflated(Crc32Reader<DeflateDecoder<CryptoReader<'a>>>),
+    /// Deflate data read from a stream, whose size and CRC-32 are in a data descriptor after it
+    #[cfg(any(
+        feature = "deflate",
+        feature = "deflate-zlib",
+        feature = "deflate-zlib-ng"
+    ))]
+    DeflatedDataDescriptor(DataDescriptorDeflateReader<'a>),
     #[cfg(feature = "deflate64")]
     Deflate64(Crc32Reader<Deflate64Decoder<io::BufReader<CryptoReader<'a>>>>),
     #[cfg(feature = "bzip2")]
@@ -158,6 +231,13 @@ pub(crate) enum ZipFileReader<'a> {
     Zstd(Crc32Reader<ZstdDecoder<'a, io::BufReader<CryptoReader<'a>>>>),
     #[cfg(feature = "lzma")]
     Lzma(Crc32Reader<Box<LzmaDecoder<CryptoReader<'a>>>>),
+    #[cfg(feature = "brotli")]
+    Brotli(Crc32Reader<Box<BrotliDecoder<CryptoReader<'a>>>>),
+    #[cfg(feature = "lz4")]
+    Lz4(Crc32Reader<Lz4Decoder<io::Chain<io::Cursor<[u8; 4]>, CryptoReader<'a>>>>),
+    #[cfg(feature = "ppmd")]
+    Ppmd(Crc32Reader<PpmdDecoder<CryptoReader<'a>>>),
+    Custom(Crc32Reader<Box<dyn Read + 'a>>),
 }
 
 impl<'a> Read for ZipFileReader<'a> {
@@ -168,6 +248,12 @@ impl<'a> Read for ZipFileReader<'a> {
             ZipFileReader::Stored(r) => r.read(buf),
             #[cfg(feature = "_deflate-any")]
             ZipFileReader::Deflated(r) => r.read(buf),
+            #[cfg(any(
+                feature = "deflate",
+                feature = "deflate-zlib",
+                feature = "deflate-zlib-ng"
+            ))]
+            ZipFileReader::DeflatedDataDescriptor(r) => r.read(buf),
             #[cfg(feature = "deflate64")]
             ZipFileReader::Deflate64(r) => r.read(buf),
             #[cfg(feature = "bzip2")]
@@ -176,6 +262,13 @@ impl<'a> Read for ZipFileReader<'a> {
             ZipFileReader::Zstd(r) => r.read(buf),
             #[cfg(feature = "lzma")]
             ZipFileReader::Lzma(r) => r.read(buf),
+            #[cfg(feature = "brotli")]
+            ZipFileReader::Brotli(r) => r.read(buf),
+            #[cfg(feature = "lz4")]
+            ZipFileReader::Lz4(r) => r.read(buf),
+            #[cfg(feature = "ppmd")]
+            ZipFileReader::Ppmd(r) => r.read(buf),
+            ZipFileReader::Custom(r) => r.read(buf),
         }
     }
 }
@@ -189,6 +282,16 @@ impl<'a> ZipFileReader<'a> {
             ZipFileReader::Stored(r) => r.into_inner().into_inner(),
             #[cfg(feature = "_deflate-any")]
             ZipFileReader::Deflated(r) => r.into_inner().into_inner().into_inner(),
+            // The end of the compressed data is only found by decompressing it
+            #[cfg(any(
+                feature = "deflate",
+                feature = "deflate-zlib",
+                feature = "deflate-zlib-ng"
+            ))]
+            ZipFileReader::DeflatedDataDescriptor(mut r) => {
+                let _ = copy(&mut r, &mut sink());
+                return;
+            }
             #[cfg(feature = "deflate64")]
             ZipFileReader::Deflate64(r) => r.into_inner().into_inner().into_inner().into_inner(),
             #[cfg(feature = "bzip2")]
@@ -204,9 +307,54 @@ impl<'a> ZipFileReader<'a> {
                 }
                 return;
             }
+            #[cfg(feature = "brotli")]
+            ZipFileReader::Brotli(r) => r.into_inner().into_inner().into_inner(),
+            #[cfg(feature = "lz4")]
+            ZipFileReader::Lz4(r) => r.into_inner().into_inner().into_inner().1.into_inner(),
+            #[cfg(feature = "ppmd")]
+            ZipFileReader::Ppmd(r) => r.into_inner().into_inner().into_inner(),
+            // A custom decoder doesn't give its reader back, so the rest of the data can only be
+            // consumed by decoding it
+            ZipFileReader::Custom(mut r) => {
+                let _ = copy(&mut r, &mut sink());
+                return;
+            }
         };
         let _ = copy(&mut inner, &mut sink());
     }
+
+    /// Stop checking the decompressed data against the stored CRC-32 once it's all been read.
+    /// Its size is still checked.
+    fn disable_crc_check(&mut self) {
+        match self {
+            ZipFileReader::NoReader | ZipFileReader::Raw(_) => (),
+            ZipFileReader::Stored(r) => r.disable_check(),
+            #[cfg(feature = "_deflate-any")]
+            ZipFileReader::Deflated(r) => r.disable_check(),
+            // The CRC-32 is only known once the data descriptor after the data has been read
+            #[cfg(any(
+                feature = "deflate",
+                feature = "deflate-zlib",
+                feature = "deflate-zlib-ng"
+            ))]
+            ZipFileReader::DeflatedDataDescriptor(_) => (),
+            #[cfg(feature = "deflate64")]
+            ZipFileReader::Deflate64(r) => r.disable_check(),
+            #[cfg(feature = "bzip2")]
+            ZipFileReader::Bzip2(r) => r.disable_check(),
+            #[cfg(feature = "zstd")]
+            ZipFileReader::Zstd(r) => r.disable_check(),
+            #[cfg(feature = "lzma")]
+            ZipFileReader::Lzma(r) => r.disable_check(),
+            #[cfg(feature = "brotli")]
+            ZipFileReader::Brotli(r) => r.disable_check(),
+            #[cfg(feature = "lz4")]
+            ZipFileReader::Lz4(r) => r.disable_check(),
+            #[cfg(feature = "ppmd")]
+            ZipFileReader::Ppmd(r) => r.disable_check(),
+            ZipFileReader::Custom(r) => r.disable_check(),
+        }
+    }
 }
 
 /// A struct for reading a zip file
@@ -214,6 +362,10 @@ pub struct ZipFile<'a> {
     pub(crate) data: Cow<'a, ZipFileData>,
     pub(crate) crypto_reader: Option<CryptoReader<'a>>,
     pub(crate) reader: ZipFileReader<'a>,
+    /// Decoder registered with [`ZipArchive::with_decoder`] for this file's compression method
+    pub(crate) custom_decoder: Option<&'a DecoderFactory>,
+    /// Limits of the archive the file was read from
+    pub(crate) limits: ArchiveLimits,
 }
 
 pub(crate) fn find_content<'a>(
@@ -255,24 +407,37 @@ pub(crate) fn find_content<'a>(
     Ok((reader as &mut dyn Read).take(data.compressed_size))
 }
 
+/// Fail early for a file whose compression method can't be decoded, before setting up decryption.
+fn check_decodable(
+    compression_method: CompressionMethod,
+    custom_decoder: Option<&DecoderFactory>,
+) -> ZipResult<()> {
+    // Whether method 95 is LZ4 rather than XZ can only be told once the data is read
+    #[cfg(feature = "lz4")]
+    if compression_method == CompressionMethod::XZ {
+        return Ok(());
+    }
+    #[allow(deprecated)]
+    if let (CompressionMethod::Unsupported(_), None) = (compression_method, custom_decoder) {
+        return unsupported_compression(compression_method);
+    }
+    Ok(())
+}
+
+/// The magic number at the start of an LZ4 frame
+#[cfg(feature = "lz4")]
+const LZ4_FRAME_MAGIC: [u8; 4] = [0x04, 0x22, 0x4D, 0x18];
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn make_crypto_reader<'a>(
-    compression_method: CompressionMethod,
     crc32: u32,
-    mut last_modified_time: Option<DateTime>,
+    last_modified_time: Option<DateTime>,
     using_data_descriptor: bool,
     reader: io::Take<&'a mut dyn Read>,
     password: Option<&[u8]>,
     aes_info: Option<(AesMode, AesVendorVersion, CompressionMethod)>,
     #[cfg(feature = "aes-crypto")] compressed_size: u64,
 ) -> ZipResult<CryptoReader<'a>> {
-    #[allow(deprecated)]
-    {
-        if let CompressionMethod::Unsupported(_) = compression_method {
-            return unsupported_zip_error("Compression method not supported");
-        }
-    }
-
     let reader = match (password, aes_info) {
         #[cfg(not(feature = "aes-crypto"))]
         (Some(_), Some(_)) => {
@@ -286,13 +451,17 @@ pub(crate) fn make_crypto_reader<'a>(
             vendor_version,
         },
         (Some(password), None) => {
-            if !using_data_descriptor {
-                last_modified_time = None;
-            }
-            let validator = if let Some(last_modified_time) = last_modified_time {
-                ZipCryptoValidator::InfoZipMsdosTime(last_modified_time.timepart())
-            } else {
-                ZipCryptoValidator::PkzipCrc32(crc32)
+            // The last byte of the encryption header is normally the high byte of the CRC-32. When
+            // the file uses a data descriptor, the CRC-32 isn't known when the header is written,
+            // so Info-ZIP uses the high byte of the MS-DOS modification time instead. Some writers
+            // use the time even without a data descriptor, so accept either in that case.
+            let validator = match (using_data_descriptor, last_modified_time) {
+                (true, Some(time)) => ZipCryptoValidator::InfoZipMsdosTime(time.timepart()),
+                (false, Some(time)) => ZipCryptoValidator::PkzipCrc32OrMsdosTime {
+                    crc32,
+                    last_mod_time: time.timepart(),
+                },
+                (_, None) => ZipCryptoValidator::PkzipCrc32(crc32),
             };
             CryptoReader::ZipCrypto(ZipCryptoReader::new(reader, password).validate(validator)?)
         }
@@ -302,68 +471,596 @@ pub(crate) fn make_crypto_reader<'a>(
     Ok(reader)
 }
 
-pub(crate) fn make_reader(
+#[cfg_attr(not(feature = "ppmd"), allow(unused_variables))]
+pub(crate) fn make_reader<'a>(
     compression_method: CompressionMethod,
     crc32: u32,
-    reader: CryptoReader,
-) -> ZipResult<ZipFileReader> {
+    uncompressed_size: Option<u64>,
+    reader: CryptoReader<'a>,
+    custom_decoder: Option<&DecoderFactory>,
+    limits: ArchiveLimits,
+) -> ZipResult<ZipFileReader<'a>> {
     let ae2_encrypted = reader.is_ae2_encrypted();
 
     match compression_method {
-        CompressionMethod::Stored => Ok(ZipFileReader::Stored(Crc32Reader::new(
-            reader,
-            crc32,
-            ae2_encrypted,
-        ))),
+        CompressionMethod::Stored => Ok(ZipFileReader::Stored(
+            Crc32Reader::new(reader, crc32, ae2_encrypted).with_expected_size(uncompressed_size),
+        )),
         #[cfg(feature = "_deflate-any")]
         CompressionMethod::Deflated => {
             let deflate_reader = DeflateDecoder::new(reader);
-            Ok(ZipFileReader::Deflated(Crc32Reader::new(
-                deflate_reader,
-                crc32,
-                ae2_encrypted,
-            )))
+            Ok(ZipFileReader::Deflated(
+                Crc32Reader::new(deflate_reader, crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
         }
         #[cfg(feature = "deflate64")]
         CompressionMethod::Deflate64 => {
             let deflate64_reader = Deflate64Decoder::new(reader);
-            Ok(ZipFileReader::Deflate64(Crc32Reader::new(
-                deflate64_reader,
-                crc32,
-                ae2_encrypted,
-            )))
+            Ok(ZipFileReader::Deflate64(
+                Crc32Reader::new(deflate64_reader, crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
         }
         #[cfg(feature = "bzip2")]
         CompressionMethod::Bzip2 => {
             let bzip2_reader = BzDecoder::new(reader);
-            Ok(ZipFileReader::Bzip2(Crc32Reader::new(
-                bzip2_reader,
-                crc32,
-                ae2_encrypted,
-            )))
+            Ok(ZipFileReader::Bzip2(
+                Crc32Reader::new(bzip2_reader, crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
         }
         #[cfg(feature = "zstd")]
         CompressionMethod::Zstd => {
+            // Not `single_frame()`: some compressors split an entry into several frames, and the
+            // decoder reads on through them until the compressed data runs out
             let zstd_reader = ZstdDecoder::new(reader).unwrap();
-            Ok(ZipFileReader::Zstd(Crc32Reader::new(
-                zstd_reader,
-                crc32,
-                ae2_encrypted,
-            )))
+            Ok(ZipFileReader::Zstd(
+                Crc32Reader::new(zstd_reader, crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
         }
         #[cfg(feature = "lzma")]
         CompressionMethod::Lzma => {
-            let reader = LzmaDecoder::new(reader);
-            Ok(ZipFileReader::Lzma(Crc32Reader::new(
-                Box::new(reader),
-                crc32,
-                ae2_encrypted,
-            )))
+            let reader = LzmaDecoder::new(reader, uncompressed_size);
+            Ok(ZipFileReader::Lzma(
+                Crc32Reader::new(Box::new(reader), crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
+        }
+        #[cfg(feature = "brotli")]
+        CompressionMethod::Brotli => {
+            let brotli_reader = BrotliDecoder::new(reader, 4096);
+            Ok(ZipFileReader::Brotli(
+                Crc32Reader::new(Box::new(brotli_reader), crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
+        }
+        // Some game engines store LZ4 frames under method 95, which APPNOTE assigns to XZ, so the
+        // data is only read as LZ4 if it starts like an LZ4 frame
+        #[cfg(feature = "lz4")]
+        CompressionMethod::XZ if custom_decoder.is_none() => {
+            let mut reader = reader;
+            let mut magic = [0u8; 4];
+            match reader.read_exact(&mut magic) {
+                Ok(()) if magic == LZ4_FRAME_MAGIC => (),
+                Err(e) if e.kind() != io::ErrorKind::UnexpectedEof => return Err(e.into()),
+                _ => return unsupported_compression(compression_method),
+            }
+            let lz4_reader = Lz4Decoder::new(io::Cursor::new(magic).chain(reader));
+            Ok(ZipFileReader::Lz4(
+                Crc32Reader::new(lz4_reader, crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
+        }
+        #[cfg(feature = "ppmd")]
+        CompressionMethod::Ppmd => {
+            let ppmd_reader = PpmdDecoder::new(reader, uncompressed_size, limits.max_ppmd_memory);
+            Ok(ZipFileReader::Ppmd(
+                Crc32Reader::new(ppmd_reader, crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            ))
+        }
+        _ => match custom_decoder {
+            Some(factory) => Ok(ZipFileReader::Custom(
+                Crc32Reader::new(factory(Box::new(reader)), crc32, ae2_encrypted)
+                    .with_expected_size(uncompressed_size),
+            )),
+            None => unsupported_compression(compression_method),
+        },
+    }
+}
+
+/// Creates a reader that decompresses a file's data, for a compression method this crate doesn't
+/// support. It's given the data after any decryption, and the CRC-32 and size of what the
+/// returned reader produces are checked as for any other file.
+///
+/// See [`ZipArchive::with_decoder`].
+pub type DecoderFactory =
+    Box<dyn for<'r> Fn(Box<dyn Read + 'r>) -> Box<dyn Read + 'r> + Send + Sync>;
+
+/// Decoders registered with [`ZipArchive::with_decoder`], by compression method
+#[derive(Clone, Default)]
+pub(crate) struct CustomDecoders(std::collections::HashMap<u16, Arc<DecoderFactory>>);
+
+impl CustomDecoders {
+    fn get(&self, method: CompressionMethod) -> Option<&DecoderFactory> {
+        self.0
+            .get(&method.serialize_to_u16())
+            .map(|factory| &**factory)
+    }
+}
+
+impl std::fmt::Debug for CustomDecoders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Configuration for reading ZIP archives.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Config {
+    /// Try to recover from some kinds of malformation that would otherwise make an archive
+    /// unreadable, rather than returning an error.
+    ///
+    /// Currently, this resynchronizes parsing of the central directory by searching for the next
+    /// central directory header when an entry can't be parsed, for example because the archive
+    /// understates the size of its central directory, and accepts an archive comment that's cut
+    /// short by the end of the file (see [`ZipArchive::comment_truncated`]).
+    pub lenient: bool,
+
+    /// The maximum number of entries the central directory may declare. Archives that declare
+    /// more are rejected before any entries are read, which bounds the time and memory spent
+    /// parsing untrusted input.
+    ///
+    /// Defaults to 2<sup>20</sup>.
+    pub max_entries: usize,
+
+    /// Read a multi-disk archive whose pieces have been concatenated, in order, into the one
+    /// reader, such as with `cat archive.z01 archive.z02 archive.zip`. Offsets in such an archive
+    /// are relative to the start of the piece they point into, so this assumes every piece but
+    /// the last has the same size, as with `zip -s`; the size is found from where the central
+    /// directory starts.
+    ///
+    /// Defaults to false, in which case archives whose central directory doesn't start on the
+    /// last disk are rejected with [`ZipError::UnsupportedArchive`], and disk numbers are
+    /// otherwise ignored.
+    pub concatenated_disks: bool,
+
+    /// Limits on how much memory the central directory and decompression may take up.
+    pub limits: ArchiveLimits,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            lenient: false,
+            max_entries: 1 << 20,
+            concatenated_disks: false,
+            limits: ArchiveLimits::default(),
+        }
+    }
+}
+
+/// Limits on the resources spent reading an archive's central directory and decompressing its
+/// entries, to bound the memory an untrusted archive can make [`ZipArchive`] allocate.
+///
+/// See [`ZipArchive::with_limits`] and [`Config::limits`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ArchiveLimits {
+    /// The maximum total size, in bytes, of every entry's name, comment and extra fields.
+    /// Each of these is limited to 64 KiB per entry by the format, but an archive with many
+    /// entries can still add up to a lot; once the total is exceeded, the archive is rejected.
+    ///
+    /// Defaults to 256 MiB.
+    pub max_metadata_size: u64,
+    /// The maximum size, in bytes, of the model a PPMd entry may ask for. Reading an entry that
+    /// needs more fails with an I/O error of kind [`io::ErrorKind::OutOfMemory`]. This is only
+    /// used with the `ppmd` feature.
+    ///
+    /// Defaults to 256 MiB, the most that a PPMd entry can ask for.
+    pub max_ppmd_memory: u32,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        ArchiveLimits {
+            max_metadata_size: 256 << 20,
+            max_ppmd_memory: 256 << 20,
+        }
+    }
+}
+
+/// The maximum number of times [`Config::lenient`] parsing will search for the next central
+/// directory header before giving up.
+const MAX_CENTRAL_DIRECTORY_RESYNCS: usize = 16;
+
+const STORED_DATA_TRUNCATED: ZipError =
+    ZipError::InvalidArchive("Stored entry data is shorter than its size");
+
+const METADATA_SIZE_EXCEEDED: ZipError = ZipError::InvalidArchive(
+    "Entry names, comments and extra fields exceed the metadata size limit",
+);
+
+/// A reason why an entry's name can't be safely used as a path when extracting.
+///
+/// See [`ZipArchive::validate_names`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NameIssue {
+    /// The name contains a NUL byte.
+    NulByte,
+    /// The name is flagged as UTF-8, but isn't valid UTF-8.
+    InvalidUtf8,
+    /// The name is an absolute path, or escapes the extraction directory.
+    UnsafePath,
+}
+
+/// Controls how [`ZipFile::safe_path_with`] turns an entry's name into a relative path.
+///
+/// The default policy is the one used by [`ZipFile::enclosed_name`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PathPolicy {
+    /// Treat `\` as a directory separator as well as `/`. Windows always does this.
+    pub backslash_as_separator: bool,
+    /// Reject names with a component that can't be a file name on Windows, such as `CON`,
+    /// `nul.txt`, `a:b` or `trailing.`.
+    pub reject_windows_reserved: bool,
+    /// Reject names with a component longer than this many bytes.
+    pub max_component_len: Option<usize>,
+}
+
+impl PathPolicy {
+    /// A policy suitable for extracting on any platform: backslashes are separators, names
+    /// Windows reserves are rejected, and components are limited to 255 bytes.
+    pub const fn portable() -> Self {
+        PathPolicy {
+            backslash_as_separator: true,
+            reject_windows_reserved: true,
+            max_component_len: Some(255),
+        }
+    }
+}
+
+/// What to do when extracting an entry whose path is the same as one already extracted, such as
+/// `a.txt` and `dir/../a.txt`.
+///
+/// See [`ExtractOptions::on_duplicate`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OnDuplicate {
+    /// Replace the earlier file, which is what [`ZipArchive::extract`] does.
+    #[default]
+    Overwrite,
+    /// Keep the earlier file, and don't extract the later entry.
+    Skip,
+    /// Extract the later entry with a numeric suffix added to its file stem, so `a.txt` becomes
+    /// `a (1).txt`, then `a (2).txt`, and so on.
+    Rename,
+    /// Stop extracting, and return an error.
+    Error,
+}
+
+/// The fields of an entry's local file header, exactly as stored.
+///
+/// See [`ZipArchive::local_header`]. The central directory holds most of the same information,
+/// and is what the rest of this crate uses; the two normally agree, except that sizes and the
+/// CRC-32 may be zero here if they're given in a data descriptor instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LocalHeader {
+    /// Minimum PKZIP version needed to extract the entry
+    pub version_needed: u16,
+    /// General purpose bit flags
+    pub flags: u16,
+    /// Compression method, as the numeric ID; see [`CompressionMethod`]
+    pub compression_method: u16,
+    /// Last modification time, in MS-DOS format
+    pub last_mod_time: u16,
+    /// Last modification date, in MS-DOS format
+    pub last_mod_date: u16,
+    /// CRC-32 of the uncompressed data
+    pub crc32: u32,
+    /// Compressed size, or `0xFFFFFFFF` if it's in the ZIP64 extra field
+    pub compressed_size: u32,
+    /// Uncompressed size, or `0xFFFFFFFF` if it's in the ZIP64 extra field
+    pub uncompressed_size: u32,
+    /// Raw file name
+    pub file_name: Vec<u8>,
+    /// Raw extra field
+    pub extra_field: Vec<u8>,
+}
+
+/// One entry's metadata in an archive's manifest.
+///
+/// See [`ZipArchive::manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ManifestEntry {
+    /// Name of the entry
+    pub name: String,
+    /// Size of the entry when extracted
+    pub uncompressed_size: u64,
+    /// Size of the entry in the archive
+    pub compressed_size: u64,
+    /// CRC-32 of the entry's uncompressed data
+    pub crc32: u32,
+    /// Compression method used to store the entry
+    pub compression: CompressionMethod,
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+}
+
+/// A key identifying an entry by its path and contents, independent of its position in the
+/// archive, so that the same file can be recognized across archives or versions of one.
+///
+/// See [`ZipFile::identity`]. Two entries with the same identity almost certainly have the same
+/// contents, but since it relies on CRC-32, this isn't guaranteed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct EntryIdentity {
+    /// Name of the entry
+    pub name: String,
+    /// CRC-32 of the entry's uncompressed data
+    pub crc32: u32,
+    /// Size of the entry when extracted
+    pub uncompressed_size: u64,
+    /// Compression method used to store the entry
+    pub compression: CompressionMethod,
+}
+
+/// A snapshot of one entry's metadata, which doesn't borrow the archive.
+///
+/// See [`ZipArchive::file_metadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ZipEntryMetadata {
+    /// Name of the entry
+    pub name: String,
+    /// Size of the entry when extracted
+    pub size: u64,
+    /// Size of the entry in the archive
+    pub compressed_size: u64,
+    /// CRC-32 of the entry's uncompressed data
+    pub crc32: u32,
+    /// Compression method used to store the entry
+    pub compression: CompressionMethod,
+    /// Last modified time, if it's valid
+    pub last_modified: Option<DateTime>,
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+    /// Unix mode, if the entry was made on Unix
+    pub unix_mode: Option<u32>,
+}
+
+/// How [`ZipArchive::extract_with_options`] extracts an archive: how entries are decrypted, which
+/// metadata is restored on the extracted files, which entries are skipped, and how progress and
+/// failures are reported.
+#[non_exhaustive]
+pub struct ExtractOptions<'a> {
+    /// Set each file's and directory's modification time to the one stored in the archive. This
+    /// comes from the extended timestamp extra field if there is one; otherwise, the MS-DOS
+    /// time, which has no time zone, is taken to be UTC. Symlinks are left alone.
+    ///
+    /// Defaults to false. Requires the `filetime` feature.
+    #[cfg(feature = "filetime")]
+    pub set_mtime: bool,
+    /// On Unix, set each file's permissions to those stored in the archive, as
+    /// [`ZipArchive::extract`] does.
+    ///
+    /// Defaults to true.
+    pub set_permissions: bool,
+    /// Skip entries that can't be read because they use a compression method or encryption
+    /// that isn't supported (or, for encrypted entries, need a password), and carry on with the
+    /// rest, instead of aborting. The skipped entries are returned.
+    ///
+    /// Defaults to false.
+    pub skip_unsupported: bool,
+    /// Fail with [`ZipError::InvalidArchive`] before extracting anything if any entry's name is
+    /// absolute: if it starts with `/` or `\`, or with a drive letter such as `C:`, on any
+    /// platform.
+    ///
+    /// Defaults to false, in which case only names that are absolute on the current platform
+    /// are rejected, when the entry is reached.
+    pub forbid_absolute: bool,
+    /// Fail with [`ZipError::InvalidArchive`] before extracting anything if any entry's name has
+    /// a `..` component, taking both `/` and `\` as separators.
+    ///
+    /// Defaults to false, in which case `..` components are resolved against the components
+    /// before them, and only names that would escape the extraction directory are rejected.
+    pub forbid_parent_refs: bool,
+    /// Fail with [`ZipError::InvalidArchive`] on any symlink whose target would be outside the
+    /// extraction directory, such as `../../etc/passwd` or an absolute path. The target isn't
+    /// checked against other symlinks, so a link to a link that escapes is caught only when that
+    /// link is extracted.
+    ///
+    /// Defaults to false.
+    pub confine_symlinks: bool,
+    /// What to do when an entry's path is the same as that of an entry extracted earlier. Only
+    /// paths extracted by the same call count; existing files are still overwritten.
+    ///
+    /// Defaults to [`OnDuplicate::Overwrite`].
+    pub on_duplicate: OnDuplicate,
+    /// Password to decrypt every encrypted entry with. Entries that aren't encrypted are
+    /// extracted as normal.
+    ///
+    /// Defaults to `None`, in which case encrypted entries fail with
+    /// [`ZipError::UnsupportedArchive`].
+    pub password: Option<&'a [u8]>,
+    /// Carry on past any entry that can't be extracted, and return it along with the error that
+    /// occurred, instead of aborting. Entries that failed part of the way through may be left
+    /// partially written on disk.
+    ///
+    /// Defaults to false.
+    pub continue_on_error: bool,
+    /// Called once each entry has been extracted, with [`ExtractProgress::finished`] set, and
+    /// also every 1 MiB while a file's contents are being written. It isn't called for entries
+    /// that fail to extract.
+    ///
+    /// Defaults to `None`.
+    pub progress: Option<&'a mut dyn FnMut(ExtractProgress<'_>)>,
+}
+
+impl Default for ExtractOptions<'_> {
+    fn default() -> Self {
+        ExtractOptions {
+            #[cfg(feature = "filetime")]
+            set_mtime: false,
+            set_permissions: true,
+            skip_unsupported: false,
+            forbid_absolute: false,
+            forbid_parent_refs: false,
+            confine_symlinks: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            password: None,
+            continue_on_error: false,
+            progress: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for ExtractOptions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ExtractOptions");
+        #[cfg(feature = "filetime")]
+        debug.field("set_mtime", &self.set_mtime);
+        debug
+            .field("set_permissions", &self.set_permissions)
+            .field("skip_unsupported", &self.skip_unsupported)
+            .field("forbid_absolute", &self.forbid_absolute)
+            .field("forbid_parent_refs", &self.forbid_parent_refs)
+            .field("confine_symlinks", &self.confine_symlinks)
+            .field("on_duplicate", &self.on_duplicate)
+            .field("password", &self.password.map(|_| "[redacted]"))
+            .field("continue_on_error", &self.continue_on_error)
+            .field("progress", &self.progress.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// How far [`ZipArchive::extract_with_options`] has got, as reported to
+/// [`ExtractOptions::progress`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ExtractProgress<'a> {
+    /// Index of the entry being extracted
+    pub file_number: usize,
+    /// Number of entries in the archive
+    pub total_files: usize,
+    /// Name of the entry being extracted
+    pub name: &'a str,
+    /// Number of bytes of the entry's contents written so far
+    pub bytes_written: u64,
+    /// Whether the entry has been completely extracted
+    pub finished: bool,
+}
+
+/// How many bytes of a file [`ZipArchive::extract_with_options`] writes between progress reports
+const PROGRESS_INTERVAL: u64 = 1 << 20;
+
+/// Writer that reports the total number of bytes written after each write.
+struct ProgressWriter<W, F> {
+    inner: W,
+    written: u64,
+    on_write: F,
+}
+
+impl<W: Write, F: FnMut(u64)> Write for ProgressWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        self.written += count as u64;
+        (self.on_write)(self.written);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader that passes each chunk it reads to a callback.
+struct TapReader<R, F> {
+    inner: R,
+    tap: F,
+}
+
+impl<R: Read, F: FnMut(&[u8])> Read for TapReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        if count > 0 {
+            (self.tap)(&buf[..count]);
         }
-        _ => Err(UnsupportedArchive("Compression method not supported")),
+        Ok(count)
     }
 }
 
+/// The parsed central directory and comment of a [`ZipArchive`], without its reader.
+///
+/// Created by [`ZipArchive::index`], and turned back into an archive by
+/// [`ZipArchive::from_parts`] with a reader over the same archive. Since reading an entry only
+/// needs its local header and data, that reader doesn't have to include the central directory,
+/// so the index and the data can be stored or transferred separately. The index is cheap to
+/// clone.
+///
+/// With the `serde` feature, the index can be serialized, so that it can be sent or stored in
+/// a different place than the data. Deserializing it doesn't check it against any archive.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArchiveIndex {
+    shared: Arc<zip_archive::Shared>,
+    comment: Arc<[u8]>,
+}
+
+/// A summary of the features a reader needs to support to read every entry in an archive.
+///
+/// See [`ZipArchive::compatibility_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompatibilityReport {
+    /// The highest PKZIP version needed to extract any entry (from APPNOTE 4.4.3.2)
+    pub version_needed: u16,
+    /// Every compression method used by some entry, in order of first use
+    pub compression_methods: Vec<CompressionMethod>,
+    /// Whether reading the archive requires ZIP64 support
+    pub zip64: bool,
+    /// Whether any entry is encrypted with the legacy ZipCrypto scheme
+    pub zip_crypto: bool,
+    /// Every AES key size used to encrypt some entry, in order of first use
+    pub aes_modes: Vec<AesMode>,
+}
+
+/// An end of central directory record found while looking for the central directory, for
+/// diagnosing archives that are misdetected.
+///
+/// See [`ZipArchive::eocd_candidates`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct EocdCandidate {
+    /// Position of the record in the reader
+    pub position: u64,
+    /// Whether this is a ZIP64 end of central directory record
+    pub zip64: bool,
+    /// Number of entries the record declares on this disk
+    pub number_of_files: u64,
+    /// Offset of the central directory, as recorded
+    pub central_directory_offset: u64,
+    /// Where the central directory actually starts, after accounting for any data prepended to
+    /// the archive, if the record is valid
+    pub directory_start: Option<u64>,
+    /// Number of this disk
+    pub disk_number: u32,
+    /// Number of the disk on which the central directory starts
+    pub disk_with_central_directory: u32,
+    /// Why the record is invalid on its own, if it is
+    pub rejection: Option<ZipError>,
+}
+
 #[derive(Debug)]
 pub(crate) struct CentralDirectoryInfo {
     pub(crate) archive_offset: u64,
@@ -379,6 +1076,7 @@ impl<R> ZipArchive<R> {
         comment: Box<[u8]>,
         reader: R,
         central_start: u64,
+        cde_start_pos: u64,
     ) -> ZipResult<Self> {
         let initial_offset = match files.first() {
             Some((_, file)) => file.header_start,
@@ -388,104 +1086,450 @@ impl<R> ZipArchive<R> {
             files,
             offset: initial_offset,
             dir_start: central_start,
+            cde_start_pos,
+            lazy: None,
+            comment_truncated: false,
         });
         Ok(Self {
             reader,
             shared,
             comment: comment.into(),
+            decoders: CustomDecoders::default(),
+            limits: ArchiveLimits::default(),
         })
     }
 
+    /// Get the parsed central directory and comment of this archive, to open the same archive
+    /// again later with [`ZipArchive::from_parts`] without parsing them again.
+    pub fn index(&self) -> ArchiveIndex {
+        ArchiveIndex {
+            shared: self.shared.clone(),
+            comment: self.comment.clone(),
+        }
+    }
+
+    /// Build an archive from an index taken from an earlier [`ZipArchive`] and a reader over
+    /// the same archive's contents.
+    ///
+    /// Offsets are the same as in the original reader, so `reader` must have the archive's local
+    /// headers and data at the same positions; anything from the start of the central directory
+    /// onwards can be left out. Nothing is read until an entry is.
+    pub fn from_parts(index: ArchiveIndex, reader: R) -> Self {
+        Self {
+            reader,
+            shared: index.shared,
+            comment: index.comment,
+            decoders: CustomDecoders::default(),
+            limits: ArchiveLimits::default(),
+        }
+    }
+
+    /// Register a decoder for files compressed with `method`, when it's a method this crate
+    /// doesn't support, such as a proprietary one. Files whose method is supported are always
+    /// decoded by the crate. Registering a decoder for the same method again replaces it.
+    ///
+    /// The decoder is used by [`ZipArchive::by_index`] and the methods built on it, but not by
+    /// [`DualReader::decompressed`].
+    pub fn with_decoder(mut self, method: u16, factory: DecoderFactory) -> Self {
+        self.decoders.0.insert(method, Arc::new(factory));
+        self
+    }
+
     /// Total size of the files in the archive, if it can be known. Doesn't include directories or
     /// metadata.
+    ///
+    /// This uses the sizes in the central directory, including for files whose local header
+    /// defers its sizes to a data descriptor; the central directory holds the real sizes in all
+    /// but badly broken archives. Use [`ZipArchive::decompressed_size_strict`] to give up on
+    /// those files instead.
     pub fn decompressed_size(&self) -> Option<u128> {
+        self.total_uncompressed_size(false)
+    }
+
+    /// Total size of the files in the archive like [`ZipArchive::decompressed_size`], but
+    /// returning `None` if any file uses a data descriptor.
+    pub fn decompressed_size_strict(&self) -> Option<u128> {
+        self.total_uncompressed_size(true)
+    }
+
+    /// Total size of the files in the archive as stored, i.e. the number of bytes of file data
+    /// that reading every file takes from the underlying reader, not counting headers.
+    ///
+    /// The central directory always holds these sizes, so unlike
+    /// [`ZipArchive::decompressed_size`], this always has a value. For an archive opened with
+    /// [`ZipArchive::new_lazy`], only the entries parsed so far are counted.
+    pub fn total_compressed_size(&self) -> u128 {
+        self.shared
+            .files
+            .values()
+            .map(|file| file.compressed_size as u128)
+            .sum()
+    }
+
+    fn total_uncompressed_size(&self, reject_data_descriptors: bool) -> Option<u128> {
+        if self.shared.lazy.is_some() {
+            return None;
+        }
         let mut total = 0u128;
         for file in self.shared.files.values() {
-            if file.using_data_descriptor {
+            if reject_data_descriptors && file.using_data_descriptor {
                 return None;
             }
             total = total.checked_add(file.uncompressed_size as u128)?;
         }
         Some(total)
     }
-}
-
-impl<R: Read + Seek> ZipArchive<R> {
-    pub(crate) fn merge_contents<W: Write + io::Seek>(
-        &mut self,
-        mut w: W,
-    ) -> ZipResult<IndexMap<Box<str>, ZipFileData>> {
-        if self.shared.files.is_empty() {
-            return Ok(IndexMap::new());
-        }
-        let mut new_files = self.shared.files.clone();
-        /* The first file header will probably start at the beginning of the file, but zip doesn't
-         * enforce that, and executable zips like PEX files will have a shebang line so will
-         * definitely be greater than 0.
-         *
-         * assert_eq!(0, new_files[0].header_start); // Avoid this.
-         */
 
-        let new_initial_header_start = w.stream_position()?;
-        /* Push back file header starts for all entries in the covered files. */
-        new_files.values_mut().try_for_each(|f| {
-            /* This is probably the only really important thing to change. */
-            f.header_start = f.header_start.checked_add(new_initial_header_start).ok_or(
-                ZipError::InvalidArchive("new header start from merge would have been too large"),
-            )?;
-            /* This is only ever used internally to cache metadata lookups (it's not part of the
-             * zip spec), and 0 is the sentinel value. */
-            f.central_header_start = 0;
-            /* This is an atomic variable so it can be updated from another thread in the
-             * implementation (which is good!). */
-            if let Some(old_data_start) = f.data_start.take() {
-                let new_data_start = old_data_start.checked_add(new_initial_header_start).ok_or(
-                    ZipError::InvalidArchive("new data start from merge would have been too large"),
-                )?;
-                f.data_start.get_or_init(|| new_data_start);
-            }
-            Ok::<_, ZipError>(())
-        })?;
+    /// Whether the archive looks like a Java archive (JAR): its first entry has the JAR marker
+    /// extra field or is in `META-INF/`, or it contains a `META-INF/MANIFEST.MF`.
+    pub fn looks_like_jar(&self) -> bool {
+        let first_is_jar = self.shared.files.first().is_some_and(|(name, file)| {
+            name.starts_with("META-INF/")
+                || file
+                    .extra_fields
+                    .iter()
+                    .any(|field| matches!(field, ExtraField::JavaAlignment(_)))
+        });
+        first_is_jar || self.shared.files.contains_key("META-INF/MANIFEST.MF")
+    }
 
-        /* Rewind to the beginning of the file.
-         *
-         * NB: we *could* decide to start copying from new_files[0].header_start instead, which
-         * would avoid copying over e.g. any pex shebangs or other file contents that start before
-         * the first zip file entry. However, zip files actually shouldn't care about garbage data
-         * in *between* real entries, since the central directory header records the correct start
-         * location of each, and keeping track of that math is more complicated logic that will only
-         * rarely be used, since most zips that get merged together are likely to be produced
-         * specifically for that purpose (and therefore are unlikely to have a shebang or other
-         * preface). Finally, this preserves any data that might actually be useful.
-         */
-        self.reader.rewind()?;
-        /* Find the end of the file data. */
-        let length_to_read = self.shared.dir_start;
-        /* Produce a Read that reads bytes up until the start of the central directory header.
-         * This "as &mut dyn Read" trick is used elsewhere to avoid having to clone the underlying
-         * handle, which it really shouldn't need to anyway. */
-        let mut limited_raw = (&mut self.reader as &mut dyn Read).take(length_to_read);
-        /* Copy over file data from source archive directly. */
-        io::copy(&mut limited_raw, &mut w)?;
+    /// Indices of the entries whose names match a shell-style glob pattern.
+    ///
+    /// `?` and `*` match any character and any sequence of characters respectively, except for
+    /// the `/` path separator; `**` also matches across `/`, so `docs/**/*.md` matches every
+    /// Markdown file under `docs`. Character classes such as `[a-z]` and `[!0-9]` are supported.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn glob<'a>(&'a self, pattern: &str) -> impl Iterator<Item = usize> + 'a {
+        let pattern = glob::Pattern::new(pattern);
+        self.shared
+            .files
+            .keys()
+            .enumerate()
+            .filter_map(move |(index, name)| pattern.matches(name).then_some(index))
+    }
 
-        /* Return the files we've just written to the data stream. */
-        Ok(new_files)
+    /// Get the highest PKZIP version needed to extract any entry, as stored in the central
+    /// directory (see [`ZipFile::version_needed`]), or `(1, 0)` if the archive is empty.
+    pub fn min_version_needed(&self) -> (u8, u8) {
+        let version = self
+            .shared
+            .files
+            .values()
+            .map(|file| file.version_needed_raw as u8)
+            .fold(MIN_VERSION, u8::max);
+        (version / 10, version % 10)
     }
 
-    fn get_directory_info_zip32(
-        footer: &spec::Zip32CentralDirectoryEnd,
-        cde_start_pos: u64,
-    ) -> ZipResult<CentralDirectoryInfo> {
-        // Some zip files have data prepended to them, resulting in the
+    /// Summarize the features needed to read every entry in the archive, such as compression
+    /// methods, ZIP64 and encryption, without reading any file data.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn compatibility_report(&self) -> CompatibilityReport {
+        let mut report = CompatibilityReport {
+            version_needed: 0,
+            compression_methods: Vec::new(),
+            zip64: self.shared.files.len() > spec::ZIP64_ENTRY_THR
+                || self.shared.dir_start > spec::ZIP64_BYTES_THR,
+            zip_crypto: false,
+            aes_modes: Vec::new(),
+        };
+        for file in self.shared.files.values() {
+            report.version_needed = report.version_needed.max(file.version_needed());
+            if !report
+                .compression_methods
+                .contains(&file.compression_method)
+            {
+                report.compression_methods.push(file.compression_method);
+            }
+            report.zip64 |= file.large_file;
+            match file.aes_mode {
+                Some((mode, _, _)) => {
+                    if !report.aes_modes.contains(&mode) {
+                        report.aes_modes.push(mode);
+                    }
+                }
+                None => report.zip_crypto |= file.encrypted,
+            }
+        }
+        report
+    }
+
+    /// Check the name of every entry without reading any file data, and report every entry whose
+    /// name can't be safely used as a path, along with the reason.
+    ///
+    /// Entries with a [`NameIssue::NulByte`] or [`NameIssue::UnsafePath`] would make
+    /// [`ZipArchive::extract`] fail; callers can use this to skip them and extract the rest.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn validate_names(&self) -> Vec<(usize, NameIssue)> {
+        self.shared
+            .files
+            .values()
+            .enumerate()
+            .filter_map(|(index, file)| {
+                let issue = if file.file_name.contains('\0') {
+                    NameIssue::NulByte
+                } else if file.file_name.contains(char::REPLACEMENT_CHARACTER)
+                    && std::str::from_utf8(&file.file_name_raw).is_err()
+                {
+                    NameIssue::InvalidUtf8
+                } else if file.enclosed_name().is_none() {
+                    NameIssue::UnsafePath
+                } else {
+                    return None;
+                };
+                Some((index, issue))
+            })
+            .collect()
+    }
+
+    /// Get a snapshot of the metadata of a file entry, by name, if it's present.
+    ///
+    /// Unlike [`ZipArchive::by_name`], this doesn't read from the archive or borrow it, so it's
+    /// cheap to call for many entries when deciding which to read.
+    pub fn file_metadata(&self, name: &str) -> Option<ZipEntryMetadata> {
+        let file = self.shared.files.get(name)?;
+        Some(ZipEntryMetadata {
+            name: file.file_name.to_string(),
+            size: file.uncompressed_size,
+            compressed_size: file.compressed_size,
+            crc32: file.crc32,
+            compression: file.compression_method,
+            last_modified: file.last_modified_time,
+            is_dir: file.is_dir(),
+            unix_mode: file.unix_mode(),
+        })
+    }
+
+    /// List the name, sizes, CRC-32 and compression method of every entry, in central directory
+    /// order.
+    ///
+    /// This only uses the metadata read when the archive was opened, so it doesn't read from the
+    /// archive at all.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn manifest(&self) -> Vec<ManifestEntry> {
+        self.shared
+            .files
+            .values()
+            .map(|file| ManifestEntry {
+                name: file.file_name.to_string(),
+                uncompressed_size: file.uncompressed_size,
+                compressed_size: file.compressed_size,
+                crc32: file.crc32,
+                compression: file.compression_method,
+                is_dir: file.is_dir(),
+            })
+            .collect()
+    }
+
+    /// Whether any entry in the archive is encrypted, and so needs a password to be read.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn has_encrypted_entries(&self) -> bool {
+        self.shared.files.values().any(|file| file.encrypted)
+    }
+
+    /// Indices of the entries in the archive that are encrypted.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn encrypted_entry_indices(&self) -> Vec<usize> {
+        self.shared
+            .files
+            .values()
+            .enumerate()
+            .filter_map(|(index, file)| file.encrypted.then_some(index))
+            .collect()
+    }
+
+    /// Get the indices of all the entries that are zero-length files (not directories), such
+    /// as the placeholder or marker files some tools use.
+    pub fn empty_entries(&self) -> Vec<usize> {
+        self.shared
+            .files
+            .values()
+            .enumerate()
+            .filter_map(|(index, file)| file.is_empty_file().then_some(index))
+            .collect()
+    }
+
+    /// Get the indices of the entries that are directories, like `foo/`.
+    ///
+    /// See [`ZipArchive::implied_directories`] for directories that files are in but that have
+    /// no entry of their own.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn explicit_directories(&self) -> Vec<usize> {
+        self.shared
+            .files
+            .values()
+            .enumerate()
+            .filter_map(|(index, file)| file.is_dir().then_some(index))
+            .collect()
+    }
+
+    /// Get the names of the directories that entries are in but that don't have an entry of
+    /// their own, in order of first appearance. Like directory entries' names, they end in `/`:
+    /// an archive with just `foo/bar/baz.txt` implies `foo/` and `foo/bar/`.
+    pub fn implied_directories(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut implied = Vec::new();
+        for name in self.shared.files.keys() {
+            let name = name.strip_suffix('/').unwrap_or(name);
+            for (separator, _) in name.match_indices('/') {
+                let directory = &name[..=separator];
+                if !self.shared.files.contains_key(directory) && seen.insert(directory) {
+                    implied.push(directory.to_string());
+                }
+            }
+        }
+        implied
+    }
+
+    /// Get the indices of all the entries that aren't directories, ordered by uncompressed size,
+    /// smallest first if `ascending` is true and largest first otherwise. Entries of equal size
+    /// stay in central directory order.
+    ///
+    /// [`ZipArchive::extract`] and the other extraction methods work in central directory order;
+    /// pass these indices to [`ZipArchive::by_index`] to process entries in order of size instead.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], this only sees the entries parsed so
+    /// far; call [`ZipArchive::parse_remaining_entries`] first to include them all.
+    pub fn indices_sorted_by_size(&self, ascending: bool) -> Vec<usize> {
+        let files = &self.shared.files;
+        let mut indices: Vec<usize> = files
+            .values()
+            .enumerate()
+            .filter_map(|(index, file)| (!file.is_dir()).then_some(index))
+            .collect();
+        if ascending {
+            indices.sort_by_key(|&index| files[index].uncompressed_size);
+        } else {
+            indices.sort_by_key(|&index| std::cmp::Reverse(files[index].uncompressed_size));
+        }
+        indices
+    }
+}
+
+impl<R: Read + Seek> ZipArchive<R> {
+    /// Copy this archive's raw bytes, from the start of the reader up to the start of the central
+    /// directory, to the current position of `w`, without decompressing anything. Returns an
+    /// index of the copied entries at their new positions in `w`.
+    ///
+    /// No central directory is written, so what's in `w` isn't a complete archive yet: the
+    /// caller has to write one, or use [`ZipWriter::merge_archive`](crate::ZipWriter::merge_archive),
+    /// which does all of this. Until then, the entries can be read by passing the index to
+    /// [`ZipArchive::from_parts`] along with a reader over what was written to `w`.
+    ///
+    /// Returns [`ZipError::InvalidArchive`] if an offset would overflow at the new position.
+    pub fn append_raw_to<W: Write + Seek>(&mut self, mut w: W) -> ZipResult<ArchiveIndex> {
+        let start = w.stream_position()?;
+        let files = self.merge_contents(&mut w)?;
+        let shift = |position: u64| {
+            position.checked_add(start).ok_or(ZipError::InvalidArchive(
+                "new central directory start from merge would have been too large",
+            ))
+        };
+        let dir_start = shift(self.shared.dir_start)?;
+        Ok(ArchiveIndex {
+            shared: Arc::new(zip_archive::Shared {
+                files,
+                offset: shift(self.shared.offset)?,
+                dir_start,
+                cde_start_pos: dir_start,
+                lazy: None,
+                comment_truncated: self.shared.comment_truncated,
+            }),
+            comment: self.comment.clone(),
+        })
+    }
+
+    pub(crate) fn merge_contents<W: Write + io::Seek>(
+        &mut self,
+        mut w: W,
+    ) -> ZipResult<IndexMap<Box<str>, ZipFileData>> {
+        self.parse_remaining_entries()?;
+        if self.shared.files.is_empty() {
+            return Ok(IndexMap::new());
+        }
+        let mut new_files = self.shared.files.clone();
+        /* The first file header will probably start at the beginning of the file, but zip doesn't
+         * enforce that, and executable zips like PEX files will have a shebang line so will
+         * definitely be greater than 0.
+         *
+         * assert_eq!(0, new_files[0].header_start); // Avoid this.
+         */
+
+        let new_initial_header_start = w.stream_position()?;
+        /* Push back file header starts for all entries in the covered files. */
+        new_files.values_mut().try_for_each(|f| {
+            /* This is probably the only really important thing to change. */
+            f.header_start = f.header_start.checked_add(new_initial_header_start).ok_or(
+                ZipError::InvalidArchive("new header start from merge would have been too large"),
+            )?;
+            /* This is only ever used internally to cache metadata lookups (it's not part of the
+             * zip spec), and 0 is the sentinel value. */
+            f.central_header_start = 0;
+            /* This is an atomic variable so it can be updated from another thread in the
+             * implementation (which is good!). */
+            if let Some(old_data_start) = f.data_start.take() {
+                let new_data_start = old_data_start.checked_add(new_initial_header_start).ok_or(
+                    ZipError::InvalidArchive("new data start from merge would have been too large"),
+                )?;
+                f.data_start.get_or_init(|| new_data_start);
+            }
+            Ok::<_, ZipError>(())
+        })?;
+
+        /* Rewind to the beginning of the file.
+         *
+         * NB: we *could* decide to start copying from new_files[0].header_start instead, which
+         * would avoid copying over e.g. any pex shebangs or other file contents that start before
+         * the first zip file entry. However, zip files actually shouldn't care about garbage data
+         * in *between* real entries, since the central directory header records the correct start
+         * location of each, and keeping track of that math is more complicated logic that will only
+         * rarely be used, since most zips that get merged together are likely to be produced
+         * specifically for that purpose (and therefore are unlikely to have a shebang or other
+         * preface). Finally, this preserves any data that might actually be useful.
+         */
+        self.reader.rewind()?;
+        /* Find the end of the file data. */
+        let length_to_read = self.shared.dir_start;
+        /* Produce a Read that reads bytes up until the start of the central directory header.
+         * This "as &mut dyn Read" trick is used elsewhere to avoid having to clone the underlying
+         * handle, which it really shouldn't need to anyway. */
+        let mut limited_raw = (&mut self.reader as &mut dyn Read).take(length_to_read);
+        /* Copy over file data from source archive directly. */
+        io::copy(&mut limited_raw, &mut w)?;
+
+        /* Return the files we've just written to the data stream. */
+        Ok(new_files)
+    }
+
+    fn get_directory_info_zip32(
+        footer: &spec::Zip32CentralDirectoryEnd,
+        cde_start_pos: u64,
+    ) -> ZipResult<CentralDirectoryInfo> {
+        // Some zip files have data prepended to them, resulting in the
         // offsets all being too small. Get the amount of error by comparing
         // the actual file position we found the CDE at with the offset
         // recorded in the CDE.
-        let archive_offset = cde_start_pos
-            .checked_sub(footer.central_directory_size as u64)
-            .and_then(|x| x.checked_sub(footer.central_directory_offset as u64))
-            .ok_or(ZipError::InvalidArchive(
-                "Invalid central directory size or offset",
-            ))?;
+        let archive_offset = spec::compute_archive_offset(
+            cde_start_pos,
+            footer.central_directory_size as u64,
+            footer.central_directory_offset as u64,
+        )
+        .ok_or(ZipError::InvalidArchive(
+            "Invalid central directory size or offset",
+        ))?;
 
         let directory_start = footer.central_directory_offset as u64 + archive_offset;
         let number_of_files = footer.number_of_files_on_this_disk as usize;
@@ -516,16 +1560,55 @@ impl<R: Read + Seek> ZipArchive<R> {
         footer: &spec::Zip32CentralDirectoryEnd,
         cde_start_pos: u64,
     ) -> ZipResult<Vec<ZipResult<CentralDirectoryInfo>>> {
+        let locator64 =
+            Self::find_zip64_locator(reader, footer)?.ok_or(spec::Zip64CDELocatorBlock::ERROR)?;
+        Ok(
+            Self::find_zip64_directory_ends(reader, &locator64, cde_start_pos)?
+                .into_iter()
+                .map(|(_, _, result)| result)
+                .collect(),
+        )
+    }
+
+    /// Find the ZIP64 end of central directory locator, returning `None` if there isn't one.
+    fn find_zip64_locator(
+        reader: &mut R,
+        footer: &spec::Zip32CentralDirectoryEnd,
+    ) -> ZipResult<Option<spec::Zip64CentralDirectoryEndLocator>> {
         // See if there's a ZIP64 footer. The ZIP64 locator if present will
         // have its signature 20 bytes in front of the standard footer. The
         // standard footer, in turn, is 22+N bytes large, where N is the
         // comment length. Therefore:
         /* TODO: compute this from constant sizes and offsets! */
-        reader.seek(io::SeekFrom::End(
-            -(20 + 22 + footer.zip_file_comment.len() as i64),
-        ))?;
-        let locator64 = spec::Zip64CentralDirectoryEndLocator::parse(reader)?;
+        let end = reader.seek(io::SeekFrom::End(0))?;
+        let Some(locator_start) = end.checked_sub(20 + 22 + footer.zip_file_comment.len() as u64)
+        else {
+            return Ok(None);
+        };
+        reader.seek(io::SeekFrom::Start(locator_start))?;
+        match spec::Zip64CentralDirectoryEndLocator::parse(reader) {
+            Ok(locator64) => Ok(Some(locator64)),
+            // Parsing only fails this way when the signature doesn't match
+            Err(ZipError::InvalidArchive(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 
+    /// Find every ZIP64 end of central directory record that `locator64` could refer to,
+    /// returning the position of each along with its fields and either the central directory it
+    /// describes or why it's invalid.
+    #[allow(clippy::type_complexity)]
+    fn find_zip64_directory_ends(
+        reader: &mut R,
+        locator64: &spec::Zip64CentralDirectoryEndLocator,
+        cde_start_pos: u64,
+    ) -> ZipResult<
+        Vec<(
+            u64,
+            spec::Zip64CentralDirectoryEnd,
+            ZipResult<CentralDirectoryInfo>,
+        )>,
+    > {
         // We need to reassess `archive_offset`. We know where the ZIP64
         // central-directory-end structure *should* be, but unfortunately we
         // don't know how to precisely relate that location to our current
@@ -547,46 +1630,64 @@ impl<R: Read + Seek> ZipArchive<R> {
         );
 
         let search_results = spec::Zip64CentralDirectoryEnd::find_and_parse(reader, lower, upper)?;
-        let results: Vec<ZipResult<CentralDirectoryInfo>> =
-            search_results.into_iter().map(|(footer64, archive_offset)| {
-                let directory_start = footer64
-                    .central_directory_offset
-                    .checked_add(archive_offset)
-                    .ok_or(ZipError::InvalidArchive(
-                        "Invalid central directory size or offset",
-                    ))?;
-                if directory_start > search_upper_bound {
-                    Err(ZipError::InvalidArchive(
-                        "Invalid central directory size or offset",
-                    ))
-                } else if footer64.number_of_files_on_this_disk > footer64.number_of_files {
-                    Err(ZipError::InvalidArchive(
-                        "ZIP64 footer indicates more files on this disk than in the whole archive",
-                    ))
-                } else if footer64.version_needed_to_extract > footer64.version_made_by {
-                    Err(ZipError::InvalidArchive(
-                        "ZIP64 footer indicates a new version is needed to extract this archive than the \
-                         version that wrote it",
-                    ))
-                } else {
-                    Ok(CentralDirectoryInfo {
-                        archive_offset,
-                        directory_start,
-                        number_of_files: footer64.number_of_files as usize,
-                        disk_number: footer64.disk_number,
-                        disk_with_central_directory: footer64.disk_with_central_directory,
-                    })
-                }
-            }).collect();
-        Ok(results)
+        Ok(search_results
+            .into_iter()
+            .map(|(footer64, archive_offset)| {
+                let result =
+                    Self::zip64_directory_info(&footer64, archive_offset, search_upper_bound);
+                (lower + archive_offset, footer64, result)
+            })
+            .collect())
+    }
+
+    /// Check a ZIP64 end of central directory record found `archive_offset` bytes after where
+    /// the locator says it should be, and get the central directory it describes.
+    fn zip64_directory_info(
+        footer64: &spec::Zip64CentralDirectoryEnd,
+        archive_offset: u64,
+        search_upper_bound: u64,
+    ) -> ZipResult<CentralDirectoryInfo> {
+        let directory_start = footer64
+            .central_directory_offset
+            .checked_add(archive_offset)
+            .ok_or(ZipError::InvalidArchive(
+                "Invalid central directory size or offset",
+            ))?;
+        if directory_start > search_upper_bound {
+            Err(ZipError::InvalidArchive(
+                "Invalid central directory size or offset",
+            ))
+        } else if footer64.number_of_files_on_this_disk > footer64.number_of_files {
+            Err(ZipError::InvalidArchive(
+                "ZIP64 footer indicates more files on this disk than in the whole archive",
+            ))
+        } else if footer64.version_needed_to_extract > footer64.version_made_by {
+            Err(ZipError::InvalidArchive(
+                "ZIP64 footer indicates a new version is needed to extract this archive than the \
+                 version that wrote it",
+            ))
+        } else {
+            Ok(CentralDirectoryInfo {
+                archive_offset,
+                directory_start,
+                number_of_files: footer64.number_of_files as usize,
+                disk_number: footer64.disk_number,
+                disk_with_central_directory: footer64.disk_with_central_directory,
+            })
+        }
     }
 
     /// Get the directory start offset and number of files. This is done in a
     /// separate function to ease the control flow design.
+    ///
+    /// `on_entry` is called with the index and name of each central directory entry as soon as it
+    /// has been parsed.
     pub(crate) fn get_metadata(
+        config: Config,
         reader: &mut R,
         footer: &spec::Zip32CentralDirectoryEnd,
         cde_start_pos: u64,
+        on_entry: &mut dyn FnMut(usize, &str),
     ) -> ZipResult<Shared> {
         // Check if file has a zip64 footer
         let mut results = Self::get_directory_info_zip64(reader, footer, cde_start_pos)
@@ -631,6 +1732,9 @@ impl<R: Read + Seek> ZipArchive<R> {
             .into_iter()
             .map(|result| {
                 result.and_then(|dir_info| {
+                    if dir_info.number_of_files > config.max_entries {
+                        return Err(ZipError::InvalidArchive("Too many entries"));
+                    }
                     // If the parsed number of files is greater than the offset then
                     // something fishy is going on and we shouldn't trust number_of_files.
                     let file_capacity =
@@ -640,20 +1744,79 @@ impl<R: Read + Seek> ZipArchive<R> {
                             dir_info.number_of_files
                         };
                     let mut files = IndexMap::with_capacity(file_capacity);
-                    reader.seek(io::SeekFrom::Start(dir_info.directory_start))?;
-                    for _ in 0..dir_info.number_of_files {
-                        let file = central_header_to_zip_file(reader, dir_info.archive_offset)?;
-                        files.insert(file.file_name.clone(), file);
+                    let mut archive_offset = dir_info.archive_offset;
+                    let mut directory_start = dir_info.directory_start;
+                    let mut resyncs = 0;
+                    let mut entries_read = 0;
+                    let mut metadata_size = 0u64;
+                    reader.seek(io::SeekFrom::Start(directory_start))?;
+                    while entries_read < dir_info.number_of_files {
+                        let header_start = reader.stream_position()?;
+                        let e = match central_header_to_zip_file(reader, archive_offset) {
+                            Ok(file) => {
+                                metadata_size += file.metadata_size();
+                                if metadata_size > config.limits.max_metadata_size {
+                                    return Err(METADATA_SIZE_EXCEEDED);
+                                }
+                                on_entry(entries_read, &file.file_name);
+                                files.insert(file.file_name.clone(), file);
+                                entries_read += 1;
+                                continue;
+                            }
+                            Err(e) => e,
+                        };
+                        if !config.lenient || resyncs == MAX_CENTRAL_DIRECTORY_RESYNCS {
+                            return Err(e);
+                        }
+                        resyncs += 1;
+                        // If the first header is bad, the archive offset (which was derived from
+                        // the declared directory size) can't be trusted either, so search from
+                        // where the directory would start if nothing were prepended to the archive.
+                        let declared_directory_start =
+                            dir_info.directory_start - dir_info.archive_offset;
+                        let search_start = if entries_read == 0 {
+                            declared_directory_start
+                        } else {
+                            header_start + 1
+                        };
+                        let Some(next_header) = spec::find_next_magic(
+                            reader,
+                            spec::Magic::CENTRAL_DIRECTORY_HEADER_SIGNATURE,
+                            search_start,
+                            cde_start_pos,
+                        )?
+                        else {
+                            return Err(e);
+                        };
+                        if entries_read == 0 {
+                            directory_start = next_header;
+                            archive_offset = next_header - declared_directory_start;
+                        }
+                        reader.seek(io::SeekFrom::Start(next_header))?;
                     }
-                    if dir_info.disk_number != dir_info.disk_with_central_directory {
-                        unsupported_zip_error("Support for multi-disk files is not implemented")
-                    } else {
-                        Ok(Shared {
-                            files,
-                            offset: dir_info.archive_offset,
-                            dir_start: dir_info.directory_start,
-                        })
+                    if config.concatenated_disks {
+                        // If the central directory starts on the first disk, so does every file
+                        if dir_info.disk_with_central_directory > 0 {
+                            Self::rebase_concatenated_disks(
+                                &mut files,
+                                archive_offset,
+                                dir_info.disk_with_central_directory,
+                            )?;
+                            archive_offset = 0;
+                        }
+                    } else if dir_info.disk_number != dir_info.disk_with_central_directory {
+                        return unsupported_zip_error(
+                            "Support for multi-disk files is not implemented",
+                        );
                     }
+                    Ok(Shared {
+                        files,
+                        offset: archive_offset,
+                        dir_start: directory_start,
+                        cde_start_pos,
+                        lazy: None,
+                        comment_truncated: footer.comment_truncated,
+                    })
                 })
             })
             .for_each(|result| match result {
@@ -677,6 +1840,35 @@ impl<R: Read + Seek> ZipArchive<R> {
         Ok(shared)
     }
 
+    /// Make each file's header offset relative to the start of the reader rather than to the start
+    /// of its disk, for [`Config::concatenated_disks`]. `disk_offset` is where the disk that the
+    /// central directory starts on begins, which mustn't be the first; the disks before it are
+    /// taken to be the same size.
+    fn rebase_concatenated_disks(
+        files: &mut IndexMap<Box<str>, ZipFileData>,
+        disk_offset: u64,
+        disk_with_central_directory: u32,
+    ) -> ZipResult<()> {
+        let disks = u64::from(disk_with_central_directory);
+        if disk_offset % disks != 0 {
+            return Err(ZipError::InvalidArchive(
+                "Pieces of a concatenated multi-disk archive have different sizes",
+            ));
+        }
+        let disk_size = disk_offset / disks;
+        for file in files.values_mut() {
+            if file.disk_start > disk_with_central_directory {
+                return Err(ZipError::InvalidArchive(
+                    "File starts on a disk after the central directory",
+                ));
+            }
+            file.header_start = (file.header_start - disk_offset)
+                .checked_add(u64::from(file.disk_start) * disk_size)
+                .ok_or(ZipError::InvalidArchive("Invalid local header offset"))?;
+        }
+        Ok(())
+    }
+
     /// Returns the verification value and salt for the AES encryption of the file
     ///
     /// It fails if the file number is invalid.
@@ -689,6 +1881,7 @@ impl<R: Read + Seek> ZipArchive<R> {
         &mut self,
         file_number: usize,
     ) -> ZipResult<Option<AesInfo>> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
         let (_, data) = self
             .shared
             .files
@@ -715,15 +1908,256 @@ impl<R: Read + Seek> ZipArchive<R> {
     /// Read a ZIP archive, collecting the files it contains
     ///
     /// This uses the central directory record of the ZIP file, and ignores local file headers
-    pub fn new(mut reader: R) -> ZipResult<ZipArchive<R>> {
-        let (footer, cde_start_pos) = spec::Zip32CentralDirectoryEnd::find_and_parse(&mut reader)?;
-        let shared = Self::get_metadata(&mut reader, &footer, cde_start_pos)?;
+    pub fn new(reader: R) -> ZipResult<ZipArchive<R>> {
+        Self::new_with_callback(reader, |_, _| {})
+    }
+
+    /// Read a ZIP archive like [`ZipArchive::new`], and check that it contains exactly
+    /// `expected` entries.
+    ///
+    /// This is a cheap integrity check for when the contents of the archive are already known,
+    /// such as from a manifest. Returns [`ZipError::InvalidArchive`] if the count differs.
+    pub fn new_expecting(reader: R, expected: usize) -> ZipResult<ZipArchive<R>> {
+        let archive = Self::new(reader)?;
+        if archive.len() != expected {
+            return Err(ZipError::InvalidArchive(
+                "Number of entries differs from the expected count",
+            ));
+        }
+        Ok(archive)
+    }
+
+    /// Read a ZIP archive like [`ZipArchive::new`], calling `on_entry` with the index and name of
+    /// each central directory entry as it is parsed.
+    ///
+    /// This is intended for progress reporting while opening archives with very many entries. The
+    /// callback can't abort parsing. If the archive has both a ZIP64 and a ZIP32 end of central
+    /// directory record and the first one tried turns out to be invalid, the indices will start
+    /// again from 0 when the other is parsed.
+    pub fn new_with_callback<F: FnMut(usize, &str)>(
+        reader: R,
+        mut on_entry: F,
+    ) -> ZipResult<ZipArchive<R>> {
+        Self::with_config_and_callback(Config::default(), reader, &mut on_entry)
+    }
+
+    /// Read a ZIP archive like [`ZipArchive::new`], rejecting it with
+    /// [`ZipError::InvalidArchive`] if its central directory exceeds the given limits. The
+    /// limits on decompression apply when entries are read.
+    pub fn with_limits(limits: ArchiveLimits, reader: R) -> ZipResult<ZipArchive<R>> {
+        let config = Config {
+            limits,
+            ..Config::default()
+        };
+        Self::with_config(config, reader)
+    }
+
+    /// Read a ZIP archive like [`ZipArchive::new`], using the given configuration.
+    pub fn with_config(config: Config, reader: R) -> ZipResult<ZipArchive<R>> {
+        Self::with_config_and_callback(config, reader, &mut |_, _| {})
+    }
+
+    /// Read a ZIP archive like [`ZipArchive::new`], but without parsing the central directory
+    /// up front.
+    ///
+    /// Only the end of central directory record is read here. Entries are parsed in order, and
+    /// cached, the first time [`ZipArchive::by_index`] or [`ZipArchive::by_name`] (or one of
+    /// their variants) needs them, so opening an archive with very many entries is fast and
+    /// uses little memory if only a few near the start of the central directory are read.
+    /// However, looking up the last entry, or a name that isn't in the archive, parses the whole
+    /// directory just as [`ZipArchive::new`] would.
+    ///
+    /// [`ZipArchive::len`] counts every entry, but other methods that take `&self`, such as
+    /// [`ZipArchive::file_names`] and [`ZipArchive::index_for_name`], only see the entries
+    /// parsed so far; call [`ZipArchive::parse_remaining_entries`] first if you need
+    /// them all.
+    ///
+    /// Unlike [`ZipArchive::new`], which keeps the last of several entries with the same name,
+    /// this fails with [`ZipError::InvalidArchive`] once a duplicate name is parsed.
+    ///
+    /// The default [`Config`] limits apply, with the entry count checked here and the metadata
+    /// size checked as entries are parsed; see [`ZipArchive::new_lazy_with_config`].
+    pub fn new_lazy(reader: R) -> ZipResult<ZipArchive<R>> {
+        Self::new_lazy_with_config(Config::default(), reader)
+    }
+
+    /// Read a ZIP archive like [`ZipArchive::new_lazy`], using the given configuration.
+    ///
+    /// [`Config::lenient`] and [`Config::concatenated_disks`] aren't supported in this mode, and
+    /// make this fail with [`ZipError::UnsupportedArchive`].
+    pub fn new_lazy_with_config(config: Config, mut reader: R) -> ZipResult<ZipArchive<R>> {
+        if config.lenient || config.concatenated_disks {
+            return unsupported_zip_error(
+                "Lenient and concatenated-disk parsing aren't supported for lazy reading",
+            );
+        }
+        let (footer, cde_start_pos) =
+            spec::Zip32CentralDirectoryEnd::find_and_parse(&mut reader, false)?;
+        let zip32_result = Self::get_directory_info_zip32(&footer, cde_start_pos);
+        let zip64_dir_info = Self::get_directory_info_zip64(&mut reader, &footer, cde_start_pos)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|dir_info| match &zip32_result {
+                Ok(zip32_dir_info) => {
+                    zip32_dir_info.number_of_files == u16::MAX as usize
+                        || zip32_dir_info.number_of_files == dir_info.number_of_files
+                }
+                Err(_) => true,
+            })
+            .max_by_key(|dir_info| dir_info.directory_start);
+        let dir_info = match zip64_dir_info {
+            Some(dir_info) => dir_info,
+            None => zip32_result?,
+        };
+        if dir_info.disk_number != dir_info.disk_with_central_directory {
+            return unsupported_zip_error("Support for multi-disk files is not implemented");
+        }
+        if dir_info.number_of_files > config.max_entries {
+            return Err(ZipError::InvalidArchive("Too many entries"));
+        }
+        let shared = Shared {
+            files: IndexMap::new(),
+            offset: dir_info.archive_offset,
+            dir_start: dir_info.directory_start,
+            cde_start_pos,
+            lazy: (dir_info.number_of_files > 0).then_some(LazyDirectory {
+                next_header: dir_info.directory_start,
+                remaining: dir_info.number_of_files,
+                metadata_size: 0,
+                max_metadata_size: config.limits.max_metadata_size,
+            }),
+            comment_truncated: false,
+        };
+        Ok(ZipArchive {
+            reader,
+            shared: shared.into(),
+            comment: footer.zip_file_comment.into(),
+            decoders: CustomDecoders::default(),
+            limits: config.limits,
+        })
+    }
+
+    /// Parse every central directory entry that hasn't been parsed yet, for an archive opened
+    /// with [`ZipArchive::new_lazy`]. Does nothing for an archive that's been fully parsed.
+    pub fn parse_remaining_entries(&mut self) -> ZipResult<()> {
+        self.parse_lazy_entries_until(|_| false)
+    }
+
+    /// Parse lazily-read central directory entries until `done` returns true for the entries
+    /// parsed so far, or none are left.
+    fn parse_lazy_entries_until<F: Fn(&IndexMap<Box<str>, ZipFileData>) -> bool>(
+        &mut self,
+        done: F,
+    ) -> ZipResult<()> {
+        while let Some(lazy) = self.shared.lazy {
+            if done(&self.shared.files) {
+                break;
+            }
+            self.reader.seek(io::SeekFrom::Start(lazy.next_header))?;
+            let file = central_header_to_zip_file(&mut self.reader, self.shared.offset)?;
+            let next_header = self.reader.stream_position()?;
+            let metadata_size = lazy.metadata_size + file.metadata_size();
+            if metadata_size > lazy.max_metadata_size {
+                return Err(METADATA_SIZE_EXCEEDED);
+            }
+            // The eager parser keeps the last of several entries with the same name, but here that
+            // would leave `len()` counting entries that can never be reached by index
+            if self.shared.files.contains_key(&file.file_name) {
+                return Err(ZipError::InvalidArchive(
+                    "Duplicate entry name in a lazily read archive",
+                ));
+            }
+            let shared = Arc::make_mut(&mut self.shared);
+            shared.files.insert(file.file_name.clone(), file);
+            shared.lazy = (lazy.remaining > 1).then_some(LazyDirectory {
+                next_header,
+                remaining: lazy.remaining - 1,
+                metadata_size,
+                ..lazy
+            });
+        }
+        Ok(())
+    }
+
+    fn with_config_and_callback(
+        config: Config,
+        mut reader: R,
+        on_entry: &mut dyn FnMut(usize, &str),
+    ) -> ZipResult<ZipArchive<R>> {
+        let (footer, cde_start_pos) =
+            spec::Zip32CentralDirectoryEnd::find_and_parse(&mut reader, config.lenient)?;
+        let shared = Self::get_metadata(config, &mut reader, &footer, cde_start_pos, on_entry)?;
         Ok(ZipArchive {
             reader,
             shared: shared.into(),
             comment: footer.zip_file_comment.into(),
+            decoders: CustomDecoders::default(),
+            limits: config.limits,
         })
     }
+
+    /// List every end of central directory record found in `reader`, as considered when opening
+    /// an archive: the ZIP32 record nearest the end, followed by any ZIP64 records, of which
+    /// there can be several when the archive comment contains their signature.
+    ///
+    /// If there's a ZIP64 locator but no valid ZIP64 record can be found from it, a ZIP64
+    /// candidate is included anyway at the position the locator gives, with the reason in
+    /// [`EocdCandidate::rejection`] and the fields the record would have provided left as 0.
+    ///
+    /// This is for diagnosing archives that open wrongly or not at all. The records are checked
+    /// individually, but not against each other; when opening an archive, a ZIP64 record must
+    /// also agree with the ZIP32 one, and of the records left, the one whose central directory
+    /// starts last is used.
+    pub fn eocd_candidates(reader: &mut R) -> ZipResult<Vec<EocdCandidate>> {
+        let (footer, cde_start_pos) =
+            spec::Zip32CentralDirectoryEnd::find_and_parse(reader, false)?;
+        let zip32_result = Self::get_directory_info_zip32(&footer, cde_start_pos);
+        let mut candidates = vec![EocdCandidate {
+            position: cde_start_pos,
+            zip64: false,
+            number_of_files: footer.number_of_files_on_this_disk.into(),
+            central_directory_offset: footer.central_directory_offset.into(),
+            directory_start: zip32_result.as_ref().ok().map(|info| info.directory_start),
+            disk_number: footer.disk_number.into(),
+            disk_with_central_directory: footer.disk_with_central_directory.into(),
+            rejection: zip32_result.err(),
+        }];
+        let Some(locator64) = Self::find_zip64_locator(reader, &footer)? else {
+            return Ok(candidates);
+        };
+        let zip64_ends = match Self::find_zip64_directory_ends(reader, &locator64, cde_start_pos) {
+            Ok(zip64_ends) => zip64_ends,
+            Err(ZipError::Io(e)) => return Err(e.into()),
+            Err(e) => {
+                candidates.push(EocdCandidate {
+                    position: locator64.end_of_central_directory_offset,
+                    zip64: true,
+                    number_of_files: 0,
+                    central_directory_offset: 0,
+                    directory_start: None,
+                    disk_number: 0,
+                    disk_with_central_directory: locator64.disk_with_central_directory,
+                    rejection: Some(e),
+                });
+                return Ok(candidates);
+            }
+        };
+        candidates.extend(zip64_ends.into_iter().map(|(position, footer64, result)| {
+            EocdCandidate {
+                position,
+                zip64: true,
+                number_of_files: footer64.number_of_files_on_this_disk,
+                central_directory_offset: footer64.central_directory_offset,
+                directory_start: result.as_ref().ok().map(|info| info.directory_start),
+                disk_number: footer64.disk_number,
+                disk_with_central_directory: footer64.disk_with_central_directory,
+                rejection: result.err(),
+            }
+        }));
+        Ok(candidates)
+    }
+
     /// Extract a Zip archive into a directory, overwriting files if they
     /// already exist. Paths are sanitized with [`ZipFile::enclosed_name`].
     ///
@@ -736,89 +2170,397 @@ impl<R: Read + Seek> ZipArchive<R> {
     /// WebAssembly, symbolic links aren't supported, so they're extracted as normal files
     /// containing the target path in UTF-8.
     pub fn extract<P: AsRef<Path>>(&mut self, directory: P) -> ZipResult<()> {
-        use std::fs;
-        #[cfg(unix)]
-        let mut files_by_unix_mode = Vec::new();
-        for i in 0..self.len() {
-            let mut file = self.by_index(i)?;
-            let filepath = file
-                .enclosed_name()
-                .ok_or(ZipError::InvalidArchive("Invalid file path"))?;
-
-            let outpath = directory.as_ref().join(filepath);
+        self.extract_with_options(directory, ExtractOptions::default())?;
+        Ok(())
+    }
 
-            if file.is_dir() {
-                Self::make_writable_dir_all(&outpath)?;
-                continue;
-            }
-            let symlink_target = if file.is_symlink() && (cfg!(unix) || cfg!(windows)) {
-                let mut target = Vec::with_capacity(file.size() as usize);
-                file.read_exact(&mut target)?;
-                Some(target)
-            } else {
-                None
-            };
-            drop(file);
-            if let Some(p) = outpath.parent() {
-                Self::make_writable_dir_all(p)?;
+    /// Extract a Zip archive into a directory like [`ZipArchive::extract`], with `options`
+    /// controlling how entries are decrypted, which metadata is restored, which entries are
+    /// skipped, and how progress and failures are reported.
+    ///
+    /// Returns the index of each entry skipped because of [`ExtractOptions::skip_unsupported`]
+    /// or [`ExtractOptions::continue_on_error`], along with the error it caused. This is always
+    /// empty if neither option is set.
+    pub fn extract_with_options<P: AsRef<Path>>(
+        &mut self,
+        directory: P,
+        mut options: ExtractOptions<'_>,
+    ) -> ZipResult<Vec<(usize, ZipError)>> {
+        let directory = directory.as_ref();
+        if options.forbid_absolute || options.forbid_parent_refs {
+            self.parse_remaining_entries()?;
+            for data in self.shared.files.values() {
+                check_strict_name(&data.file_name, &options)?;
+            }
+        }
+        let mut progress = options.progress.take();
+        let mut progress = |report: ExtractProgress<'_>| {
+            if let Some(progress) = progress.as_mut() {
+                progress(report);
+            }
+        };
+        let mut failures = Vec::new();
+        let mut on_error = |file_number, err| match err {
+            ZipError::UnsupportedArchive(_) | ZipError::UnsupportedCompression { .. }
+                if options.skip_unsupported =>
+            {
+                failures.push((file_number, err));
+                Ok(())
+            }
+            err if options.continue_on_error => {
+                failures.push((file_number, err));
+                Ok(())
             }
-            if let Some(target) = symlink_target {
+            err => Err(err),
+        };
+        #[cfg(unix)]
+        let mut files_by_unix_mode = Vec::new();
+        #[cfg(feature = "filetime")]
+        let mut dir_mtimes = Vec::new();
+        let mut extracted = HashSet::new();
+        let total_files = self.len();
+        for file_number in 0..total_files {
+            let mut bytes_written = 0;
+            let mut last_reported = 0;
+            let result = self.extract_entry(
+                directory,
+                file_number,
+                &options,
+                &mut extracted,
+                &mut |name, written| {
+                    bytes_written = written;
+                    if written - last_reported >= PROGRESS_INTERVAL {
+                        last_reported = written;
+                        progress(ExtractProgress {
+                            file_number,
+                            total_files,
+                            name,
+                            bytes_written,
+                            finished: false,
+                        });
+                    }
+                },
+            );
+            match result {
                 #[cfg(unix)]
-                {
-                    use std::os::unix::ffi::OsStringExt;
-                    let target = OsString::from_vec(target);
-                    let target_path = directory.as_ref().join(target);
-                    std::os::unix::fs::symlink(target_path, outpath.as_path())?;
+                Ok(Some((path, mode))) if options.set_permissions => {
+                    files_by_unix_mode.push((path, mode, file_number))
                 }
-                #[cfg(windows)]
-                {
-                    let Ok(target) = String::from_utf8(target) else {
-                        return Err(ZipError::InvalidArchive("Invalid UTF-8 as symlink target"));
-                    };
-                    let target = target.into_boxed_str();
-                    let target_is_dir_from_archive =
-                        self.shared.files.contains_key(&target) && is_dir(&target);
-                    let target_path = directory.as_ref().join(OsString::from(target.to_string()));
-                    let target_is_dir = if target_is_dir_from_archive {
-                        true
-                    } else if let Ok(meta) = std::fs::metadata(&target_path) {
-                        meta.is_dir()
-                    } else {
-                        false
-                    };
-                    if target_is_dir {
-                        std::os::windows::fs::symlink_dir(target_path, outpath.as_path())?;
-                    } else {
-                        std::os::windows::fs::symlink_file(target_path, outpath.as_path())?;
-                    }
+                Ok(_) => (),
+                Err(err) => {
+                    on_error(file_number, err)?;
+                    continue;
                 }
-                continue;
             }
-            let mut file = self.by_index(i)?;
-            let mut outfile = fs::File::create(&outpath)?;
-            io::copy(&mut file, &mut outfile)?;
-            #[cfg(unix)]
-            {
-                // Check for real permissions, which we'll set in a second pass
-                if let Some(mode) = file.unix_mode() {
-                    files_by_unix_mode.push((outpath.clone(), mode));
+            #[cfg(feature = "filetime")]
+            if options.set_mtime {
+                let (_, data) = self.shared.files.get_index(file_number).unwrap();
+                if data.is_dir() {
+                    if let (Some(path), Some(mtime)) = (data.enclosed_name(), entry_mtime(data)) {
+                        dir_mtimes.push((directory.join(path), mtime, file_number));
+                    }
                 }
             }
+            progress(ExtractProgress {
+                file_number,
+                total_files,
+                name: self.name_for_index(file_number).unwrap_or_default(),
+                bytes_written,
+                finished: true,
+            });
         }
         #[cfg(unix)]
         {
             use std::cmp::Reverse;
+            use std::fs;
             use std::os::unix::fs::PermissionsExt;
 
             if files_by_unix_mode.len() > 1 {
                 // Ensure we update children's permissions before making a parent unwritable
-                files_by_unix_mode.sort_by_key(|(path, _)| Reverse(path.clone()));
+                files_by_unix_mode.sort_by_key(|(path, _, _)| Reverse(path.clone()));
             }
-            for (path, mode) in files_by_unix_mode.into_iter() {
-                fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+            for (path, mode, file_number) in files_by_unix_mode.into_iter() {
+                if let Err(err) = fs::set_permissions(&path, fs::Permissions::from_mode(mode)) {
+                    on_error(file_number, err.into())?;
+                }
             }
         }
-        Ok(())
+        // Writing a directory's contents updates its modification time, so do these last, and
+        // children before their parents
+        #[cfg(feature = "filetime")]
+        {
+            dir_mtimes.sort_by(|(a, _, _), (b, _, _)| b.cmp(a));
+            for (path, mtime, file_number) in dir_mtimes {
+                if let Err(err) = filetime::set_file_mtime(&path, mtime) {
+                    on_error(file_number, err.into())?;
+                }
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Extract a single entry's contents to the file `dest`, resuming an earlier extraction that
+    /// was interrupted after `already_written` bytes. Returns the total number of bytes in `dest`
+    /// afterwards, which is the entry's size on success.
+    ///
+    /// `dest` is created if it doesn't exist, and any bytes in it past `already_written` are
+    /// discarded. For unencrypted [`Stored`](CompressionMethod::Stored) entries, reading seeks
+    /// straight to where it left off, but the CRC-32 isn't checked because only part of the data
+    /// is read. Any other entry has to be decompressed from the start, so the first
+    /// `already_written` bytes are decompressed and thrown away.
+    pub fn extract_entry_resumable(
+        &mut self,
+        file_number: usize,
+        dest: &Path,
+        already_written: u64,
+    ) -> ZipResult<u64> {
+        use std::fs;
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
+        let (_, data) = self
+            .shared
+            .files
+            .get_index(file_number)
+            .ok_or(ZipError::FileNotFound)?;
+        if already_written > data.uncompressed_size {
+            return Err(ZipError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "already_written is past the end of the entry",
+            )));
+        }
+        let mut outfile = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dest)?;
+        if outfile.metadata()?.len() < already_written {
+            return Err(ZipError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Destination is shorter than already_written",
+            )));
+        }
+        outfile.set_len(already_written)?;
+        outfile.seek(io::SeekFrom::End(0))?;
+        if let Some((mut stored, expected)) =
+            self.stored_entry_reader_at(file_number, already_written)?
+        {
+            if io::copy(&mut stored, &mut outfile)? != expected {
+                return Err(STORED_DATA_TRUNCATED);
+            }
+        } else {
+            let mut file = self.by_index(file_number)?;
+            io::copy(&mut (&mut file).take(already_written), &mut sink())?;
+            io::copy(&mut file, &mut outfile)?;
+        }
+        Ok(outfile.stream_position()?)
+    }
+
+    /// Read an entry's decompressed data starting `offset` bytes in, filling as much of `buf` as
+    /// possible. Returns the number of bytes read, which is only less than `buf.len()` if the end
+    /// of the entry was reached.
+    ///
+    /// For unencrypted [`Stored`](CompressionMethod::Stored) entries, this seeks straight to
+    /// `offset`, so it takes the same time wherever the offset is; the CRC-32 isn't checked,
+    /// because only part of the data is read. Compressed data can't be read from the middle,
+    /// so for any other entry the first `offset` bytes are decompressed and thrown away each
+    /// time this is called, which takes time proportional to `offset`. To read a compressed
+    /// entry sequentially, use [`ZipArchive::by_index`] instead.
+    pub fn read_entry_at(
+        &mut self,
+        file_number: usize,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> ZipResult<usize> {
+        let mut bytes_read = 0;
+        if let Some((mut stored, expected)) = self.stored_entry_reader_at(file_number, offset)? {
+            while bytes_read < buf.len() {
+                match stored.read(&mut buf[bytes_read..])? {
+                    0 => break,
+                    n => bytes_read += n,
+                }
+            }
+            if bytes_read < buf.len() && bytes_read as u64 != expected {
+                return Err(STORED_DATA_TRUNCATED);
+            }
+        } else {
+            let mut file = self.by_index(file_number)?;
+            io::copy(&mut (&mut file).take(offset), &mut sink())?;
+            while bytes_read < buf.len() {
+                match file.read(&mut buf[bytes_read..])? {
+                    0 => break,
+                    n => bytes_read += n,
+                }
+            }
+        }
+        Ok(bytes_read)
+    }
+
+    /// If the given entry is unencrypted and [`Stored`](CompressionMethod::Stored), seek to
+    /// `offset` bytes into its data and return a reader over the rest of it, along with the
+    /// number of bytes that reader should yield. It yields fewer if the archive is truncated.
+    fn stored_entry_reader_at(
+        &mut self,
+        file_number: usize,
+        offset: u64,
+    ) -> ZipResult<Option<(io::Take<&mut R>, u64)>> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
+        let (_, data) = self
+            .shared
+            .files
+            .get_index(file_number)
+            .ok_or(ZipError::FileNotFound)?;
+        if data.compression_method != CompressionMethod::Stored || data.encrypted {
+            return Ok(None);
+        }
+        find_content(data, &mut self.reader)?;
+        let data_start = *data.data_start.get().unwrap();
+        let expected = data.uncompressed_size.saturating_sub(offset);
+        let offset = offset.min(data.compressed_size);
+        let remaining = data.compressed_size - offset;
+        self.reader.seek(io::SeekFrom::Start(data_start + offset))?;
+        Ok(Some(((&mut self.reader).take(remaining), expected)))
+    }
+
+    /// Extract a single entry into `directory`, returning the path and mode to set on it once
+    /// every entry has been extracted. `extracted` holds the normalized paths of the files and
+    /// directories extracted so far, for detecting duplicates. `on_write` is called with the entry's name and
+    /// the number of bytes written so far each time some of its contents are written.
+    fn extract_entry(
+        &mut self,
+        directory: &Path,
+        file_number: usize,
+        options: &ExtractOptions<'_>,
+        extracted: &mut HashSet<PathBuf>,
+        on_write: &mut dyn FnMut(&str, u64),
+    ) -> ZipResult<Option<(PathBuf, u32)>> {
+        use std::fs;
+        let mut file = self.by_index_with_optional_password(file_number, options.password)?;
+        // A directory entry such as `./` only names the extraction directory, which exists already
+        if file.is_dir() && file.data.names_root() {
+            return Ok(None);
+        }
+        let filepath = file
+            .enclosed_name()
+            .ok_or(ZipError::InvalidArchive("Invalid file path"))?;
+
+        let mut outpath = directory.join(&filepath);
+        // enclosed_name() guarantees this never pops past the start
+        let mut normalized = filepath
+            .components()
+            .fold(PathBuf::new(), |mut path, component| {
+                match component {
+                    Component::ParentDir => {
+                        path.pop();
+                    }
+                    Component::CurDir => (),
+                    component => path.push(component),
+                }
+                path
+            });
+
+        if file.is_dir() {
+            Self::make_writable_dir_all(&outpath)?;
+            extracted.insert(normalized);
+            return Ok(None);
+        }
+        if extracted.contains(&normalized) {
+            match options.on_duplicate {
+                OnDuplicate::Overwrite => (),
+                OnDuplicate::Skip => return Ok(None),
+                OnDuplicate::Error => {
+                    return Err(ZipError::InvalidArchive("Duplicate file path"));
+                }
+                OnDuplicate::Rename => {
+                    let stem = normalized.file_stem().unwrap_or_default().to_owned();
+                    let extension = normalized.extension().map(|ext| ext.to_owned());
+                    let renamed = (1u64..)
+                        .map(|n| {
+                            let mut name = stem.clone();
+                            name.push(format!(" ({n})"));
+                            if let Some(extension) = &extension {
+                                name.push(".");
+                                name.push(extension);
+                            }
+                            normalized.with_file_name(name)
+                        })
+                        .find(|renamed| !extracted.contains(renamed))
+                        .unwrap();
+                    outpath = directory.join(&renamed);
+                    normalized = renamed;
+                }
+            }
+        }
+        let symlink_target = if file.is_symlink() && (cfg!(unix) || cfg!(windows)) {
+            let mut target = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut target)?;
+            if options.confine_symlinks && symlink_target_escapes(&target) {
+                return Err(ZipError::InvalidArchive(
+                    "Symlink target is outside the extraction directory",
+                ));
+            }
+            Some(target)
+        } else {
+            None
+        };
+        drop(file);
+        if let Some(p) = outpath.parent() {
+            Self::make_writable_dir_all(p)?;
+        }
+        if let Some(target) = symlink_target {
+            #[cfg(unix)]
+            {
+                use std::os::unix::ffi::OsStringExt;
+                let target = OsString::from_vec(target);
+                let target_path = directory.join(target);
+                std::os::unix::fs::symlink(target_path, outpath.as_path())?;
+            }
+            #[cfg(windows)]
+            {
+                let Ok(target) = String::from_utf8(target) else {
+                    return Err(ZipError::InvalidArchive("Invalid UTF-8 as symlink target"));
+                };
+                let target = target.into_boxed_str();
+                let target_is_dir_from_archive =
+                    self.shared.files.contains_key(&target) && is_dir(&target);
+                let target_path = directory.join(OsString::from(target.to_string()));
+                let target_is_dir = if target_is_dir_from_archive {
+                    true
+                } else if let Ok(meta) = std::fs::metadata(&target_path) {
+                    meta.is_dir()
+                } else {
+                    false
+                };
+                if target_is_dir {
+                    std::os::windows::fs::symlink_dir(target_path, outpath.as_path())?;
+                } else {
+                    std::os::windows::fs::symlink_file(target_path, outpath.as_path())?;
+                }
+            }
+            extracted.insert(normalized);
+            return Ok(None);
+        }
+        let mut file = self.by_index_with_optional_password(file_number, options.password)?;
+        // Fail on an unsupported compression method before creating the file
+        file.get_reader()?;
+        let name = file.name().to_owned();
+        let mut outfile = ProgressWriter {
+            inner: fs::File::create(&outpath)?,
+            written: 0,
+            on_write: |written| on_write(&name, written),
+        };
+        io::copy(&mut file, &mut outfile)?;
+        extracted.insert(normalized);
+        #[cfg(feature = "filetime")]
+        if options.set_mtime {
+            if let Some(mtime) = entry_mtime(&file.data) {
+                filetime::set_file_handle_times(&outfile.inner, None, Some(mtime))?;
+            }
+        }
+        // Check for real permissions, which we'll set in a second pass
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            return Ok(Some((outpath, mode)));
+        }
+        Ok(None)
     }
 
     fn make_writable_dir_all<T: AsRef<Path>>(outpath: T) -> Result<(), ZipError> {
@@ -839,7 +2581,7 @@ impl<R: Read + Seek> ZipArchive<R> {
 
     /// Number of files contained in this zip.
     pub fn len(&self) -> usize {
-        self.shared.files.len()
+        self.shared.files.len() + self.shared.lazy.map_or(0, |lazy| lazy.remaining)
     }
 
     /// Whether this zip archive contains no files
@@ -855,16 +2597,52 @@ impl<R: Read + Seek> ZipArchive<R> {
         self.shared.offset
     }
 
+    /// Get the number of bytes of other data before the start of the zip, such as a
+    /// self-extractor stub. This is the same as [`ZipArchive::offset`].
+    pub fn prepended_bytes(&self) -> u64 {
+        self.shared.offset
+    }
+
+    /// Get the position in the underlying reader of the (ZIP32) end of central directory record.
+    ///
+    /// Any bytes after the record and its comment were appended to the archive.
+    pub fn eocd_position(&self) -> u64 {
+        self.shared.cde_start_pos
+    }
+
     /// Get the comment of the zip archive.
     pub fn comment(&self) -> &[u8] {
         &self.comment
     }
 
+    /// Whether the file ended before the archive comment did, which usually means the end of the
+    /// archive is missing, for example because a download was interrupted.
+    ///
+    /// This can only be true for archives opened with [`Config::lenient`] set; otherwise, a
+    /// truncated comment is an error. [`ZipArchive::comment`] holds the part of the comment
+    /// that was present.
+    pub fn comment_truncated(&self) -> bool {
+        self.shared.comment_truncated
+    }
+
+    /// Get a reader over the comment of the zip archive, for feeding it to streaming parsers.
+    pub fn comment_reader(&self) -> impl Read + '_ {
+        self.comment()
+    }
+
     /// Returns an iterator over all the file and directory names in this archive.
     pub fn file_names(&self) -> impl Iterator<Item = &str> {
         self.shared.files.keys().map(|s| s.as_ref())
     }
 
+    /// Returns an iterator over the file and directory names in this archive that match a
+    /// shell-style glob pattern, using the same syntax as [`ZipArchive::glob`]. For example,
+    /// `src/**/*.rs` matches every Rust file anywhere under `src`.
+    pub fn file_names_matching<'a>(&'a self, pattern: &str) -> impl Iterator<Item = &'a str> {
+        let pattern = glob::Pattern::new(pattern);
+        self.file_names().filter(move |name| pattern.matches(name))
+    }
+
     /// Search for a file entry by name, decrypt with given password
     ///
     /// # Warning
@@ -878,12 +2656,12 @@ impl<R: Read + Seek> ZipArchive<R> {
     /// There are many passwords out there that will also pass the validity checks
     /// we are able to perform. This is a weakness of the ZipCrypto algorithm,
     /// due to its fairly primitive approach to cryptography.
-    pub fn by_name_decrypt(&mut self, name: &str, password: &[u8]) -> ZipResult<ZipFile> {
+    pub fn by_name_decrypt(&mut self, name: &str, password: &[u8]) -> ZipResult<ZipFile<'_>> {
         self.by_name_with_optional_password(name, Some(password))
     }
 
     /// Search for a file entry by name
-    pub fn by_name(&mut self, name: &str) -> ZipResult<ZipFile> {
+    pub fn by_name(&mut self, name: &str) -> ZipResult<ZipFile<'_>> {
         self.by_name_with_optional_password(name, None)
     }
 
@@ -893,6 +2671,19 @@ impl<R: Read + Seek> ZipArchive<R> {
         self.shared.files.get_index_of(name)
     }
 
+    /// Get the index of a file entry by name, ignoring case, if it's present.
+    ///
+    /// Names are compared using Unicode lowercase mappings, so `"README.TXT"` matches
+    /// `"readme.txt"`. If several entries have names that differ only in case, the first in
+    /// central directory order is returned.
+    pub fn index_for_name_ignore_case(&self, name: &str) -> Option<usize> {
+        let name = name.to_lowercase();
+        self.shared
+            .files
+            .keys()
+            .position(|candidate| candidate.to_lowercase() == name)
+    }
+
     /// Get the index of a file entry by path, if it's present.
     #[inline(always)]
     pub fn index_for_path<T: AsRef<Path>>(&self, path: T) -> Option<usize> {
@@ -908,11 +2699,57 @@ impl<R: Read + Seek> ZipArchive<R> {
             .map(|(name, _)| name.as_ref())
     }
 
+    /// Search for a file entry by name after normalizing it the way names are normalized when
+    /// writing with [`ZipWriter::start_file_from_path`](crate::ZipWriter::start_file_from_path):
+    /// `\` and `/` are both treated as separators, and `.` and `..` components and leading
+    /// separators are resolved or removed. So `a\b.txt` and `./a/b.txt` both find `a/b.txt`.
+    ///
+    /// [`ZipArchive::by_name`] only finds names that match exactly.
+    pub fn by_name_normalized(&mut self, name: &str) -> ZipResult<ZipFile<'_>> {
+        let name = Self::normalize_name(name);
+        self.by_name_with_optional_password(&name, None)
+    }
+
+    /// Get the index of a file entry by name, if it's present, after normalizing the name as
+    /// [`ZipArchive::by_name_normalized`] does.
+    pub fn index_for_name_normalized(&self, name: &str) -> Option<usize> {
+        self.index_for_name(&Self::normalize_name(name))
+    }
+
+    fn normalize_name(name: &str) -> Box<str> {
+        let name = name.replace('\\', "/");
+        let normalized = path_to_string(Path::new(&name));
+        match name.ends_with('/') && !normalized.ends_with('/') {
+            // Keep directories' trailing separator
+            true => format!("{normalized}/").into(),
+            false => normalized,
+        }
+    }
+
+    /// Search for a file entry by name, ignoring case. See
+    /// [`ZipArchive::index_for_name_ignore_case`] for how names are compared and which entry is
+    /// returned when several match.
+    pub fn by_name_ignore_case(&mut self, name: &str) -> ZipResult<ZipFile<'_>> {
+        if self.index_for_name_ignore_case(name).is_none() {
+            let lowercase = name.to_lowercase();
+            self.parse_lazy_entries_until(|files| {
+                files
+                    .last()
+                    .is_some_and(|(candidate, _)| candidate.to_lowercase() == lowercase)
+            })?;
+        }
+        let Some(index) = self.index_for_name_ignore_case(name) else {
+            return Err(ZipError::FileNotFound);
+        };
+        self.by_index(index)
+    }
+
     fn by_name_with_optional_password<'a>(
         &'a mut self,
         name: &str,
         password: Option<&[u8]>,
     ) -> ZipResult<ZipFile<'a>> {
+        self.parse_lazy_entries_until(|files| files.contains_key(name))?;
         let Some(index) = self.shared.files.get_index_of(name) else {
             return Err(ZipError::FileNotFound);
         };
@@ -947,6 +2784,7 @@ impl<R: Read + Seek> ZipArchive<R> {
 
     /// Get a contained file by index without decompressing it
     pub fn by_index_raw(&mut self, file_number: usize) -> ZipResult<ZipFile<'_>> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
         let reader = &mut self.reader;
         let (_, data) = self
             .shared
@@ -956,578 +2794,4649 @@ impl<R: Read + Seek> ZipArchive<R> {
         Ok(ZipFile {
             crypto_reader: None,
             reader: ZipFileReader::Raw(find_content(data, reader)?),
+            custom_decoder: self.decoders.get(data.compression_method),
+            limits: self.limits,
             data: Cow::Borrowed(data),
         })
     }
 
-    fn by_index_with_optional_password(
-        &mut self,
-        file_number: usize,
-        mut password: Option<&[u8]>,
-    ) -> ZipResult<ZipFile<'_>> {
+    /// Get a reader that can seek within a contained file, by index. Only files stored without
+    /// compression or encryption are supported, since their data can be read from any position
+    /// directly; for any other file, this returns [`ZipError::UnsupportedArchive`].
+    pub fn by_index_seekable(&mut self, file_number: usize) -> ZipResult<SeekableZipFile<'_, R>> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
         let (_, data) = self
             .shared
             .files
             .get_index(file_number)
             .ok_or(ZipError::FileNotFound)?;
-
-        match (password, data.encrypted) {
-            (None, true) => return Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)),
-            (Some(_), false) => password = None, //Password supplied, but none needed! Discard.
-            _ => {}
+        if data.compression_method != CompressionMethod::Stored || data.encrypted {
+            return Err(ZipError::UnsupportedArchive(
+                "Only files stored without compression or encryption can be seeked within",
+            ));
         }
-        let limit_reader = find_content(data, &mut self.reader)?;
-
-        let crypto_reader = make_crypto_reader(
-            data.compression_method,
-            data.crc32,
-            data.last_modified_time,
-            data.using_data_descriptor,
-            limit_reader,
-            password,
-            data.aes_mode,
-            #[cfg(feature = "aes-crypto")]
+        find_content(data, &mut self.reader)?;
+        let data_start = *data.data_start.get().unwrap();
+        Ok(SeekableZipFile::new(
+            &mut self.reader,
+            data_start,
             data.compressed_size,
-        )?;
-        Ok(ZipFile {
-            crypto_reader: Some(crypto_reader),
-            reader: ZipFileReader::NoReader,
-            data: Cow::Borrowed(data),
+        ))
+    }
+
+    /// Read the compressed data of a contained file, by index, into memory, so it can be both
+    /// copied as is and decompressed from a single read of the archive.
+    ///
+    /// The whole compressed file is buffered, so this needs as much memory as its
+    /// [`ZipFile::compressed_size`].
+    pub fn by_index_dual(&mut self, file_number: usize) -> ZipResult<DualReader> {
+        let mut file = self.by_index_raw(file_number)?;
+        let mut compressed = Vec::new();
+        file.read_to_end(&mut compressed)?;
+        if compressed.len() as u64 != file.compressed_size() {
+            return Err(ZipError::InvalidArchive("Compressed data is truncated"));
+        }
+        Ok(DualReader {
+            data: file.data.as_ref().clone(),
+            compressed: io::Cursor::new(compressed),
         })
     }
 
-    /// Unwrap and return the inner reader object
+    /// Decompress a contained file, by index, and discard its contents, verifying its CRC-32.
     ///
-    /// The position of the reader is undefined.
-    pub fn into_inner(self) -> R {
-        self.reader
+    /// Directories always succeed without any I/O. Encrypted files can't be tested this way, since
+    /// no password is supplied.
+    pub fn test_entry(&mut self, file_number: usize) -> ZipResult<()> {
+        let mut file = self.by_index(file_number)?;
+        if file.is_dir() {
+            return Ok(());
+        }
+        io::copy(&mut file, &mut sink())?;
+        Ok(())
     }
-}
 
-/// Holds the AES information of a file in the zip archive
-#[derive(Debug)]
-#[cfg(feature = "aes-crypto")]
-pub struct AesInfo {
-    /// The AES encryption mode
-    pub aes_mode: AesMode,
-    /// The verification key
-    pub verification_value: [u8; PWD_VERIFY_LENGTH],
-    /// The salt
-    pub salt: Vec<u8>,
-}
+    /// Returns an iterator that decompresses each file in this archive into memory in turn,
+    /// yielding its name and contents. Directories are skipped.
+    ///
+    /// Any file larger than `max_size` bytes when decompressed yields an error instead of its
+    /// contents, without reading more than `max_size + 1` bytes of it. Encrypted files also yield
+    /// an error, since no password is supplied. Iteration continues with the next file after an
+    /// error.
+    pub fn entry_contents(&mut self, max_size: u64) -> EntryContents<'_, R> {
+        EntryContents {
+            archive: self,
+            index: 0,
+            max_size,
+        }
+    }
 
-const fn unsupported_zip_error<T>(detail: &'static str) -> ZipResult<T> {
-    Err(ZipError::UnsupportedArchive(detail))
-}
+    /// Returns a cursor over the files in this archive, in central directory order, for use
+    /// instead of calling [`ZipArchive::by_index`] with each index from `0..len()`.
+    ///
+    /// Each [`ZipFile`] borrows the archive's reader mutably, so this can't be an [`Iterator`]:
+    /// call [`Entries::next_entry`] in a loop, and drop each entry before getting the next one.
+    pub fn entries(&mut self) -> Entries<'_, R> {
+        Entries {
+            archive: self,
+            index: 0,
+            password: None,
+        }
+    }
 
-/// Parse a central directory entry to collect the information for the file.
-pub(crate) fn central_header_to_zip_file<R: Read + Seek>(
-    reader: &mut R,
-    archive_offset: u64,
-) -> ZipResult<ZipFileData> {
-    let central_header_start = reader.stream_position()?;
+    /// Like [`ZipArchive::entries`], but decrypting every encrypted entry with the given
+    /// password. Entries that aren't encrypted are read as normal.
+    pub fn entries_decrypt<'a>(&'a mut self, password: &'a [u8]) -> Entries<'a, R> {
+        Entries {
+            archive: self,
+            index: 0,
+            password: Some(password),
+        }
+    }
 
-    // Parse central header
-    let block = ZipCentralEntryBlock::parse(reader)?;
-    central_header_to_zip_file_inner(reader, archive_offset, central_header_start, block)
-}
+    /// Read the raw file name from the local file header of a contained file, by index.
+    ///
+    /// This normally matches [`ZipFile::name_raw`], which is read from the central directory. A
+    /// mismatch indicates a malformed archive, or one crafted to show a different name to tools
+    /// that only read local headers.
+    pub fn local_name(&mut self, file_number: usize) -> ZipResult<Vec<u8>> {
+        Ok(self.local_header(file_number)?.file_name)
+    }
 
-#[inline]
-fn read_variable_length_byte_field<R: Read>(reader: &mut R, len: usize) -> io::Result<Box<[u8]>> {
-    let mut data = vec![0; len].into_boxed_slice();
-    reader.read_exact(&mut data)?;
-    Ok(data)
-}
+    /// Read the raw extra field from the local file header of a contained file, by index.
+    ///
+    /// This often differs from [`ZipFile::extra_data`], which is read from the central directory;
+    /// for example, it may contain ZIP64 sizes or alignment padding.
+    pub fn local_extra_bytes(&mut self, file_number: usize) -> ZipResult<Vec<u8>> {
+        Ok(self.local_header(file_number)?.extra_field)
+    }
 
-/// Parse a central directory entry to collect the information for the file.
-fn central_header_to_zip_file_inner<R: Read>(
-    reader: &mut R,
-    archive_offset: u64,
-    central_header_start: u64,
-    block: ZipCentralEntryBlock,
-) -> ZipResult<ZipFileData> {
-    let ZipCentralEntryBlock {
-        // magic,
-        version_made_by,
-        // version_to_extract,
-        flags,
-        compression_method,
-        last_mod_time,
-        last_mod_date,
-        crc32,
-        compressed_size,
-        uncompressed_size,
-        file_name_length,
-        extra_field_length,
-        file_comment_length,
-        // disk_number,
-        // internal_file_attributes,
-        external_file_attributes,
-        offset,
-        ..
-    } = block;
+    /// Read the extended timestamp (extra field `0x5455`) of a contained file, by index, from both
+    /// its local file header and its central directory record.
+    ///
+    /// The central directory copy usually only has the modification time, while the local copy
+    /// may also have the access and creation times. Each time is taken from the local copy if
+    /// it's there and from the central copy otherwise, so the two copies can have different
+    /// flags. Returns `None` if neither copy exists.
+    pub fn extended_timestamp(
+        &mut self,
+        file_number: usize,
+    ) -> ZipResult<Option<ExtendedTimestamp>> {
+        let local_extra = self.local_extra_bytes(file_number)?;
+        let local = ExtendedTimestamp::find_in_extra_field(&local_extra)?;
+        let central = self.shared.files[file_number]
+            .extra_fields
+            .iter()
+            .find_map(|field| match field {
+                ExtraField::ExtendedTimestamp(timestamp) => Some(timestamp.clone()),
+                _ => None,
+            });
+        Ok(match (local, central) {
+            (Some(local), Some(central)) => Some(local.or(central)),
+            (local, central) => local.or(central),
+        })
+    }
 
-    let encrypted = flags & 1 == 1;
-    let is_utf8 = flags & (1 << 11) != 0;
-    let using_data_descriptor = flags & (1 << 3) != 0;
+    /// Copy the whole underlying reader, from its start to its end, to `out`, returning the
+    /// number of bytes copied.
+    ///
+    /// This copies the archive byte-for-byte, including any data before it (such as a
+    /// self-extractor stub; see [`ZipArchive::offset`]) or after it. To copy just the entries
+    /// into another archive, use [`ZipWriter::merge_archive`](crate::ZipWriter::merge_archive)
+    /// instead.
+    pub fn copy_verbatim<W: Write>(&mut self, out: &mut W) -> ZipResult<u64> {
+        self.reader.rewind()?;
+        Ok(io::copy(&mut self.reader, out)?)
+    }
 
-    let file_name_raw = read_variable_length_byte_field(reader, file_name_length as usize)?;
-    let extra_field = read_variable_length_byte_field(reader, extra_field_length as usize)?;
-    let file_comment_raw = read_variable_length_byte_field(reader, file_comment_length as usize)?;
+    /// Decompress a contained file, by name, into `out`, returning the number of bytes written.
+    ///
+    /// The CRC-32 is checked as when reading the file with [`ZipArchive::by_name`].
+    pub fn extract_file_to<W: Write>(&mut self, name: &str, out: &mut W) -> ZipResult<u64> {
+        let mut file = self.by_name(name)?;
+        Ok(io::copy(&mut file, out)?)
+    }
+
+    /// Decrypt and decompress a contained file, by name, into `out`, returning the number of bytes
+    /// written. See [`ZipArchive::by_name_decrypt`] for the caveats about passwords.
+    pub fn extract_file_to_decrypt<W: Write>(
+        &mut self,
+        name: &str,
+        password: &[u8],
+        out: &mut W,
+    ) -> ZipResult<u64> {
+        let mut file = self.by_name_decrypt(name, password)?;
+        Ok(io::copy(&mut file, out)?)
+    }
+
+    /// Decompress a contained file, by index, into `out`, and check that exactly `expected_len`
+    /// bytes were written.
+    ///
+    /// The CRC-32 and size stored in the archive are checked as usual, but `expected_len` comes
+    /// from outside the archive, such as a trusted manifest, so this also catches data that was
+    /// altered along with its stored size. Returns [`ZipError::InvalidArchive`] if the length
+    /// differs. Whatever was decompressed has already been written to `out` by then.
+    pub fn extract_entry_checked<W: Write>(
+        &mut self,
+        file_number: usize,
+        out: &mut W,
+        expected_len: u64,
+    ) -> ZipResult<()> {
+        let mut file = self.by_index(file_number)?;
+        let written = io::copy(&mut file, out)?;
+        if written != expected_len {
+            return Err(ZipError::InvalidArchive(
+                "Decompressed length differs from the expected length",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Decompress a contained file, by index, into `out`, passing each chunk of decompressed data
+    /// to `tap` before it's written, such as to scan or index the contents as they go by without
+    /// buffering the whole file. Returns the number of bytes written.
+    ///
+    /// The chunks are passed on as they're read, so `tap` sees the data before the CRC-32 is
+    /// checked at the end; if this returns an error, whatever `tap` has seen may be corrupt.
+    pub fn extract_entry_tapped<W: Write, F: FnMut(&[u8])>(
+        &mut self,
+        file_number: usize,
+        out: &mut W,
+        tap: F,
+    ) -> ZipResult<u64> {
+        let file = self.by_index(file_number)?;
+        let mut reader = TapReader { inner: file, tap };
+        Ok(io::copy(&mut reader, out)?)
+    }
+
+    /// Decompress a contained file, by index, and check whether its CRC-32 is `expected_crc`.
+    ///
+    /// Unlike the check made while reading a [`ZipFile`], this compares against a value from
+    /// outside the archive, such as a trusted manifest, so it also catches data that was altered
+    /// along with its stored CRC-32. The stored CRC-32 is ignored: if only it was altered, this
+    /// still returns `true`. Encrypted files aren't supported, since no password is supplied.
+    pub fn verify_entry_crc(&mut self, file_number: usize, expected_crc: u32) -> ZipResult<bool> {
+        let mut file = self.by_index(file_number)?;
+        Ok(file.read_rest_unchecked(|_| ())? == expected_crc)
+    }
+
+    /// Read bytes from the underlying reader starting at `offset`, filling as much of `buf` as
+    /// possible. Returns the number of bytes read, which is only less than `buf.len()` if the end
+    /// of the reader was reached.
+    ///
+    /// `offset` is a position in the underlying reader, not relative to the start of the
+    /// archive, so it can be taken straight from [`ZipFile::header_start`],
+    /// [`ZipFile::data_start`] or [`ZipFile::central_header_start`]. This is for reading parts
+    /// of the file that the rest of this API doesn't model; the archive can still be used as
+    /// normal afterwards.
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> ZipResult<usize> {
+        self.reader.seek(io::SeekFrom::Start(offset))?;
+        let mut bytes_read = 0;
+        while bytes_read < buf.len() {
+            match self.reader.read(&mut buf[bytes_read..]) {
+                Ok(0) => break,
+                Ok(n) => bytes_read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(bytes_read)
+    }
+
+    /// Read and parse the local file header of a contained file, by index.
+    pub fn local_header(&mut self, file_number: usize) -> ZipResult<LocalHeader> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
+        let (_, data) = self
+            .shared
+            .files
+            .get_index(file_number)
+            .ok_or(ZipError::FileNotFound)?;
+        self.reader.seek(io::SeekFrom::Start(data.header_start))?;
+        let block = ZipLocalEntryBlock::parse(&mut self.reader)?;
+        let mut file_name = vec![0; block.file_name_length as usize];
+        self.reader.read_exact(&mut file_name)?;
+        let mut extra_field = vec![0; block.extra_field_length as usize];
+        self.reader.read_exact(&mut extra_field)?;
+        Ok(LocalHeader {
+            version_needed: block.version_made_by,
+            flags: block.flags,
+            compression_method: block.compression_method,
+            last_mod_time: block.last_mod_time,
+            last_mod_date: block.last_mod_date,
+            crc32: block.crc32,
+            compressed_size: block.compressed_size,
+            uncompressed_size: block.uncompressed_size,
+            file_name,
+            extra_field,
+        })
+    }
+
+    /// Check that every entry lies entirely before the central directory, as it must in a
+    /// well-formed archive.
+    ///
+    /// Entries whose local header or data overlap or follow the central directory are a strong
+    /// sign of a malformed or tampered-with archive. This reads the local header of every entry.
+    pub fn check_entry_bounds(&mut self) -> ZipResult<()> {
+        self.parse_remaining_entries()?;
+        let dir_start = self.shared.dir_start;
+        for data in self.shared.files.values() {
+            if data.header_start >= dir_start {
+                return Err(ZipError::InvalidArchive(
+                    "Local file header is not before the central directory",
+                ));
+            }
+            find_content(data, &mut self.reader)?;
+            let data_end = data
+                .data_start
+                .get()
+                .and_then(|data_start| data_start.checked_add(data.compressed_size));
+            if !data_end.is_some_and(|data_end| data_end <= dir_start) {
+                return Err(ZipError::InvalidArchive(
+                    "File data overlaps the central directory",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// List the entries whose local header and compressed data lie entirely within the first
+    /// `available_bytes` bytes of the reader, such as when only part of the archive has been
+    /// downloaded. These entries can be read even though the rest of the archive is missing.
+    ///
+    /// This reads the local header of each entry that starts within the available bytes, to find
+    /// where its data starts; that position is cached, so calling this again as more bytes become
+    /// available only reads headers it hasn't read before.
+    pub fn entries_within(&mut self, available_bytes: u64) -> ZipResult<Vec<usize>> {
+        self.parse_remaining_entries()?;
+        let mut indices = Vec::new();
+        for (index, data) in self.shared.files.values().enumerate() {
+            let header_end = data
+                .header_start
+                .saturating_add(mem::size_of::<ZipLocalEntryBlock>() as u64);
+            if header_end > available_bytes {
+                continue;
+            }
+            // The reader may not hold the rest of a header that's cut off by the end of the
+            // available bytes yet
+            match find_content(data, &mut self.reader) {
+                Ok(_) => (),
+                Err(ZipError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => continue,
+                Err(e) => return Err(e),
+            }
+            let data_end = data
+                .data_start
+                .get()
+                .and_then(|data_start| data_start.checked_add(data.compressed_size));
+            if data_end.is_some_and(|data_end| data_end <= available_bytes) {
+                indices.push(index);
+            }
+        }
+        Ok(indices)
+    }
+
+    /// Measure how many bytes of compressed data actually follow the local header of a contained
+    /// file, by index, regardless of its declared [`ZipFile::compressed_size`].
+    ///
+    /// Deflate and bzip2 data is measured by decompressing it until the compressed stream ends.
+    /// Data using other compression methods, or encryption, is measured up to the next local
+    /// header, central directory header or data descriptor signature. A result that differs from
+    /// [`ZipFile::compressed_size`] indicates truncated or padded data.
+    pub fn measure_compressed_size(&mut self, file_number: usize) -> ZipResult<u64> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
+        let (_, data) = self
+            .shared
+            .files
+            .get_index(file_number)
+            .ok_or(ZipError::FileNotFound)?;
+        find_content(data, &mut self.reader)?;
+        let data_start = data.data_start.get().copied().unwrap_or_default();
+        let end = if data_start <= self.shared.dir_start {
+            self.shared.dir_start
+        } else {
+            self.reader.seek(io::SeekFrom::End(0))?
+        };
+        let available = end.saturating_sub(data_start);
+
+        if !data.encrypted {
+            self.reader.seek(io::SeekFrom::Start(data_start))?;
+            let stream = &mut io::BufReader::new((&mut self.reader).take(available));
+            let decoded: Option<io::Result<u64>> = match data.compression_method {
+                #[cfg(any(
+                    feature = "deflate",
+                    feature = "deflate-zlib",
+                    feature = "deflate-zlib-ng"
+                ))]
+                CompressionMethod::Deflated => Some(io::copy(
+                    &mut flate2::bufread::DeflateDecoder::new(&mut *stream),
+                    &mut sink(),
+                )),
+                #[cfg(feature = "bzip2")]
+                CompressionMethod::Bzip2 => Some(io::copy(
+                    &mut bzip2::bufread::BzDecoder::new(&mut *stream),
+                    &mut sink(),
+                )),
+                _ => None,
+            };
+            if let Some(decoded) = decoded {
+                decoded?;
+                // Only count what the decoder consumed, not what's still buffered
+                let unread = stream.get_ref().limit() + stream.buffer().len() as u64;
+                return Ok(available - unread);
+            }
+        }
+
+        let mut size = available;
+        for magic in [
+            spec::Magic::LOCAL_FILE_HEADER_SIGNATURE,
+            spec::Magic::CENTRAL_DIRECTORY_HEADER_SIGNATURE,
+            spec::Magic::DATA_DESCRIPTOR_SIGNATURE,
+        ] {
+            if let Some(pos) = spec::find_next_magic(&mut self.reader, magic, data_start, end)? {
+                size = size.min(pos - data_start);
+            }
+        }
+        Ok(size)
+    }
+
+    /// Read the block size of a contained bzip2 file, by index, from the header of its
+    /// compressed data: between 100,000 and 900,000 bytes, which is how much memory (times a
+    /// small factor) a decoder needs for it.
+    ///
+    /// Returns `None` if the file isn't compressed with bzip2, or is encrypted so that its
+    /// header can't be read without decrypting it.
+    pub fn bzip2_block_size(&mut self, file_number: usize) -> ZipResult<Option<u32>> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
+        let (_, data) = self
+            .shared
+            .files
+            .get_index(file_number)
+            .ok_or(ZipError::FileNotFound)?;
+        if data.compression_method != CompressionMethod::BZIP2 || data.encrypted {
+            return Ok(None);
+        }
+        let mut header = [0u8; 4];
+        find_content(data, &mut self.reader)?.read_exact(&mut header)?;
+        match header {
+            [b'B', b'Z', b'h', level @ b'1'..=b'9'] => Ok(Some(u32::from(level - b'0') * 100_000)),
+            _ => Err(ZipError::InvalidArchive("Invalid bzip2 stream header")),
+        }
+    }
+
+    /// Read the raw bytes of the end-of-central-directory record, including the archive comment
+    /// and anything after it.
+    ///
+    /// For ZIP64 archives, this starts at the ZIP64 end-of-central-directory record, and so also
+    /// includes the ZIP64 end-of-central-directory locator.
+    pub fn eocd_bytes(&mut self) -> ZipResult<Vec<u8>> {
+        let cde_start_pos = self.shared.cde_start_pos;
+        let mut start = cde_start_pos;
+        if let Some(locator_start) =
+            cde_start_pos.checked_sub(mem::size_of::<spec::Zip64CDELocatorBlock>() as u64)
+        {
+            self.reader.seek(io::SeekFrom::Start(locator_start))?;
+            let mut magic = [0; mem::size_of::<spec::Magic>()];
+            self.reader.read_exact(&mut magic)?;
+            if spec::Magic::from_le_bytes(magic)
+                == spec::Magic::ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE
+            {
+                self.reader.seek(io::SeekFrom::Start(locator_start))?;
+                let locator = spec::Zip64CentralDirectoryEndLocator::parse(&mut self.reader)?;
+                start = locator
+                    .end_of_central_directory_offset
+                    .checked_add(self.shared.offset)
+                    .filter(|&start| start <= locator_start)
+                    .ok_or(ZipError::InvalidArchive(
+                        "Invalid ZIP64 end of central directory offset",
+                    ))?;
+            }
+        }
+        self.reader.seek(io::SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        self.reader.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn by_index_with_optional_password(
+        &mut self,
+        file_number: usize,
+        mut password: Option<&[u8]>,
+    ) -> ZipResult<ZipFile<'_>> {
+        self.parse_lazy_entries_until(|files| files.len() > file_number)?;
+        let (_, data) = self
+            .shared
+            .files
+            .get_index(file_number)
+            .ok_or(ZipError::FileNotFound)?;
+
+        match (password, data.encrypted) {
+            (None, true) => return Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)),
+            (Some(_), false) => password = None, //Password supplied, but none needed! Discard.
+            _ => {}
+        }
+        let custom_decoder = self.decoders.get(data.compression_method);
+        check_decodable(data.compression_method, custom_decoder)?;
+        let limit_reader = find_content(data, &mut self.reader)?;
+
+        let crypto_reader = make_crypto_reader(
+            data.crc32,
+            data.last_modified_time,
+            data.using_data_descriptor,
+            limit_reader,
+            password,
+            data.aes_mode,
+            #[cfg(feature = "aes-crypto")]
+            data.compressed_size,
+        )?;
+        let file = ZipFile {
+            crypto_reader: Some(crypto_reader),
+            reader: ZipFileReader::NoReader,
+            custom_decoder,
+            limits: self.limits,
+            data: Cow::Borrowed(data),
+        };
+        // Tell LZ4 from XZ now, so that XZ fails here like any other unsupported method
+        #[cfg(feature = "lz4")]
+        let file = {
+            let mut file = file;
+            if file.data.compression_method == CompressionMethod::XZ {
+                file.get_reader()?;
+            }
+            file
+        };
+        Ok(file)
+    }
+
+    /// Unwrap and return the inner reader object
+    ///
+    /// The position of the reader is undefined.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<R: Read + Seek> ZipArchive<R> {
+    /// Hash the names and decompressed contents of the files in the archive, so that archives
+    /// holding the same files hash the same regardless of how they were compressed, the order
+    /// of their entries or their other metadata.
+    ///
+    /// Directories are skipped. The other entries are taken in order of name, comparing the
+    /// UTF-8 bytes of the names, and for each one the following are fed to the digest:
+    ///
+    /// 1. the length of the name in bytes, as a little-endian `u64`
+    /// 2. the name, in UTF-8
+    /// 3. the length of the contents in bytes, as a little-endian `u64`
+    /// 4. the contents
+    ///
+    /// Encrypted files are an error, since no password is supplied.
+    pub fn content_digest<D: digest::Digest>(&mut self) -> ZipResult<digest::Output<D>> {
+        self.parse_remaining_entries()?;
+        let mut indices: Vec<usize> = (0..self.shared.files.len())
+            .filter(|&index| !self.shared.files[index].is_dir())
+            .collect();
+        indices.sort_by(|&a, &b| {
+            let (a, _) = self.shared.files.get_index(a).unwrap();
+            let (b, _) = self.shared.files.get_index(b).unwrap();
+            a.as_bytes().cmp(b.as_bytes())
+        });
+
+        let mut digest = D::new();
+        for index in indices {
+            let mut file = self.by_index(index)?;
+            digest.update((file.name().len() as u64).to_le_bytes());
+            digest.update(file.name().as_bytes());
+            digest.update(file.size().to_le_bytes());
+            if file.read_rest_unchecked(|data| digest.update(data))? != file.crc32() {
+                return Err(ZipError::InvalidArchive("Invalid checksum"));
+            }
+        }
+        Ok(digest.finalize())
+    }
+
+    /// Decompress a contained file, by index, and check whether its hash is `expected`, like
+    /// [`ZipArchive::verify_entry_crc`] but with any hash function implementing
+    /// [`digest::Digest`], such as one listed in a trusted manifest. The stored CRC-32 is
+    /// ignored. Encrypted files aren't supported, since no password is supplied.
+    pub fn verify_entry_digest<D: digest::Digest>(
+        &mut self,
+        file_number: usize,
+        expected: &[u8],
+    ) -> ZipResult<bool> {
+        let mut file = self.by_index(file_number)?;
+        let mut digest = D::new();
+        file.read_rest_unchecked(|data| digest.update(data))?;
+        Ok(digest.finalize().as_slice() == expected)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<R: Read + Seek + Clone + Send + Sync> ZipArchive<R> {
+    /// Read and decompress every file in the archive concurrently, using a clone of the reader
+    /// for each file, and return their contents by name. Directories are skipped.
+    ///
+    /// Nothing is read if any file is encrypted, or if the total size the files declare is larger
+    /// than `max_total_size`. A file that decompresses to more than its declared size is also an
+    /// error, so no more than `max_total_size` bytes of contents are ever held in memory.
+    ///
+    /// On an archive opened with [`ZipArchive::new_lazy`], the rest of the central directory is
+    /// parsed first.
+    pub fn read_all_parallel(
+        &mut self,
+        max_total_size: u64,
+    ) -> ZipResult<std::collections::HashMap<String, Vec<u8>>> {
+        use rayon::prelude::*;
+
+        self.parse_remaining_entries()?;
+        let this = &*self;
+        let mut total_size: u64 = 0;
+        for file in self.shared.files.values() {
+            if file.encrypted {
+                return unsupported_zip_error(ZipError::PASSWORD_REQUIRED);
+            }
+            if !file.is_dir() {
+                total_size = total_size.saturating_add(file.uncompressed_size);
+            }
+        }
+        if total_size > max_total_size {
+            return Err(ZipError::InvalidArchive(
+                "Total size of the files is larger than the size limit",
+            ));
+        }
+        (0..this.len())
+            .into_par_iter()
+            .filter(|&file_number| !this.shared.files[file_number].is_dir())
+            .map(|file_number| {
+                let mut archive = this.clone();
+                let mut file = archive.by_index(file_number)?;
+                let size = file.size();
+                let mut contents = Vec::with_capacity(size as usize);
+                (&mut file).take(size + 1).read_to_end(&mut contents)?;
+                if contents.len() as u64 > size {
+                    return Err(ZipError::InvalidArchive(
+                        "File is larger than its declared size",
+                    ));
+                }
+                Ok((file.name().to_string(), contents))
+            })
+            .collect()
+    }
+}
+
+/// Iterator over the decompressed contents of the files in a [`ZipArchive`].
+///
+/// Created by [`ZipArchive::entry_contents`].
+#[derive(Debug)]
+pub struct EntryContents<'a, R> {
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    max_size: u64,
+}
+
+impl<'a, R: Read + Seek> EntryContents<'a, R> {
+    fn read_entry(&mut self, index: usize) -> ZipResult<(String, Vec<u8>)> {
+        let file = self.archive.by_index(index)?;
+        if file.size() > self.max_size {
+            return Err(ZipError::InvalidArchive(
+                "File is larger than the size limit",
+            ));
+        }
+        let name = file.name().to_owned();
+        let mut contents = Vec::with_capacity(file.size() as usize);
+        file.take(self.max_size + 1).read_to_end(&mut contents)?;
+        if contents.len() as u64 > self.max_size {
+            return Err(ZipError::InvalidArchive(
+                "File is larger than the size limit",
+            ));
+        }
+        Ok((name, contents))
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for EntryContents<'a, R> {
+    type Item = ZipResult<(String, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.archive.len() {
+            let index = self.index;
+            self.index += 1;
+            if let Err(e) = self
+                .archive
+                .parse_lazy_entries_until(|files| files.len() > index)
+            {
+                return Some(Err(e));
+            }
+            let (_, data) = self.archive.shared.files.get_index(index)?;
+            if data.is_dir() {
+                continue;
+            }
+            return Some(self.read_entry(index));
+        }
+        None
+    }
+}
+
+/// Cursor over the files in a [`ZipArchive`], in central directory order.
+///
+/// Created by [`ZipArchive::entries`] or [`ZipArchive::entries_decrypt`].
+///
+/// ```no_run
+/// use std::io::prelude::*;
+/// fn list_zip_contents(reader: impl Read + Seek) -> zip::result::ZipResult<()> {
+///     let mut zip = zip::ZipArchive::new(reader)?;
+///     let mut entries = zip.entries();
+///
+///     while let Some(file) = entries.next_entry() {
+///         let mut file = file?;
+///         println!("Filename: {}", file.name());
+///         std::io::copy(&mut file, &mut std::io::stdout())?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Entries<'a, R> {
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&'a [u8]>,
+}
+
+impl<'a, R: Read + Seek> Entries<'a, R> {
+    /// Get the next file, or `None` once every file has been returned. The file borrows this
+    /// cursor, so it must be dropped before the next call.
+    ///
+    /// After an error, the next call moves on to the following file.
+    pub fn next_entry(&mut self) -> Option<ZipResult<ZipFile<'_>>> {
+        if self.index >= self.archive.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(
+            self.archive
+                .by_index_with_optional_password(self.index - 1, self.password),
+        )
+    }
+
+    /// The number of files left to return.
+    pub fn remaining(&self) -> usize {
+        self.archive.len() - self.index
+    }
+}
+
+/// A contained file whose compressed data has been read into memory, so it can be both copied
+/// as is and decompressed without reading it from the archive again. Returned by
+/// [`ZipArchive::by_index_dual`].
+#[derive(Debug)]
+pub struct DualReader {
+    data: ZipFileData,
+    compressed: io::Cursor<Vec<u8>>,
+}
+
+impl DualReader {
+    /// The compressed (and, if applicable, encrypted) data, as stored in the archive.
+    pub fn raw(&self) -> &[u8] {
+        self.compressed.get_ref()
+    }
+
+    /// Get a reader that decompresses the buffered data from the start, checking its CRC-32 as
+    /// [`ZipArchive::by_index`] does. This can be called any number of times.
+    pub fn decompressed(&mut self) -> ZipResult<ZipFile<'_>> {
+        if self.data.encrypted {
+            return Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED));
+        }
+        self.compressed.set_position(0);
+        check_decodable(self.data.compression_method, None)?;
+        let limit_reader = (&mut self.compressed as &mut dyn Read).take(self.data.compressed_size);
+        let crypto_reader = make_crypto_reader(
+            self.data.crc32,
+            self.data.last_modified_time,
+            self.data.using_data_descriptor,
+            limit_reader,
+            None,
+            self.data.aes_mode,
+            #[cfg(feature = "aes-crypto")]
+            self.data.compressed_size,
+        )?;
+        Ok(ZipFile {
+            crypto_reader: Some(crypto_reader),
+            reader: ZipFileReader::NoReader,
+            custom_decoder: None,
+            limits: ArchiveLimits::default(),
+            data: Cow::Borrowed(&self.data),
+        })
+    }
+}
+
+/// Holds the AES information of a file in the zip archive
+#[derive(Debug)]
+#[cfg(feature = "aes-crypto")]
+pub struct AesInfo {
+    /// The AES encryption mode
+    pub aes_mode: AesMode,
+    /// The verification key
+    pub verification_value: [u8; PWD_VERIFY_LENGTH],
+    /// The salt
+    pub salt: Vec<u8>,
+}
+
+const fn unsupported_zip_error<T>(detail: &'static str) -> ZipResult<T> {
+    Err(ZipError::UnsupportedArchive(detail))
+}
+
+const fn unsupported_compression<T>(method: CompressionMethod) -> ZipResult<T> {
+    let method = method.serialize_to_u16();
+    Err(ZipError::UnsupportedCompression {
+        method,
+        name: CompressionMethod::name_of(method),
+    })
+}
+
+/// Parse a central directory entry to collect the information for the file.
+pub(crate) fn central_header_to_zip_file<R: Read + Seek>(
+    reader: &mut R,
+    archive_offset: u64,
+) -> ZipResult<ZipFileData> {
+    let central_header_start = reader.stream_position()?;
+
+    // Parse central header
+    let block = ZipCentralEntryBlock::parse(reader)?;
+    let mut file =
+        central_header_to_zip_file_inner(reader, archive_offset, central_header_start, block)?;
+    if file.uncompressed_size == spec::ZIP64_BYTES_THR
+        || file.compressed_size == spec::ZIP64_BYTES_THR
+    {
+        let central_header_end = reader.stream_position()?;
+        parse_local_zip64_extra_field(reader, &mut file)?;
+        reader.seek(io::SeekFrom::Start(central_header_end))?;
+        // Without a ZIP64 field in either header, the sentinel would be taken as the real size
+        if !file.large_file {
+            return Err(ZipError::InvalidArchive(
+                "zip64 size sentinel without extra field",
+            ));
+        }
+    }
+    Ok(file)
+}
+
+/// Check an entry's name against [`ExtractOptions::forbid_absolute`] and
+/// [`ExtractOptions::forbid_parent_refs`].
+fn check_strict_name(name: &str, options: &ExtractOptions<'_>) -> ZipResult<()> {
+    let bytes = name.as_bytes();
+    if options.forbid_absolute
+        && (bytes.first().is_some_and(|b| *b == b'/' || *b == b'\\')
+            || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'))
+    {
+        return Err(ZipError::InvalidArchive("Absolute path in file name"));
+    }
+    if options.forbid_parent_refs && name.split(['/', '\\']).any(|part| part == "..") {
+        return Err(ZipError::InvalidArchive(
+            "Parent directory reference in file name",
+        ));
+    }
+    Ok(())
+}
+
+/// The modification time to give an extracted entry, preferring the extended timestamp.
+#[cfg(feature = "filetime")]
+fn entry_mtime(data: &ZipFileData) -> Option<filetime::FileTime> {
+    data.extra_fields
+        .iter()
+        .find_map(|field| match field {
+            ExtraField::ExtendedTimestamp(timestamp) => timestamp.mod_time().copied(),
+            _ => None,
+        })
+        .map(|mod_time| filetime::FileTime::from_unix_time(mod_time.into(), 0))
+        .or_else(|| {
+            data.last_modified_time
+                .map(|time| filetime::FileTime::from_unix_time(time.unix_timestamp(), 0))
+        })
+}
+
+/// Whether a symlink target, which [`ZipArchive::extract`] resolves relative to the extraction
+/// directory, would point outside it: either because it's absolute or has a drive letter, or
+/// because it has more `..` components than it has components to cancel them out.
+fn symlink_target_escapes(target: &[u8]) -> bool {
+    if target.first().is_some_and(|b| *b == b'/' || *b == b'\\')
+        || (target.len() >= 2 && target[1] == b':')
+    {
+        return true;
+    }
+    let mut depth = 0usize;
+    for component in target.split(|b| *b == b'/' || *b == b'\\') {
+        match component {
+            b"" | b"." => (),
+            b".." => match depth.checked_sub(1) {
+                Some(new_depth) => depth = new_depth,
+                None => return true,
+            },
+            _ => depth += 1,
+        }
+    }
+    false
+}
+
+/// Fill in sizes that the central directory defers to a ZIP64 extra field, when that field is only
+/// present in the local header.
+fn parse_local_zip64_extra_field<R: Read + Seek>(
+    reader: &mut R,
+    file: &mut ZipFileData,
+) -> ZipResult<()> {
+    reader.seek(io::SeekFrom::Start(file.header_start))?;
+    let block = ZipLocalEntryBlock::parse(reader)?;
+    reader.seek(io::SeekFrom::Current(block.file_name_length as i64))?;
+    let extra_field = read_variable_length_byte_field(reader, block.extra_field_length as usize)?;
+    let mut reader = io::Cursor::new(extra_field.as_ref());
+
+    while (reader.position() as usize) < extra_field.len() {
+        let kind = reader.read_u16_le()?;
+        let len = reader.read_u16_le()?;
+        if kind != 0x0001 {
+            reader.seek(io::SeekFrom::Current(len as i64))?;
+            continue;
+        }
+        // Unlike the central directory's, the local ZIP64 field must hold both sizes, whichever
+        // of them overflowed.
+        if len >= 16 {
+            let uncompressed_size = reader.read_u64_le()?;
+            let compressed_size = reader.read_u64_le()?;
+            if file.uncompressed_size == spec::ZIP64_BYTES_THR {
+                file.uncompressed_size = uncompressed_size;
+            }
+            if file.compressed_size == spec::ZIP64_BYTES_THR {
+                file.compressed_size = compressed_size;
+            }
+        } else if len >= 8 {
+            let size = reader.read_u64_le()?;
+            if file.uncompressed_size == spec::ZIP64_BYTES_THR {
+                file.uncompressed_size = size;
+            } else {
+                file.compressed_size = size;
+            }
+        }
+        file.large_file = true;
+        break;
+    }
+    Ok(())
+}
+
+#[inline]
+fn read_variable_length_byte_field<R: Read>(reader: &mut R, len: usize) -> io::Result<Box<[u8]>> {
+    let mut data = vec![0; len].into_boxed_slice();
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Parse a central directory entry to collect the information for the file.
+fn central_header_to_zip_file_inner<R: Read>(
+    reader: &mut R,
+    archive_offset: u64,
+    central_header_start: u64,
+    block: ZipCentralEntryBlock,
+) -> ZipResult<ZipFileData> {
+    let ZipCentralEntryBlock {
+        // magic,
+        version_made_by,
+        version_to_extract,
+        flags,
+        compression_method,
+        last_mod_time,
+        last_mod_date,
+        crc32,
+        compressed_size,
+        uncompressed_size,
+        file_name_length,
+        extra_field_length,
+        file_comment_length,
+        disk_number,
+        // internal_file_attributes,
+        external_file_attributes,
+        offset,
+        ..
+    } = block;
+
+    let encrypted = flags & 1 == 1;
+    let is_utf8 = flags & (1 << 11) != 0;
+    let using_data_descriptor = flags & (1 << 3) != 0;
+
+    let file_name_raw = read_variable_length_byte_field(reader, file_name_length as usize)?;
+    let extra_field = read_variable_length_byte_field(reader, extra_field_length as usize)?;
+    let file_comment_raw = read_variable_length_byte_field(reader, file_comment_length as usize)?;
+
+    let (file_name, file_name_had_bom) = ZipFileData::decode_file_name(&file_name_raw, is_utf8);
+    let file_comment: Box<str> = match is_utf8 {
+        true => String::from_utf8_lossy(&file_comment_raw).into(),
+        false => file_comment_raw.from_cp437(),
+    };
+
+    // Construct the result
+    let mut result = ZipFileData {
+        system: System::from((version_made_by >> 8) as u8),
+        /* NB: this strips the top 8 bits! */
+        version_made_by: version_made_by as u8,
+        version_made_by_raw: version_made_by,
+        version_needed_raw: version_to_extract,
+        disk_start: disk_number.into(),
+        encrypted,
+        using_data_descriptor,
+        compression_method: CompressionMethod::parse_from_u16(compression_method),
+        compression_method_raw: compression_method,
+        compression_level: None,
+        last_modified_time: DateTime::try_from_msdos(last_mod_date, last_mod_time).ok(),
+        crc32,
+        compressed_size: compressed_size.into(),
+        uncompressed_size: uncompressed_size.into(),
+        file_name,
+        file_name_raw,
+        file_name_had_bom,
+        extra_field: Some(Arc::new(extra_field.to_vec())),
+        central_extra_field: None,
+        file_comment,
+        header_start: offset.into(),
+        extra_data_start: None,
+        central_header_start,
+        data_start: OnceLock::new(),
+        external_attributes: external_file_attributes,
+        large_file: false,
+        aes_mode: None,
+        aes_extra_data_start: 0,
+        extra_fields: Vec::new(),
+        zip64_extended_information: None,
+    };
+
+    match parse_extra_field(&mut result) {
+        Ok(..) | Err(ZipError::Io(..)) => {}
+        Err(e) => return Err(e),
+    }
+
+    let aes_enabled = result.compression_method == CompressionMethod::AES;
+    if aes_enabled && result.aes_mode.is_none() {
+        return Err(ZipError::InvalidArchive(
+            "AES encryption without AES extra data field",
+        ));
+    }
+
+    // Account for shifted zip offsets.
+    result.header_start = result
+        .header_start
+        .checked_add(archive_offset)
+        .ok_or(ZipError::InvalidArchive("Archive header is too large"))?;
+
+    Ok(result)
+}
+
+fn parse_extra_field(file: &mut ZipFileData) -> ZipResult<()> {
+    let Some(extra_field) = &file.extra_field else {
+        return Ok(());
+    };
+    let mut reader = io::Cursor::new(extra_field.as_ref());
+
+    /* TODO: codify this structure into Zip64ExtraFieldBlock fields! */
+    while (reader.position() as usize) < extra_field.len() {
+        let kind = reader.read_u16_le()?;
+        let len = reader.read_u16_le()?;
+        let mut len_left = len as i64;
+        match kind {
+            // Zip64 extended information extra field
+            0x0001 => {
+                // The values appear in this order, but APPNOTE only calls for those whose 32-bit
+                // counterparts in the header are set to the sentinel. Some archivers write them
+                // all regardless, so when there are more values than sentinels, take them by
+                // position instead.
+                let sentinels = [
+                    file.uncompressed_size == spec::ZIP64_BYTES_THR,
+                    file.compressed_size == spec::ZIP64_BYTES_THR,
+                    file.header_start == spec::ZIP64_BYTES_THR,
+                ];
+                let available = usize::from(len / 8).min(3);
+                let by_position = available > sentinels.iter().filter(|s| **s).count();
+                let mut values = [None; 3];
+                let mut read = 0;
+                for (value, sentinel) in values.iter_mut().zip(sentinels) {
+                    if read < available && (by_position || sentinel) {
+                        *value = Some(reader.read_u64_le()?);
+                        read += 1;
+                    }
+                }
+                len_left -= 8 * read as i64;
+                let disk_start = if len_left >= 4 {
+                    len_left -= 4;
+                    Some(reader.read_u32_le()?)
+                } else {
+                    None
+                };
+                if let (true, Some(disk)) = (file.disk_start == u16::MAX as u32, disk_start) {
+                    file.disk_start = disk;
+                }
+                let [uncompressed_size, compressed_size, header_start] = values;
+                if let (true, Some(size)) = (sentinels[0], uncompressed_size) {
+                    file.large_file = true;
+                    file.uncompressed_size = size;
+                }
+                if let (true, Some(size)) = (sentinels[1], compressed_size) {
+                    file.large_file = true;
+                    file.compressed_size = size;
+                }
+                if let (true, Some(offset)) = (sentinels[2], header_start) {
+                    file.header_start = offset;
+                }
+                file.zip64_extended_information = Some(Zip64ExtendedInformation {
+                    uncompressed_size,
+                    compressed_size,
+                    header_start,
+                    disk_start,
+                });
+            }
+            0x9901 => {
+                // AES
+                if len != 7 {
+                    return Err(ZipError::UnsupportedArchive(
+                        "AES extra data field has an unsupported length",
+                    ));
+                }
+                let vendor_version = reader.read_u16_le()?;
+                let vendor_id = reader.read_u16_le()?;
+                let mut out = [0u8];
+                reader.read_exact(&mut out)?;
+                let aes_mode = out[0];
+                let compression_method = CompressionMethod::parse_from_u16(reader.read_u16_le()?);
+
+                if vendor_id != 0x4541 {
+                    return Err(ZipError::InvalidArchive("Invalid AES vendor"));
+                }
+                let vendor_version = match vendor_version {
+                    0x0001 => AesVendorVersion::Ae1,
+                    0x0002 => AesVendorVersion::Ae2,
+                    _ => return Err(ZipError::InvalidArchive("Invalid AES vendor version")),
+                };
+                if compression_method == CompressionMethod::AES {
+                    return Err(ZipError::InvalidArchive(
+                        "AES field specifies AES as inner method",
+                    ));
+                }
+                match aes_mode {
+                    0x01 => {
+                        file.aes_mode = Some((AesMode::Aes128, vendor_version, compression_method))
+                    }
+                    0x02 => {
+                        file.aes_mode = Some((AesMode::Aes192, vendor_version, compression_method))
+                    }
+                    0x03 => {
+                        file.aes_mode = Some((AesMode::Aes256, vendor_version, compression_method))
+                    }
+                    _ => return Err(ZipError::InvalidArchive("Invalid AES encryption strength")),
+                };
+                file.compression_method = compression_method;
+            }
+            0x5455 => {
+                // extended timestamp
+                // https://libzip.org/specifications/extrafld.txt
+
+                file.extra_fields.push(ExtraField::ExtendedTimestamp(
+                    ExtendedTimestamp::try_from_reader(&mut reader, len)?,
+                ));
+
+                // the reader for ExtendedTimestamp consumes `len` bytes
+                len_left = 0;
+            }
+            0xcafe => {
+                // JAR marker, possibly padded for alignment
+                file.extra_fields.push(ExtraField::JavaAlignment(len));
+            }
+            0x7075 => {
+                // Info-ZIP Unicode Path: version, CRC-32 of the name in the header, UTF-8 name
+                // https://libzip.org/specifications/extrafld.txt
+                let mut payload = vec![0; len as usize];
+                reader.read_exact(&mut payload)?;
+                if let [1, c0, c1, c2, c3, ref name @ ..] = payload[..] {
+                    let name_crc32 = u32::from_le_bytes([c0, c1, c2, c3]);
+                    // A mismatch means the name was changed by a tool that didn't update this
+                    if name_crc32 == crc32fast::hash(&file.file_name_raw) {
+                        if let Ok(name) = std::str::from_utf8(name) {
+                            file.file_name = name.into();
+                        }
+                    }
+                }
+                file.extra_fields.push(ExtraField::UnicodePath(payload));
+                len_left = 0;
+            }
+            0x7875 => {
+                // Info-ZIP New Unix: version, then the UID and GID, each preceded by its size
+                // https://libzip.org/specifications/extrafld.txt
+                let mut payload = vec![0; len as usize];
+                reader.read_exact(&mut payload)?;
+                if let Some((uid, gid)) = parse_unix_uid_gid(&payload)? {
+                    file.extra_fields.push(ExtraField::UnixUidGid { uid, gid });
+                }
+                len_left = 0;
+            }
+            0x0009 => {
+                // OS/2 extended attributes
+                let mut payload = vec![0; len as usize];
+                reader.read_exact(&mut payload)?;
+                file.extra_fields
+                    .push(ExtraField::Os2ExtendedAttributes(payload));
+                len_left = 0;
+            }
+            _ => {
+                // Other fields are ignored
+            }
+        }
+
+        // We could also check for < 0 to check for errors
+        if len_left > 0 {
+            reader.seek(io::SeekFrom::Current(len_left))?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse the payload of an Info-ZIP New Unix extra field, returning `None` if it has a version
+/// other than 1 or either ID doesn't fit in a `u32`, and an error if it's malformed.
+fn parse_unix_uid_gid(payload: &[u8]) -> ZipResult<Option<(u32, u32)>> {
+    const INVALID: ZipError = ZipError::InvalidArchive("Invalid Info-ZIP New Unix extra field");
+
+    fn parse_id(bytes: &[u8]) -> ZipResult<(Option<u32>, &[u8])> {
+        let (&size, rest) = bytes.split_first().ok_or(INVALID)?;
+        // An ID is at least one byte long, so an empty one isn't a zero
+        if size == 0 {
+            return Err(INVALID);
+        }
+        let id = rest.get(..size as usize).ok_or(INVALID)?;
+        let rest = &rest[size as usize..];
+        if id.iter().skip(4).any(|&b| b != 0) {
+            return Ok((None, rest));
+        }
+        let mut id_bytes = [0u8; 4];
+        let len = id.len().min(4);
+        id_bytes[..len].copy_from_slice(&id[..len]);
+        Ok((Some(u32::from_le_bytes(id_bytes)), rest))
+    }
+
+    let [1, rest @ ..] = payload else {
+        return Ok(None);
+    };
+    let (uid, rest) = parse_id(rest)?;
+    let (gid, _) = parse_id(rest)?;
+    Ok(uid.zip(gid))
+}
+
+/// Methods for retrieving information on zip files
+impl<'a> ZipFile<'a> {
+    fn get_reader(&mut self) -> ZipResult<&mut ZipFileReader<'a>> {
+        if let ZipFileReader::NoReader = self.reader {
+            let data = &self.data;
+            let crypto_reader = self.crypto_reader.take().expect("Invalid reader state");
+            self.reader = make_reader(
+                data.compression_method,
+                data.crc32,
+                Some(data.uncompressed_size),
+                crypto_reader,
+                self.custom_decoder,
+                self.limits,
+            )?;
+        }
+        Ok(&mut self.reader)
+    }
+
+    pub(crate) fn get_raw_reader(&mut self) -> &mut dyn Read {
+        if let ZipFileReader::NoReader = self.reader {
+            let crypto_reader = self.crypto_reader.take().expect("Invalid reader state");
+            self.reader = ZipFileReader::Raw(crypto_reader.into_inner())
+        }
+        &mut self.reader
+    }
+
+    /// Get the version of the file
+    pub fn version_made_by(&self) -> (u8, u8) {
+        (
+            self.data.version_made_by / 10,
+            self.data.version_made_by % 10,
+        )
+    }
+
+    /// Get the minimum PKZIP version needed to extract the file, as stored in the central
+    /// directory, such as `(4, 5)` for an entry that uses ZIP64. The upper byte of the field,
+    /// which some tools fill in with the host system, is ignored.
+    pub fn version_needed(&self) -> (u8, u8) {
+        let version = self.data.version_needed_raw as u8;
+        (version / 10, version % 10)
+    }
+
+    /// Get the `version made by` field exactly as stored in the central directory, including the
+    /// host system in its upper byte.
+    ///
+    /// Unlike [`ZipFile::version_made_by`] and [`ZipFile::unix_mode`], this doesn't interpret the
+    /// value, so it can be written back unchanged.
+    pub fn version_made_by_raw(&self) -> u16 {
+        self.data.version_made_by_raw
+    }
+
+    /// Get the name of the file
+    ///
+    /// # Warnings
+    ///
+    /// It is dangerous to use this name directly when extracting an archive.
+    /// It may contain an absolute path (`/etc/shadow`), or break out of the
+    /// current directory (`../runtime`). Carelessly writing to these paths
+    /// allows an attacker to craft a ZIP archive that will overwrite critical
+    /// files.
+    ///
+    /// You can use the [`ZipFile::enclosed_name`] method to validate the name
+    /// as a safe path.
+    pub fn name(&self) -> &str {
+        &self.data.file_name
+    }
+
+    /// Get the name of the file, in the raw (internal) byte representation.
+    ///
+    /// The encoding of this data is currently undefined.
+    pub fn name_raw(&self) -> &[u8] {
+        &self.data.file_name_raw
+    }
+
+    /// Whether the raw name starts with a UTF-8 byte order mark.
+    ///
+    /// Some writers add one, but it isn't part of the name, so it's left out of
+    /// [`ZipFile::name`]. It's still included in [`ZipFile::name_raw`].
+    pub fn name_had_bom(&self) -> bool {
+        self.data.file_name_had_bom
+    }
+
+    /// Get the name of the file in a sanitized form. It truncates the name to the first NULL byte,
+    /// removes a leading '/' and removes '..' parts.
+    #[deprecated(
+        since = "0.5.7",
+        note = "by stripping `..`s from the path, the meaning of paths can change.
+                `mangled_name` can be used if this behaviour is desirable"
+    )]
+    pub fn sanitized_name(&self) -> PathBuf {
+        self.mangled_name()
+    }
+
+    /// Rewrite the path, ignoring any path components with special meaning.
+    ///
+    /// - Absolute paths are made relative
+    /// - [`ParentDir`]s are ignored
+    /// - Truncates the filename at a NULL byte
+    ///
+    /// This is appropriate if you need to be able to extract *something* from
+    /// any archive, but will easily misrepresent trivial paths like
+    /// `foo/../bar` as `foo/bar` (instead of `bar`). Because of this,
+    /// [`ZipFile::enclosed_name`] is the better option in most scenarios.
+    ///
+    /// [`ParentDir`]: `Component::ParentDir`
+    pub fn mangled_name(&self) -> PathBuf {
+        self.data.file_name_sanitized()
+    }
+
+    /// Ensure the file path is safe to use as a [`Path`].
+    ///
+    /// - It can't contain NULL bytes
+    /// - It can't resolve to a path outside the current directory
+    ///   > `foo/../bar` is fine, `foo/../../bar` is not.
+    /// - It can't be an absolute path
+    /// - It can't resolve to the current directory itself
+    ///   > `.`, `./` and `foo/..` are all rejected.
+    ///
+    /// This will read well-formed ZIP files correctly, and is resistant
+    /// to path-based exploits. It is recommended over
+    /// [`ZipFile::mangled_name`].
+    pub fn enclosed_name(&self) -> Option<PathBuf> {
+        self.data.enclosed_name()
+    }
+
+    /// Like [`ZipFile::enclosed_name`], but with the path rules given by `policy`.
+    ///
+    /// Returns `None` if the name can't be used as a path under the policy.
+    pub fn safe_path_with(&self, policy: PathPolicy) -> Option<PathBuf> {
+        self.data.safe_path_with(policy)
+    }
+
+    /// Get the comment of the file
+    pub fn comment(&self) -> &str {
+        &self.data.file_comment
+    }
+
+    /// Get the compression method used to store the file
+    pub fn compression(&self) -> CompressionMethod {
+        self.data.compression_method
+    }
+
+    /// Get the compression method field exactly as stored in the central directory (or, when
+    /// reading a stream, the local header).
+    ///
+    /// Unlike [`ZipFile::compression`], this is available for methods this crate doesn't know
+    /// or wasn't built with support for, and for AES-encrypted files it's 99 rather than the
+    /// method the data was compressed with.
+    pub fn compression_method_raw(&self) -> u16 {
+        self.data.compression_method_raw
+    }
+
+    /// Get the size of the file, in bytes, in the archive
+    pub fn compressed_size(&self) -> u64 {
+        self.data.compressed_size
+    }
+
+    /// Get the size of the file, in bytes, when uncompressed
+    pub fn size(&self) -> u64 {
+        self.data.uncompressed_size
+    }
+
+    /// Returns whether compressing the file made it smaller, i.e. its size in the archive is less
+    /// than its uncompressed size. Since the size in the archive includes any encryption header,
+    /// this also accounts for encryption overhead.
+    ///
+    /// Returns `None` if the file is empty, or if its sizes aren't known, as for an entry read
+    /// from a stream whose sizes are in a data descriptor after its data.
+    pub fn is_compression_beneficial(&self) -> Option<bool> {
+        if self.data.uncompressed_size == 0
+            || (self.data.using_data_descriptor && self.data.compressed_size == 0)
+        {
+            return None;
+        }
+        Some(self.data.compressed_size < self.data.uncompressed_size)
+    }
+
+    /// Returns whether the raw data of the file is byte-for-byte identical to its contents, i.e. it
+    /// is stored without compression or encryption.
+    ///
+    /// Such files can be copied directly from the range of the underlying reader that starts at
+    /// [`ZipFile::data_start`] and is [`ZipFile::compressed_size`] bytes long.
+    pub fn is_raw_copyable(&self) -> bool {
+        self.data.compression_method == CompressionMethod::Stored && !self.data.encrypted
+    }
+
+    /// Get the time the file was last modified
+    pub fn last_modified(&self) -> Option<DateTime> {
+        self.data.last_modified_time
+    }
+    /// Returns whether the file is actually a directory
+    pub fn is_dir(&self) -> bool {
+        is_dir(self.name())
+    }
+
+    /// Returns whether the file is actually a symbolic link
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode()
+            .is_some_and(|mode| mode & S_IFMT == S_IFLNK)
+    }
+
+    /// Returns whether the file is a normal file (i.e. not a directory or symlink)
+    pub fn is_file(&self) -> bool {
+        !self.is_dir() && !self.is_symlink()
+    }
+
+    /// Returns whether the file is a zero-length file, as opposed to a directory (which also has
+    /// no contents) or a file with contents
+    pub fn is_empty_file(&self) -> bool {
+        self.data.is_empty_file()
+    }
+
+    /// Get unix mode for the file
+    pub fn unix_mode(&self) -> Option<u32> {
+        self.data.unix_mode()
+    }
+
+    /// Get the Unix user ID of the file's owner, from the Info-ZIP New Unix extra field, if it
+    /// has one
+    pub fn unix_uid(&self) -> Option<u32> {
+        self.unix_uid_gid().map(|(uid, _)| uid)
+    }
+
+    /// Get the Unix group ID of the file's group, from the Info-ZIP New Unix extra field, if it
+    /// has one
+    pub fn unix_gid(&self) -> Option<u32> {
+        self.unix_uid_gid().map(|(_, gid)| gid)
+    }
+
+    fn unix_uid_gid(&self) -> Option<(u32, u32)> {
+        self.data.extra_fields.iter().find_map(|field| match field {
+            ExtraField::UnixUidGid { uid, gid } => Some((*uid, *gid)),
+            _ => None,
+        })
+    }
+
+    /// Get the CRC32 hash of the original file
+    pub fn crc32(&self) -> u32 {
+        self.data.crc32
+    }
+
+    /// Decompress the rest of the file and check whether its CRC-32 matches [`ZipFile::crc32`],
+    /// returning `false` rather than an error if it doesn't.
+    ///
+    /// This also works on a file opened with [`ZipArchive::by_index_raw`], as long as it isn't
+    /// encrypted. It should be called before any of the file's data has been read, since only
+    /// what's read from here on is checked. AE-2 encrypted files don't store a CRC-32, so this
+    /// returns `false` for them.
+    pub fn verify_crc32(&mut self) -> ZipResult<bool> {
+        if let ZipFileReader::Raw(_) = self.reader {
+            if self.data.encrypted {
+                return Err(ZipError::UnsupportedArchive(
+                    "Can't verify the CRC-32 of an encrypted file opened as raw",
+                ));
+            }
+            let ZipFileReader::Raw(raw) = mem::replace(&mut self.reader, ZipFileReader::NoReader)
+            else {
+                unreachable!()
+            };
+            self.reader = make_reader(
+                self.data.compression_method,
+                self.data.crc32,
+                Some(self.data.uncompressed_size),
+                CryptoReader::Plaintext(raw),
+                self.custom_decoder,
+                self.limits,
+            )?;
+        }
+        Ok(self.read_rest_unchecked(|_| ())? == self.data.crc32)
+    }
+
+    /// Decompress the rest of the file, passing each chunk of data to `on_data`, and return the
+    /// CRC-32 of what was read. The stored CRC-32 isn't checked, so that the caller can compare
+    /// the result against whichever value it trusts, but any other error, such as the data
+    /// having the wrong size, is returned.
+    fn read_rest_unchecked(&mut self, mut on_data: impl FnMut(&[u8])) -> ZipResult<u32> {
+        let reader = self.get_reader()?;
+        reader.disable_crc_check();
+        let mut hasher = crc32fast::Hasher::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    hasher.update(&buf[..n]);
+                    on_data(&buf[..n]);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// Get a key for this file made of its name, CRC-32, uncompressed size and compression
+    /// method, for use in maps or for spotting unchanged files across archives.
+    pub fn identity(&self) -> EntryIdentity {
+        EntryIdentity {
+            name: self.name().to_string(),
+            crc32: self.data.crc32,
+            uncompressed_size: self.data.uncompressed_size,
+            compression: self.data.compression_method,
+        }
+    }
+
+    /// Get the extra data of the zip header for this file
+    pub fn extra_data(&self) -> Option<&[u8]> {
+        self.data.extra_field.as_ref().map(|v| v.deref().deref())
+    }
+
+    /// Get the starting offset of the data of the compressed file
+    pub fn data_start(&self) -> u64 {
+        *self.data.data_start.get().unwrap_or(&0)
+    }
+
+    /// Get the starting offset of the zip header for this file
+    pub fn header_start(&self) -> u64 {
+        self.data.header_start
+    }
+    /// Get the starting offset of the zip header in the central directory for this file
+    pub fn central_header_start(&self) -> u64 {
+        self.data.central_header_start
+    }
+
+    /// iterate through all extra fields
+    pub fn extra_data_fields(&self) -> impl Iterator<Item = &ExtraField> {
+        self.data.extra_fields.iter()
+    }
+
+    /// Get the ZIP64 extended information field, if the file has one.
+    ///
+    /// This isn't one of the [`ZipFile::extra_data_fields`], but its values are used in place of
+    /// the 32-bit sizes and offset in the header when those overflow.
+    pub fn zip64_extended_information(&self) -> Option<&Zip64ExtendedInformation> {
+        self.data.zip64_extended_information.as_ref()
+    }
+
+    /// Get the absolute offset, in the underlying reader, of the payload of the first extra field
+    /// with the given header ID in the central directory record for this file.
+    ///
+    /// Returns `None` if there is no such field, or if the position of the central directory
+    /// record isn't known (see [`ZipFile::central_header_start`]).
+    pub fn extra_field_offset(&self, id: u16) -> Option<u64> {
+        if self.data.central_header_start == 0 {
+            return None;
+        }
+        let extra_field = self.extra_data()?;
+        let extra_field_start = self.data.central_header_start
+            + mem::size_of::<ZipCentralEntryBlock>() as u64
+            + self.data.file_name_raw.len() as u64;
+        let mut reader = io::Cursor::new(extra_field);
+        while (reader.position() as usize) < extra_field.len() {
+            let kind = reader.read_u16_le().ok()?;
+            let len = reader.read_u16_le().ok()?;
+            if kind == id {
+                return Some(extra_field_start + reader.position());
+            }
+            reader.seek(io::SeekFrom::Current(len as i64)).ok()?;
+        }
+        None
+    }
+
+    /// Wrap the decompressed contents of the file in a [`BufRead`], with a
+    /// buffer sized to suit the file: no larger than the file itself, and at most 64 KiB.
+    pub fn buf_reader(&mut self) -> io::BufReader<&mut Self> {
+        let capacity = self.size().clamp(1, 64 * 1024) as usize;
+        self.buf_reader_with_capacity(capacity)
+    }
+
+    /// Wrap the decompressed contents of the file in a [`BufRead`] with a
+    /// buffer of the given capacity.
+    pub fn buf_reader_with_capacity(&mut self, capacity: usize) -> io::BufReader<&mut Self> {
+        io::BufReader::with_capacity(capacity, self)
+    }
+
+    /// Read the decompressed contents of the file as lines of text, as with
+    /// [`BufRead::lines`].
+    pub fn lines(&mut self) -> io::Lines<io::BufReader<&mut Self>> {
+        self.buf_reader().lines()
+    }
+}
+
+impl<'a> Read for ZipFile<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.get_reader()?.read(buf)?;
+        // The sizes and CRC-32 of a streamed entry with a data descriptor are known at its end
+        #[cfg(any(
+            feature = "deflate",
+            feature = "deflate-zlib",
+            feature = "deflate-zlib-ng"
+        ))]
+        if count == 0 {
+            if let ZipFileReader::DeflatedDataDescriptor(reader) = &self.reader {
+                if let Some(descriptor) = reader.descriptor() {
+                    let data = self.data.to_mut();
+                    data.crc32 = descriptor.crc32;
+                    data.compressed_size = descriptor.compressed_size;
+                    data.uncompressed_size = descriptor.uncompressed_size;
+                }
+            }
+        }
+        Ok(count)
+    }
+}
+
+impl<'a> Drop for ZipFile<'a> {
+    fn drop(&mut self) {
+        // self.data is Owned, this reader is constructed by a streaming reader.
+        // In this case, we want to exhaust the reader so that the next file is accessible.
+        if let Cow::Owned(_) = self.data {
+            // Get the inner `Take` reader so all decryption, decompression and CRC calculation is skipped.
+            match &mut self.reader {
+                ZipFileReader::NoReader => {
+                    let innerreader = self.crypto_reader.take();
+                    let _ = copy(
+                        &mut innerreader.expect("Invalid reader state").into_inner(),
+                        &mut sink(),
+                    );
+                }
+                reader => {
+                    let innerreader = std::mem::replace(reader, ZipFileReader::NoReader);
+                    innerreader.drain();
+                }
+            };
+        }
+    }
+}
+
+/// Extract the ZIP archive at the path `archive` into the directory `directory`.
+///
+/// This is a shorthand for opening the file, reading it with [`ZipArchive::new`] and calling
+/// [`ZipArchive::extract`], so the same path sanitization and permission handling applies.
+///
+/// ```no_run
+/// zip::extract_file("archive.zip", "output")?;
+/// # Ok::<(), zip::result::ZipError>(())
+/// ```
+pub fn extract_file<P: AsRef<Path>, Q: AsRef<Path>>(archive: P, directory: Q) -> ZipResult<()> {
+    let file = std::fs::File::open(archive)?;
+    ZipArchive::new(io::BufReader::new(file))?.extract(directory)
+}
+
+/// Read ZipFile structures from a non-seekable reader.
+///
+/// This is an alternative method to read a zip file. If possible, use the ZipArchive functions
+/// as some information will be missing when reading this manner.
+///
+/// Reads a file header from the start of the stream. Will return `Ok(Some(..))` if a file is
+/// present at the start of the stream. Returns `Ok(None)` if the start of the central directory
+/// is encountered. No more files should be read after this.
+///
+/// The Drop implementation of ZipFile ensures that the reader will be correctly positioned after
+/// the structure is done.
+///
+/// Missing fields are:
+/// * `comment`: set to an empty string
+/// * `data_start`: set to 0
+/// * `external_attributes`: `unix_mode()`: will return None
+///
+/// An entry whose sizes and CRC-32 are in a data descriptor after its data can only be read if
+/// it's compressed with Deflate and not encrypted, since the compressed data marks its own end.
+/// Its size, compressed size and CRC-32 are 0 until it has been read to the end, when they're
+/// checked against the data descriptor and filled in. Skipping such an entry still decompresses
+/// it, and it can't be copied with [`ZipWriter::raw_copy_file`](crate::ZipWriter::raw_copy_file).
+/// Its data is read from the stream in chunks of at most 20 bytes, so that nothing past the data
+/// descriptor is consumed, and an unbuffered reader should be wrapped in a [`io::BufReader`].
+pub fn read_zipfile_from_stream<'a, R: Read>(reader: &'a mut R) -> ZipResult<Option<ZipFile<'a>>> {
+    // We can't use the typical ::parse() method, as we follow separate code paths depending on the
+    // "magic" value (since the magic value will be from the central directory header if we've
+    // finished iterating over all the actual files).
+    /* TODO: smallvec? */
+    let mut block = [0u8; mem::size_of::<ZipLocalEntryBlock>()];
+    reader.read_exact(&mut block)?;
+    let block: Box<[u8]> = block.into();
+
+    let signature = spec::Magic::from_first_le_bytes(&block);
+
+    match signature {
+        spec::Magic::LOCAL_FILE_HEADER_SIGNATURE => (),
+        spec::Magic::CENTRAL_DIRECTORY_HEADER_SIGNATURE => return Ok(None),
+        _ => return Err(ZipError::InvalidArchive("Invalid local file header")),
+    }
+
+    let block = ZipLocalEntryBlock::interpret(block)?;
+
+    let mut result = ZipFileData::from_local_block(block, reader)?;
+
+    match parse_extra_field(&mut result) {
+        Ok(..) | Err(ZipError::Io(..)) => {}
+        Err(e) => return Err(e),
+    }
+
+    if result.using_data_descriptor {
+        if result.encrypted {
+            return Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED));
+        }
+        #[cfg(any(
+            feature = "deflate",
+            feature = "deflate-zlib",
+            feature = "deflate-zlib-ng"
+        ))]
+        if result.compression_method == CompressionMethod::Deflated {
+            let large_file = result.large_file;
+            return Ok(Some(ZipFile {
+                data: Cow::Owned(result),
+                crypto_reader: None,
+                reader: ZipFileReader::DeflatedDataDescriptor(DataDescriptorDeflateReader::new(
+                    reader, large_file,
+                )),
+                custom_decoder: None,
+                limits: ArchiveLimits::default(),
+            }));
+        }
+        return Err(ZipError::UnsupportedArchive(
+            "The file length is not available in the local header",
+        ));
+    }
+
+    let limit_reader = (reader as &'a mut dyn Read).take(result.compressed_size);
+
+    let result_crc32 = result.crc32;
+    let result_compression_method = result.compression_method;
+    // Sizes in the local header are only known up front without a data descriptor
+    let result_uncompressed_size =
+        (!result.using_data_descriptor).then_some(result.uncompressed_size);
+    check_decodable(result_compression_method, None)?;
+    let crypto_reader = make_crypto_reader(
+        result_crc32,
+        result.last_modified_time,
+        result.using_data_descriptor,
+        limit_reader,
+        None,
+        None,
+        #[cfg(feature = "aes-crypto")]
+        result.compressed_size,
+    )?;
+
+    Ok(Some(ZipFile {
+        data: Cow::Owned(result),
+        crypto_reader: None,
+        reader: make_reader(
+            result_compression_method,
+            result_crc32,
+            result_uncompressed_size,
+            crypto_reader,
+            None,
+            ArchiveLimits::default(),
+        )?,
+        custom_decoder: None,
+        limits: ArchiveLimits::default(),
+    }))
+}
+
+/// Sizes and CRC-32 from a data descriptor.
+#[cfg(any(
+    feature = "deflate",
+    feature = "deflate-zlib",
+    feature = "deflate-zlib-ng"
+))]
+pub(crate) struct DataDescriptor {
+    crc32: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+/// Decompresses a Deflate entry from a stream, then reads the data descriptor after it and checks
+/// the data against it.
+#[cfg(any(
+    feature = "deflate",
+    feature = "deflate-zlib",
+    feature = "deflate-zlib-ng"
+))]
+pub(crate) struct DataDescriptorDeflateReader<'a> {
+    decoder: flate2::bufread::DeflateDecoder<io::BufReader<&'a mut dyn Read>>,
+    hasher: crc32fast::Hasher,
+    uncompressed_size: u64,
+    large_file: bool,
+    descriptor: Option<DataDescriptor>,
+}
+
+#[cfg(any(
+    feature = "deflate",
+    feature = "deflate-zlib",
+    feature = "deflate-zlib-ng"
+))]
+impl<'a> DataDescriptorDeflateReader<'a> {
+    fn new<R: Read>(reader: &'a mut R, large_file: bool) -> Self {
+        // A data descriptor is at least 12 bytes long, or 20 with 64-bit sizes, so reading chunks
+        // no bigger than that never consumes anything past it
+        let capacity = if large_file { 20 } else { 12 };
+        let stream = io::BufReader::with_capacity(capacity, reader as &'a mut dyn Read);
+        DataDescriptorDeflateReader {
+            decoder: flate2::bufread::DeflateDecoder::new(stream),
+            hasher: crc32fast::Hasher::new(),
+            uncompressed_size: 0,
+            large_file,
+            descriptor: None,
+        }
+    }
+
+    /// The data descriptor, once the data has been read to the end and matched it.
+    fn descriptor(&self) -> Option<&DataDescriptor> {
+        self.descriptor
+            .as_ref()
+            .filter(|descriptor| self.check(descriptor).is_ok())
+    }
+
+    fn check(&self, descriptor: &DataDescriptor) -> io::Result<()> {
+        if descriptor.compressed_size != self.decoder.total_in()
+            || descriptor.uncompressed_size != self.uncompressed_size
+        {
+            return Err(
+                ZipError::InvalidArchive("Data descriptor sizes don't match the data").into(),
+            );
+        }
+        if descriptor.crc32 != self.hasher.clone().finalize() {
+            return Err(io::Error::new(io::ErrorKind::Other, "Invalid checksum"));
+        }
+        Ok(())
+    }
+
+    fn read_descriptor(&mut self) -> io::Result<DataDescriptor> {
+        let stream = self.decoder.get_mut();
+        // Whatever was read past the end of the compressed data is the start of the descriptor
+        let mut descriptor = stream.buffer().to_vec();
+        let reader = stream.get_mut();
+        let mut fill = |descriptor: &mut Vec<u8>, len: usize| -> io::Result<()> {
+            let start = descriptor.len();
+            if start < len {
+                descriptor.resize(len, 0);
+                reader.read_exact(&mut descriptor[start..])?;
+            }
+            Ok(())
+        };
+        fill(&mut descriptor, 4)?;
+        let signature_len = if spec::Magic::from_first_le_bytes(&descriptor)
+            == spec::Magic::DATA_DESCRIPTOR_SIGNATURE
+        {
+            4
+        } else {
+            0
+        };
+        let sizes_len = if self.large_file { 16 } else { 8 };
+        fill(&mut descriptor, signature_len + 4 + sizes_len)?;
+
+        let mut fields = &descriptor[signature_len..];
+        let crc32 = fields.read_u32_le()?;
+        let (compressed_size, uncompressed_size) = if self.large_file {
+            (fields.read_u64_le()?, fields.read_u64_le()?)
+        } else {
+            (fields.read_u32_le()?.into(), fields.read_u32_le()?.into())
+        };
+        Ok(DataDescriptor {
+            crc32,
+            compressed_size,
+            uncompressed_size,
+        })
+    }
+}
+
+#[cfg(any(
+    feature = "deflate",
+    feature = "deflate-zlib",
+    feature = "deflate-zlib-ng"
+))]
+impl<'a> Read for DataDescriptorDeflateReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.descriptor.is_none() {
+            let count = self.decoder.read(buf)?;
+            if count > 0 {
+                self.hasher.update(&buf[..count]);
+                self.uncompressed_size += count as u64;
+                return Ok(count);
+            }
+            self.descriptor = Some(self.read_descriptor()?);
+        }
+        match &self.descriptor {
+            Some(descriptor) => self.check(descriptor).map(|()| 0),
+            None => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::result::ZipError;
+    use crate::ZipArchive;
+    use std::io::Cursor;
+    use tempdir::TempDir;
+
+    #[test]
+    fn invalid_offset() {
+        use super::ZipArchive;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/invalid_offset.zip"));
+        let reader = ZipArchive::new(Cursor::new(v));
+        assert!(reader.is_err());
+    }
+
+    #[test]
+    fn invalid_offset2() {
+        use super::ZipArchive;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/invalid_offset2.zip"));
+        let reader = ZipArchive::new(Cursor::new(v));
+        assert!(reader.is_err());
+    }
+
+    #[test]
+    fn zip64_with_leading_junk() {
+        use super::ZipArchive;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_demo.zip"));
+        let reader = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(reader.len(), 1);
+    }
+
+    #[test]
+    fn zip_contents() {
+        use super::ZipArchive;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let mut reader = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(reader.comment(), b"");
+        assert_eq!(reader.by_index(0).unwrap().central_header_start(), 77);
+    }
+
+    #[test]
+    fn new_with_callback() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/files_and_dirs.zip"));
+        let mut seen = Vec::new();
+        let reader = ZipArchive::new_with_callback(Cursor::new(v), |i, name| {
+            seen.push((i, name.to_owned()))
+        })
+        .unwrap();
+        let expected: Vec<_> = reader
+            .file_names()
+            .enumerate()
+            .map(|(i, name)| (i, name.to_owned()))
+            .collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn extra_field_offset() {
+        use crate::write::{ExtendedFileOptions, FileOptions};
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut options = FileOptions::<ExtendedFileOptions>::default();
+        options.add_extra_data(0xbeef, b"first", false).unwrap();
+        options.add_extra_data(0xcafe, b"second", true).unwrap();
+        writer.start_file("file", options).unwrap();
+        writer.write_all(b"contents").unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut archive = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+        let file = archive.by_index(0).unwrap();
+        let offset = file.extra_field_offset(0xcafe).unwrap() as usize;
+        assert_eq!(&bytes[offset..offset + 6], b"second");
+        let offset = file.extra_field_offset(0xbeef).unwrap() as usize;
+        assert_eq!(&bytes[offset..offset + 5], b"first");
+        assert_eq!(file.extra_field_offset(0x1234), None);
+    }
+
+    #[test]
+    fn local_name() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/files_and_dirs.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        for i in 0..archive.len() {
+            let local_name = archive.local_name(i).unwrap();
+            assert_eq!(local_name, archive.by_index(i).unwrap().name_raw());
+        }
+        assert!(archive.local_name(archive.len()).is_err());
+    }
+
+    #[test]
+    fn extract_file() -> crate::result::ZipResult<()> {
+        let tempdir = TempDir::new("extract_file")?;
+        let archive = tempdir.path().join("files_and_dirs.zip");
+        std::fs::write(&archive, include_bytes!("../tests/data/files_and_dirs.zip"))?;
+        let output = tempdir.path().join("output");
+        super::extract_file(&archive, &output)?;
+
+        let mut zip = ZipArchive::new(Cursor::new(std::fs::read(&archive)?))?;
+        for i in 0..zip.len() {
+            let file = zip.by_index(i)?;
+            let path = output.join(file.enclosed_name().unwrap());
+            assert_eq!(path.is_dir(), file.is_dir());
+            assert!(path.exists());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn extract_root_directory_entry() -> crate::result::ZipResult<()> {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("./", SimpleFileOptions::default())?;
+        writer.add_directory("a/..", SimpleFileOptions::default())?;
+        writer.start_file("./file.txt", SimpleFileOptions::default())?;
+        writer.write_all(b"contents")?;
+        let mut archive = ZipArchive::new(writer.finish()?)?;
+        assert_eq!(archive.by_index(0)?.enclosed_name(), None);
+
+        let tempdir = TempDir::new("extract_root_directory_entry")?;
+        archive.extract(tempdir.path())?;
+        assert_eq!(std::fs::read(tempdir.path().join("file.txt"))?, b"contents");
+        Ok(())
+    }
+
+    #[cfg(feature = "_deflate-any")]
+    #[test]
+    fn concatenated_disks() {
+        use super::Config;
+        use std::io::Read;
+
+        // Made with `zip -s 64k`, then `cat spanned.z01 spanned.zip`
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/spanned_recombined.zip"));
+
+        // The central directory is entirely on the last disk, so it can be read, but the first
+        // file's offset is relative to the first disk
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        assert!(archive
+            .by_name("a.bin")
+            .and_then(|mut file| Ok(file.read_to_end(&mut Vec::new())?))
+            .is_err());
+
+        let config = Config {
+            concatenated_disks: true,
+            ..Default::default()
+        };
+        let mut archive = ZipArchive::with_config(config, Cursor::new(v)).unwrap();
+        let mut contents = Vec::new();
+        archive
+            .by_name("a.bin")
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents.len(), 65600);
+        assert_eq!(crc32fast::hash(&contents), 0x96aa11ae);
+        for (name, expected) in [("b.txt", "second file\n"), ("c.txt", "third file\n")] {
+            let mut contents = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            assert_eq!(contents, expected);
+        }
+    }
+
+    #[test]
+    fn max_metadata_size() {
+        use super::ArchiveLimits;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/files_and_dirs.zip"));
+        let archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        let metadata_size: usize = archive
+            .shared
+            .files
+            .values()
+            .map(|file| file.file_name_raw.len() + file.extra_field_len() + file.file_comment.len())
+            .sum();
+        assert!(metadata_size > 0);
+
+        let limits = ArchiveLimits {
+            max_metadata_size: metadata_size as u64,
+            ..Default::default()
+        };
+        ZipArchive::with_limits(limits, Cursor::new(v.clone())).unwrap();
+        let limits = ArchiveLimits {
+            max_metadata_size: metadata_size as u64 - 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ZipArchive::with_limits(limits, Cursor::new(v)),
+            Err(ZipError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn max_entries() {
+        use super::Config;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let len = ZipArchive::new(Cursor::new(v.clone())).unwrap().len();
+        let config = Config {
+            max_entries: len,
+            ..Default::default()
+        };
+        ZipArchive::with_config(config, Cursor::new(v.clone())).unwrap();
+        let config = Config {
+            max_entries: len - 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ZipArchive::with_config(config, Cursor::new(v)),
+            Err(ZipError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/duplicate_names.zip"));
+        let mut names = Vec::new();
+        let archive = ZipArchive::new_with_callback(Cursor::new(v), |index, name| {
+            names.push((index, name.to_string()))
+        })
+        .unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(names, [(0, "a.txt".into()), (1, "a.txt".into())]);
+    }
+
+    #[test]
+    fn new_lazy_duplicate_names() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/duplicate_names.zip"));
+        let mut archive = ZipArchive::new_lazy(Cursor::new(v)).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().size(), 5);
+        assert!(matches!(
+            archive.by_index(1),
+            Err(ZipError::InvalidArchive(
+                "Duplicate entry name in a lazily read archive"
+            ))
+        ));
+        assert!(archive.parse_remaining_entries().is_err());
+    }
+
+    #[test]
+    fn understated_central_directory_size() {
+        use super::Config;
+        use std::io::Read;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/understated_central_directory_size.zip"
+        ));
+        assert!(ZipArchive::new(Cursor::new(v.clone())).is_err());
+
+        let config = Config {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut archive = ZipArchive::with_config(config, Cursor::new(v)).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.offset(), 0);
+        let mut contents = String::new();
+        archive
+            .by_name("second.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "second file\n");
+    }
+
+    #[test]
+    fn zip64_sizes_only_in_local_header() {
+        use std::io::Read;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/zip64_sizes_only_in_local_header.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        assert_eq!(file.size(), 11);
+        assert_eq!(file.compressed_size(), 11);
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "hello world");
+    }
+
+    #[test]
+    fn entry_contents() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("dir/", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .start_file("dir/small", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"small").unwrap();
+        writer
+            .start_file("large", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(&[b'x'; 100]).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        let mut archive = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+
+        let mut contents = archive.entry_contents(10);
+        let (name, data) = contents.next().unwrap().unwrap();
+        assert_eq!(name, "dir/small");
+        assert_eq!(data, b"small");
+        assert!(contents.next().unwrap().is_err());
+        assert!(contents.next().is_none());
+
+        assert_eq!(archive.entry_contents(100).filter(Result::is_ok).count(), 2);
+
+        let mut lazy = ZipArchive::new_lazy(Cursor::new(bytes)).unwrap();
+        assert_eq!(lazy.entry_contents(100).filter(Result::is_ok).count(), 2);
+    }
+
+    #[test]
+    fn new_lazy_metadata_methods_see_parsed_entries() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("a.txt", options).unwrap();
+        writer.write_all(b"a").unwrap();
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("dir/b.txt", options).unwrap();
+        writer.write_all(b"bbb").unwrap();
+        writer.start_file("../evil.txt", options).unwrap();
+        #[cfg(feature = "aes-crypto")]
+        writer
+            .start_file(
+                "secret.txt",
+                options.with_aes_encryption(crate::AesMode::Aes256, "password"),
+            )
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        let eager = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+        let mut lazy = ZipArchive::new_lazy(Cursor::new(bytes)).unwrap();
+
+        // Only the first entry has been parsed
+        assert!(lazy.by_index(0).is_ok());
+        assert_eq!(lazy.manifest(), eager.manifest()[..1]);
+        assert!(lazy.validate_names().is_empty());
+        assert!(!lazy.has_encrypted_entries());
+        assert!(lazy.encrypted_entry_indices().is_empty());
+        assert_eq!(lazy.glob("**/*.txt").collect::<Vec<_>>(), [0]);
+        assert_eq!(lazy.compatibility_report().compression_methods.len(), 1);
+        assert!(lazy.explicit_directories().is_empty());
+        assert_eq!(lazy.indices_sorted_by_size(false), [0]);
+
+        lazy.parse_remaining_entries().unwrap();
+        assert_eq!(lazy.manifest(), eager.manifest());
+        assert_eq!(lazy.validate_names(), eager.validate_names());
+        assert!(!lazy.validate_names().is_empty());
+        assert_eq!(lazy.has_encrypted_entries(), eager.has_encrypted_entries());
+        assert_eq!(
+            lazy.encrypted_entry_indices(),
+            eager.encrypted_entry_indices()
+        );
+        assert!(lazy.glob("**/*.txt").eq(eager.glob("**/*.txt")));
+        assert_eq!(lazy.compatibility_report(), eager.compatibility_report());
+        assert_eq!(lazy.explicit_directories(), [1]);
+        assert_eq!(
+            lazy.indices_sorted_by_size(false),
+            eager.indices_sorted_by_size(false)
+        );
+    }
+
+    #[test]
+    fn is_raw_copyable() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        let file = archive.by_name("mimetype").unwrap();
+        assert!(file.is_raw_copyable());
+        let start = file.data_start() as usize;
+        let end = start + file.compressed_size() as usize;
+        assert_eq!(&v[start..end], b"application/vnd.oasis.opendocument.text");
+    }
+
+    #[test]
+    fn lines() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("log.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"first\nsecond\r\nthird").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        let lines: Vec<String> = file.lines().collect::<Result<_, _>>().unwrap();
+        assert_eq!(lines, ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn buf_reader() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{BufRead, Read, Write};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("records", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"one\0two\0").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        let mut reader = file.buf_reader();
+        assert_eq!(reader.capacity(), 8);
+        let mut record = Vec::new();
+        reader.read_until(0, &mut record).unwrap();
+        assert_eq!(record, b"one\0");
+        drop(file);
+
+        let mut file = archive.by_index(0).unwrap();
+        let mut reader = file.buf_reader_with_capacity(2);
+        assert_eq!(reader.capacity(), 2);
+        let mut records = Vec::new();
+        reader.read_to_end(&mut records).unwrap();
+        assert_eq!(records, b"one\0two\0");
+    }
+
+    #[test]
+    #[cfg(feature = "deflate-flate2")]
+    fn measure_compressed_size() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for method in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+            let options = SimpleFileOptions::default().compression_method(method);
+            writer.start_file(format!("{method}"), options).unwrap();
+            writer.write_all(&[b'a'; 1000]).unwrap();
+        }
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        for i in 0..archive.len() {
+            let compressed_size = archive.by_index(i).unwrap().compressed_size();
+            assert_eq!(archive.measure_compressed_size(i).unwrap(), compressed_size);
+        }
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/data_overlapping_central_directory.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(archive.measure_compressed_size(0).unwrap(), 5);
+    }
+
+    #[test]
+    fn check_entry_bounds() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        archive.check_entry_bounds().unwrap();
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/data_overlapping_central_directory.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert!(matches!(
+            archive.check_entry_bounds(),
+            Err(ZipError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn eocd_bytes() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_demo.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        assert_eq!(archive.eocd_bytes().unwrap(), &v[126..]);
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/comment_garbage.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        assert_eq!(archive.eocd_bytes().unwrap(), v);
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment("comment");
+        writer
+            .start_file("file", SimpleFileOptions::default())
+            .unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+        let bytes = archive.eocd_bytes().unwrap();
+        assert_eq!(bytes.len(), 22 + 7);
+        assert!(bytes.starts_with(b"PK\x05\x06"));
+        assert!(bytes.ends_with(b"comment"));
+    }
+
+    #[test]
+    fn extract_with_options_continues_on_error() {
+        use super::ExtractOptions;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("first.txt", options).unwrap();
+        writer.write_all(b"first").unwrap();
+        writer.start_file("../escaped.txt", options).unwrap();
+        writer.start_file("second.txt", options).unwrap();
+        writer.write_all(b"second").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let tmp_dir = TempDir::new("extract_continues_on_error").unwrap();
+        let options = ExtractOptions {
+            continue_on_error: true,
+            ..Default::default()
+        };
+        let failures = archive
+            .extract_with_options(tmp_dir.path(), options)
+            .unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 1);
+        assert!(matches!(failures[0].1, ZipError::InvalidArchive(_)));
+        assert_eq!(
+            std::fs::read(tmp_dir.path().join("first.txt")).unwrap(),
+            b"first"
+        );
+        assert_eq!(
+            std::fs::read(tmp_dir.path().join("second.txt")).unwrap(),
+            b"second"
+        );
+        assert!(archive
+            .extract(TempDir::new("extract").unwrap().path())
+            .is_err());
+    }
+
+    #[test]
+    fn looks_like_jar() {
+        use crate::write::{ExtendedFileOptions, FileOptions, SimpleFileOptions};
+        use crate::{ExtraField, ZipWriter};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut options = FileOptions::<ExtendedFileOptions>::default();
+        options.add_extra_data(0xcafe, b"", false).unwrap();
+        writer.add_directory("META-INF/", options).unwrap();
+        writer
+            .start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default())
+            .unwrap();
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert!(archive.looks_like_jar());
+        let fields: Vec<_> = archive
+            .by_index(0)
+            .unwrap()
+            .extra_data_fields()
+            .cloned()
+            .collect();
+        assert!(matches!(fields[..], [ExtraField::JavaAlignment(0)]));
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("mimetype", SimpleFileOptions::default())
+            .unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+        assert!(!archive.looks_like_jar());
+    }
+
+    #[test]
+    fn compatibility_report() {
+        use super::CompatibilityReport;
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("plain", options).unwrap();
+        writer
+            .start_file("secret", options.with_deprecated_encryption(b"password"))
+            .unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+        assert_eq!(
+            archive.compatibility_report(),
+            CompatibilityReport {
+                version_needed: 20,
+                compression_methods: vec![CompressionMethod::Stored],
+                zip64: false,
+                zip_crypto: true,
+                aes_modes: vec![],
+            }
+        );
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("large", options.large_file(true))
+            .unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+        let report = archive.compatibility_report();
+        assert!(report.zip64);
+        assert_eq!(report.version_needed, 45);
+    }
+
+    #[test]
+    fn glob() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for name in ["README.md", "docs/a.md", "docs/b.txt", "docs/guide/c.md"] {
+            writer.start_file(name, options).unwrap();
+        }
+        let archive = writer.finish_into_readable().unwrap();
+        assert_eq!(archive.glob("docs/*.md").collect::<Vec<_>>(), [1]);
+        assert_eq!(archive.glob("**/*.md").collect::<Vec<_>>(), [0, 1, 3]);
+        assert_eq!(archive.glob("docs/**").collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(archive.glob("*.zip").count(), 0);
+    }
+
+    #[test]
+    fn file_names_matching() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for name in ["Cargo.toml", "src/lib.rs", "src/read/glob.rs", "src/README"] {
+            writer.start_file(name, options).unwrap();
+        }
+        let archive = writer.finish_into_readable().unwrap();
+        let matching = |pattern| archive.file_names_matching(pattern).collect::<Vec<_>>();
+        assert_eq!(matching("src/*.rs"), ["src/lib.rs"]);
+        assert_eq!(matching("src/**/*.rs"), ["src/lib.rs", "src/read/glob.rs"]);
+        assert_eq!(matching("Cargo.toml"), ["Cargo.toml"]);
+        assert!(matching("cargo.toml").is_empty());
+    }
+
+    #[test]
+    fn validate_names() {
+        use super::NameIssue;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("good.txt", options).unwrap();
+        writer.start_file("../escaped.txt", options).unwrap();
+        writer.start_file("nul\0.txt", options).unwrap();
+        writer.start_file("dir/../fine.txt", options).unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+        assert_eq!(
+            archive.validate_names(),
+            vec![(1, NameIssue::UnsafePath), (2, NameIssue::NulByte)]
+        );
+    }
+
+    #[test]
+    fn safe_path_with() {
+        use super::PathPolicy;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::path::PathBuf;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("dir\\file.txt", options).unwrap();
+        writer
+            .start_file("dir\\..\\..\\escaped.txt", options)
+            .unwrap();
+        writer.start_file("dir/CON.txt", options).unwrap();
+        writer.start_file("dir/what?.txt", options).unwrap();
+        writer.start_file("x".repeat(300), options).unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let paths = |archive: &mut ZipArchive<_>, policy| {
+            (0..archive.len())
+                .map(|i| archive.by_index(i).unwrap().safe_path_with(policy))
+                .collect::<Vec<_>>()
+        };
+        let default = paths(&mut archive, PathPolicy::default());
+        for (i, path) in default.iter().enumerate() {
+            assert_eq!(*path, archive.by_index(i).unwrap().enclosed_name());
+        }
+        if cfg!(not(windows)) {
+            assert_eq!(default[1], Some(PathBuf::from("dir\\..\\..\\escaped.txt")));
+        }
+        assert_eq!(
+            paths(&mut archive, PathPolicy::portable()),
+            [Some(PathBuf::from("dir/file.txt")), None, None, None, None]
+        );
+        let policy = PathPolicy {
+            max_component_len: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(paths(&mut archive, policy)[4], None);
+        assert_eq!(
+            paths(&mut archive, policy)[2],
+            Some(PathBuf::from("dir/CON.txt"))
+        );
+    }
+
+    #[test]
+    fn new_lazy() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{Read, Write};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for i in 0..5 {
+            writer
+                .start_file(format!("file{i}.txt"), SimpleFileOptions::default())
+                .unwrap();
+            writer
+                .write_all(format!("contents {i}").as_bytes())
+                .unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut archive = ZipArchive::new_lazy(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(archive.len(), 5);
+        assert_eq!(archive.file_names().count(), 0);
+        let mut contents = String::new();
+        archive
+            .by_index(1)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "contents 1");
+        assert_eq!(archive.file_names().count(), 2);
+        assert_eq!(archive.by_name("file3.txt").unwrap().name(), "file3.txt");
+        assert_eq!(archive.file_names().count(), 4);
+        assert!(matches!(
+            archive.by_name("missing.txt"),
+            Err(ZipError::FileNotFound)
+        ));
+        assert!(matches!(archive.by_index(5), Err(ZipError::FileNotFound)));
+        assert_eq!(archive.len(), 5);
+
+        let eager = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert!(archive.file_names().eq(eager.file_names()));
+    }
+
+    #[test]
+    fn new_lazy_zip64() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_demo.zip"));
+        let eager = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        let mut lazy = ZipArchive::new_lazy(Cursor::new(v)).unwrap();
+        assert_eq!(lazy.len(), eager.len());
+        lazy.parse_remaining_entries().unwrap();
+        assert!(lazy.file_names().eq(eager.file_names()));
+        assert_eq!(lazy.offset(), eager.offset());
+    }
+
+    #[test]
+    fn new_lazy_with_config() {
+        use super::{ArchiveLimits, Config};
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for i in 0..3 {
+            writer
+                .start_file(format!("file{i}.txt"), SimpleFileOptions::default())
+                .unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let config = Config {
+            max_entries: 2,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ZipArchive::new_lazy_with_config(config, Cursor::new(bytes.clone())),
+            Err(ZipError::InvalidArchive("Too many entries"))
+        ));
+
+        // Each entry's metadata is just its 9-byte name, so the third one goes over the limit
+        let config = Config {
+            limits: ArchiveLimits {
+                max_metadata_size: 2 * 9,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut archive =
+            ZipArchive::new_lazy_with_config(config, Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(archive.by_index(1).unwrap().name(), "file1.txt");
+        assert!(matches!(
+            archive.by_index(2),
+            Err(ZipError::InvalidArchive(
+                "Entry names, comments and extra fields exceed the metadata size limit"
+            ))
+        ));
+
+        let config = Config {
+            lenient: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ZipArchive::new_lazy_with_config(config, Cursor::new(bytes)),
+            Err(ZipError::UnsupportedArchive(_))
+        ));
+    }
+
+    #[test]
+    fn version_made_by_raw() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/version_made_by_ntfs.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let file = archive.by_index(0).unwrap();
+        assert_eq!(file.version_made_by_raw(), 0x0A3F);
+        assert_eq!(file.version_made_by(), (6, 3));
+    }
+
+    #[test]
+    fn version_needed() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_demo.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert!(archive.min_version_needed() >= (4, 5));
+        assert!(archive.by_index(0).unwrap().version_needed() >= (4, 5));
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().version_needed(), (1, 0));
+        assert_eq!(archive.min_version_needed(), (1, 0));
+    }
 
-    let file_name: Box<str> = match is_utf8 {
-        true => String::from_utf8_lossy(&file_name_raw).into(),
-        false => file_name_raw.clone().from_cp437(),
-    };
-    let file_comment: Box<str> = match is_utf8 {
-        true => String::from_utf8_lossy(&file_comment_raw).into(),
-        false => file_comment_raw.from_cp437(),
-    };
+    #[test]
+    fn bom_names() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/bom_names.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(archive.index_for_name("utf8.txt"), Some(0));
+        assert_eq!(archive.index_for_name("unflagged.txt"), Some(1));
+        for (i, expected) in [(0, true), (1, true), (2, false)] {
+            let file = archive.by_index(i).unwrap();
+            assert_eq!(file.name_had_bom(), expected);
+            assert_eq!(file.name_raw().starts_with(b"\xEF\xBB\xBF"), expected);
+        }
+    }
 
-    // Construct the result
-    let mut result = ZipFileData {
-        system: System::from((version_made_by >> 8) as u8),
-        /* NB: this strips the top 8 bits! */
-        version_made_by: version_made_by as u8,
-        encrypted,
-        using_data_descriptor,
-        compression_method: CompressionMethod::parse_from_u16(compression_method),
-        compression_level: None,
-        last_modified_time: DateTime::try_from_msdos(last_mod_date, last_mod_time).ok(),
-        crc32,
-        compressed_size: compressed_size.into(),
-        uncompressed_size: uncompressed_size.into(),
-        file_name,
-        file_name_raw,
-        extra_field: Some(Arc::new(extra_field.to_vec())),
-        central_extra_field: None,
-        file_comment,
-        header_start: offset.into(),
-        extra_data_start: None,
-        central_header_start,
-        data_start: OnceLock::new(),
-        external_attributes: external_file_attributes,
-        large_file: false,
-        aes_mode: None,
-        aes_extra_data_start: 0,
-        extra_fields: Vec::new(),
-    };
+    #[test]
+    fn extract_entry_resumable() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let contents: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        #[cfg(feature = "deflate-flate2")]
+        let methods = [CompressionMethod::Stored, CompressionMethod::Deflated];
+        #[cfg(not(feature = "deflate-flate2"))]
+        let methods = [CompressionMethod::Stored];
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for method in methods {
+            let options = SimpleFileOptions::default().compression_method(method);
+            writer.start_file(format!("{method}"), options).unwrap();
+            writer.write_all(&contents).unwrap();
+        }
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let dir = TempDir::new("extract_entry_resumable").unwrap();
+        for i in 0..archive.len() {
+            let dest = dir.path().join(format!("{i}"));
+            // Simulate an interrupted extraction that left some garbage after what was written.
+            let mut partial = contents[..4000].to_vec();
+            partial.extend_from_slice(b"garbage");
+            std::fs::write(&dest, partial).unwrap();
+            let written = archive.extract_entry_resumable(i, &dest, 4000).unwrap();
+            assert_eq!(written, contents.len() as u64);
+            assert_eq!(std::fs::read(&dest).unwrap(), contents);
+            assert!(archive.extract_entry_resumable(i, &dest, 10_001).is_err());
+        }
+    }
+
+    #[test]
+    fn extract_entry_resumable_truncated() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("file", options).unwrap();
+        writer.write_all(&[b'a'; 1000]).unwrap();
+        let mut bytes = writer.finish().unwrap().into_inner();
+        // Claim the entry is larger than everything after its data in the archive
+        let central_header = bytes.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        for size_offset in [20, 24] {
+            let size = central_header + size_offset;
+            bytes[size..size + 4].copy_from_slice(&100_000u32.to_le_bytes());
+        }
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let dir = TempDir::new("extract_entry_resumable_truncated").unwrap();
+        let dest = dir.path().join("file");
+        std::fs::write(&dest, [b'a'; 500]).unwrap();
+        assert!(matches!(
+            archive.extract_entry_resumable(0, &dest, 500),
+            Err(ZipError::InvalidArchive(
+                "Stored entry data is shorter than its size"
+            ))
+        ));
+        let mut buf = vec![0u8; 200_000];
+        assert!(matches!(
+            archive.read_entry_at(0, 500, &mut buf),
+            Err(ZipError::InvalidArchive(
+                "Stored entry data is shorter than its size"
+            ))
+        ));
+        assert_eq!(archive.read_entry_at(0, 500, &mut buf[..100]).unwrap(), 100);
+    }
+
+    #[test]
+    fn entries_within() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(&[b'x'; 100]).unwrap();
+        }
+        let mut archive = writer.finish_into_readable().unwrap();
+        let second_end = {
+            let file = archive.by_index(1).unwrap();
+            file.data_start() + file.compressed_size()
+        };
+        assert_eq!(archive.entries_within(0).unwrap(), Vec::<usize>::new());
+        assert_eq!(archive.entries_within(second_end - 1).unwrap(), [0]);
+        assert_eq!(archive.entries_within(second_end).unwrap(), [0, 1]);
+        assert_eq!(archive.entries_within(u64::MAX).unwrap(), [0, 1, 2]);
+
+        // The available bytes end partway through the second entry's local header, in a file
+        // that's been allocated in full but not yet written past them
+        let (second_header, second_data) = {
+            let file = archive.by_index(1).unwrap();
+            (file.header_start(), file.data_start())
+        };
+        let complete = archive.into_inner().into_inner();
+        for available in second_header + 1..second_data {
+            let mut partial = complete.clone();
+            partial[available as usize..second_end as usize].fill(0);
+            let mut archive = ZipArchive::new(Cursor::new(partial)).unwrap();
+            assert_eq!(archive.entries_within(available).unwrap(), [0]);
+        }
+    }
+
+    #[test]
+    fn read_entry_at() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let contents: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        #[cfg(feature = "deflate-flate2")]
+        let methods = [CompressionMethod::Stored, CompressionMethod::Deflated];
+        #[cfg(not(feature = "deflate-flate2"))]
+        let methods = [CompressionMethod::Stored];
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for method in methods {
+            let options = SimpleFileOptions::default().compression_method(method);
+            writer.start_file(format!("{method}"), options).unwrap();
+            writer.write_all(&contents).unwrap();
+        }
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        for i in 0..archive.len() {
+            let mut buf = [0u8; 100];
+            assert_eq!(archive.read_entry_at(i, 5000, &mut buf).unwrap(), 100);
+            assert_eq!(buf, contents[5000..5100]);
+            assert_eq!(archive.read_entry_at(i, 9950, &mut buf).unwrap(), 50);
+            assert_eq!(buf[..50], contents[9950..]);
+            assert_eq!(archive.read_entry_at(i, 20_000, &mut buf).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn prepended_bytes_and_eocd_position() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("file.txt", SimpleFileOptions::default())
+            .unwrap();
+        let zip = writer.finish().unwrap().into_inner();
+        let eocd_len = 22;
+
+        let mut embedded = b"junk before".to_vec();
+        embedded.extend_from_slice(&zip);
+        let archive = ZipArchive::new(Cursor::new(embedded.clone())).unwrap();
+        assert_eq!(archive.prepended_bytes(), 11);
+        assert_eq!(archive.prepended_bytes(), archive.offset());
+        assert_eq!(archive.eocd_position(), (embedded.len() - eocd_len) as u64);
+    }
+
+    #[test]
+    fn local_header() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("file.txt", options).unwrap();
+        writer.write_all(b"hello").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let header = archive.local_header(0).unwrap();
+        let file = archive.by_index(0).unwrap();
+        assert_eq!(header.file_name, file.name_raw());
+        assert_eq!(header.compression_method, 0);
+        assert_eq!(header.crc32, file.crc32());
+        assert_eq!(header.compressed_size, 5);
+        assert_eq!(header.uncompressed_size, 5);
+        assert_eq!(header.version_needed, 10);
+        assert!(header.extra_field.is_empty());
+        drop(file);
+        assert!(matches!(
+            archive.local_header(1),
+            Err(ZipError::FileNotFound)
+        ));
+    }
+
+    #[test]
+    fn extract_with_options_on_duplicate() {
+        use super::{ExtractOptions, OnDuplicate};
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::fs;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in [
+            ("a.txt", "first"),
+            ("dir/../a.txt", "second"),
+            ("./a.txt", "third"),
+        ] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let mut archive = writer.finish_into_readable().unwrap();
+        let on_duplicate = |on_duplicate| ExtractOptions {
+            on_duplicate,
+            ..Default::default()
+        };
+
+        let dir = TempDir::new("overwrite").unwrap();
+        archive
+            .extract_with_options(dir.path(), on_duplicate(OnDuplicate::Overwrite))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "third"
+        );
+
+        let dir = TempDir::new("skip").unwrap();
+        archive
+            .extract_with_options(dir.path(), on_duplicate(OnDuplicate::Skip))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "first"
+        );
+
+        let dir = TempDir::new("rename").unwrap();
+        archive
+            .extract_with_options(dir.path(), on_duplicate(OnDuplicate::Rename))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "first"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a (1).txt")).unwrap(),
+            "second"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a (2).txt")).unwrap(),
+            "third"
+        );
+
+        let dir = TempDir::new("error").unwrap();
+        assert!(matches!(
+            archive.extract_with_options(dir.path(), on_duplicate(OnDuplicate::Error)),
+            Err(ZipError::InvalidArchive("Duplicate file path"))
+        ));
+    }
+
+    #[test]
+    fn extract_with_options_on_duplicate_counts_only_extracted_paths() {
+        use super::{ExtractOptions, OnDuplicate};
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::fs;
+        use std::io::Write;
+
+        let options = SimpleFileOptions::default();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("dir/../dir", options).unwrap();
+        writer.write_all(b"file named like a directory").unwrap();
+        // Without a password, this one fails, so the next one can have its path
+        writer
+            .start_file("a.txt", options.with_deprecated_encryption(b"password"))
+            .unwrap();
+        writer.write_all(b"encrypted").unwrap();
+        writer.start_file("./a.txt", options).unwrap();
+        writer.write_all(b"plain").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let dir = TempDir::new("rename").unwrap();
+        let failures = archive
+            .extract_with_options(
+                dir.path(),
+                ExtractOptions {
+                    on_duplicate: OnDuplicate::Rename,
+                    continue_on_error: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 2);
+        assert!(dir.path().join("dir").is_dir());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("dir (1)")).unwrap(),
+            "file named like a directory"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "plain"
+        );
+        assert!(!dir.path().join("a (1).txt").exists());
+    }
+
+    #[test]
+    fn manifest() {
+        use super::ManifestEntry;
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("b.txt", options).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.add_directory("a/", options).unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+
+        assert_eq!(
+            archive.manifest(),
+            [
+                ManifestEntry {
+                    name: "b.txt".to_string(),
+                    uncompressed_size: 5,
+                    compressed_size: 5,
+                    crc32: crc32fast::hash(b"hello"),
+                    compression: CompressionMethod::Stored,
+                    is_dir: false,
+                },
+                ManifestEntry {
+                    name: "a/".to_string(),
+                    uncompressed_size: 0,
+                    compressed_size: 0,
+                    crc32: 0,
+                    compression: CompressionMethod::Stored,
+                    is_dir: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_truncated() {
+        use super::Config;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("file.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.set_comment("a comment that gets cut off");
+        let mut zip = writer.finish().unwrap().into_inner();
+
+        let archive = ZipArchive::new(Cursor::new(zip.clone())).unwrap();
+        assert!(!archive.comment_truncated());
+        zip.truncate(zip.len() - 8);
+        assert!(ZipArchive::new(Cursor::new(zip.clone())).is_err());
+        let config = Config {
+            lenient: true,
+            ..Default::default()
+        };
+        let archive = ZipArchive::with_config(config, Cursor::new(zip)).unwrap();
+        assert!(archive.comment_truncated());
+        assert_eq!(archive.comment(), b"a comment that gets");
+    }
+
+    #[test]
+    fn copy_verbatim() {
+        let mut v = b"#!/bin/sh\nexit 0\n".to_vec();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        v.extend_from_slice(b"trailing data");
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        archive.by_index(0).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(archive.copy_verbatim(&mut out).unwrap(), v.len() as u64);
+        assert_eq!(out, v);
+        // The archive is still usable afterwards
+        assert_eq!(archive.by_index(0).unwrap().name(), "mimetype");
+    }
+
+    #[test]
+    fn empty_entries() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("dir/.keep", options).unwrap();
+        writer.start_file("dir/file.txt", options).unwrap();
+        writer.write_all(b"contents").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        assert_eq!(archive.empty_entries(), [1]);
+        assert!(!archive.by_index(0).unwrap().is_empty_file());
+        assert!(archive.by_index(1).unwrap().is_empty_file());
+        assert!(!archive.by_index(2).unwrap().is_empty_file());
+    }
+
+    #[test]
+    fn symlink_target_escapes() {
+        use super::symlink_target_escapes;
+        assert!(!symlink_target_escapes(b"foo"));
+        assert!(!symlink_target_escapes(b"a/../b/./c"));
+        assert!(symlink_target_escapes(b"../foo"));
+        assert!(symlink_target_escapes(b"a/../../foo"));
+        assert!(symlink_target_escapes(b"/etc/passwd"));
+        assert!(symlink_target_escapes(b"C:\\Windows"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extract_with_options_confines_symlinks() {
+        use super::ExtractOptions;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_symlink("inside", "dir/file", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .add_symlink("outside", "dir/../../file", SimpleFileOptions::default())
+            .unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let tempdir = TempDir::new("extract_confines_symlinks").unwrap();
+        let options = ExtractOptions {
+            confine_symlinks: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            archive.extract_with_options(tempdir.path(), options),
+            Err(ZipError::InvalidArchive(_))
+        ));
+        let inside = tempdir.path().join("inside");
+        assert!(inside.is_symlink());
+        assert_eq!(
+            std::fs::read_link(inside).unwrap(),
+            tempdir.path().join("dir/file")
+        );
+        assert!(!tempdir.path().join("outside").exists());
+    }
+
+    #[test]
+    fn entries() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{Read, Write};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("dir/", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .start_file("dir/a.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"a").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let mut entries = archive.entries();
+        assert_eq!(entries.remaining(), 2);
+        let mut seen = Vec::new();
+        while let Some(file) = entries.next_entry() {
+            let mut file = file.unwrap();
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).unwrap();
+            seen.push((file.name().to_string(), contents));
+        }
+        assert_eq!(entries.remaining(), 0);
+        assert_eq!(
+            seen,
+            vec![
+                ("dir/".to_string(), String::new()),
+                ("dir/a.txt".to_string(), "a".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn zip64_padded_end_record() {
+        use std::io::Read;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_padded_end_record.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut contents = String::new();
+        archive
+            .by_name("padded.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "Hello, padded zip64 end of central directory!\n");
+    }
+
+    #[test]
+    fn identity() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::collections::HashSet;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in [("a.txt", "a"), ("b.txt", "a"), ("dir/a.txt", "a")] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let mut first = writer.finish_into_readable().unwrap();
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in [("dir/a.txt", "a"), ("a.txt", "b")] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let mut second = writer.finish_into_readable().unwrap();
+
+        let identities: HashSet<_> = (0..first.len())
+            .map(|i| first.by_index(i).unwrap().identity())
+            .collect();
+        assert_eq!(identities.len(), 3);
+        let unchanged: Vec<_> = (0..second.len())
+            .map(|i| second.by_index(i).unwrap().identity())
+            .filter(|identity| identities.contains(identity))
+            .map(|identity| identity.name)
+            .collect();
+        assert_eq!(unchanged, vec!["dir/a.txt".to_string()]);
+    }
+
+    #[test]
+    fn by_name_ignore_case() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["Docs/README.txt", "docs/readme.TXT", "ÜBER.txt"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+        }
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        assert_eq!(
+            archive.index_for_name_ignore_case("docs/readme.txt"),
+            Some(0)
+        );
+        assert_eq!(archive.index_for_name_ignore_case("über.TXT"), Some(2));
+        assert_eq!(archive.index_for_name_ignore_case("docs"), None);
+        assert_eq!(
+            archive
+                .by_name_ignore_case("DOCS/README.TXT")
+                .unwrap()
+                .name(),
+            "Docs/README.txt"
+        );
+        assert!(matches!(
+            archive.by_name_ignore_case("missing.txt"),
+            Err(ZipError::FileNotFound)
+        ));
+    }
+
+    #[test]
+    fn indices_sorted_by_size() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("medium", options).unwrap();
+        writer.write_all(b"12345").unwrap();
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("large", options).unwrap();
+        writer.write_all(b"1234567890").unwrap();
+        writer.start_file("small", options).unwrap();
+        writer.start_file("also_medium", options).unwrap();
+        writer.write_all(b"abcde").unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+
+        assert_eq!(archive.indices_sorted_by_size(true), [3, 0, 4, 2]);
+        assert_eq!(archive.indices_sorted_by_size(false), [2, 0, 4, 3]);
+    }
+
+    #[test]
+    fn extract_with_options_reports_progress() {
+        use super::ExtractOptions;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("dir/big", options).unwrap();
+        writer.write_all(&vec![0u8; 3 << 20]).unwrap();
+        writer.start_file("small", options).unwrap();
+        writer.write_all(b"small").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let tempdir = TempDir::new("extract_reports_progress").unwrap();
+        let mut reports = Vec::new();
+        let options = ExtractOptions {
+            progress: Some(&mut |progress| {
+                assert_eq!(progress.total_files, 3);
+                reports.push((
+                    progress.file_number,
+                    progress.name.to_string(),
+                    progress.bytes_written,
+                    progress.finished,
+                ));
+            }),
+            ..Default::default()
+        };
+        archive
+            .extract_with_options(tempdir.path(), options)
+            .unwrap();
+        let finished: Vec<_> = reports.iter().filter(|report| report.3).collect();
+        assert_eq!(
+            finished,
+            [
+                &(0, "dir/".to_string(), 0, true),
+                &(1, "dir/big".to_string(), 3 << 20, true),
+                &(2, "small".to_string(), 5, true)
+            ]
+        );
+        let partial: Vec<_> = reports.iter().filter(|report| !report.3).collect();
+        assert!(partial.len() >= 2);
+        assert!(partial
+            .iter()
+            .all(|report| report.0 == 1 && report.2 <= 3 << 20));
+    }
+
+    #[test]
+    fn read_at() {
+        let mut v = b"prefix".to_vec();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let (header_start, data_start, size) = {
+            let file = archive.by_index(0).unwrap();
+            (
+                file.header_start(),
+                file.data_start(),
+                file.compressed_size(),
+            )
+        };
+
+        let mut signature = [0u8; 4];
+        assert_eq!(archive.read_at(header_start, &mut signature).unwrap(), 4);
+        assert_eq!(signature, *b"PK\x03\x04");
+        let mut data = vec![0u8; size as usize];
+        archive.read_at(data_start, &mut data).unwrap();
+        assert_eq!(data, b"application/vnd.oasis.opendocument.text");
+
+        let end = archive.eocd_position() + 22 + archive.comment().len() as u64;
+        assert_eq!(archive.read_at(end - 2, &mut signature).unwrap(), 2);
+        assert_eq!(archive.by_index(0).unwrap().name(), "mimetype");
+    }
+
+    #[test]
+    fn total_compressed_size() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/mixed_compression_methods.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let expected: u64 = (0..archive.len())
+            .map(|i| archive.by_index_raw(i).unwrap().compressed_size())
+            .sum();
+        assert_eq!(archive.total_compressed_size(), expected as u128);
+        assert_eq!(archive.total_compressed_size(), 5 + 13 + 5);
+    }
+
+    #[test]
+    fn decompressed_size_with_data_descriptor() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/data_descriptor.zip"));
+        let archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(archive.decompressed_size(), Some(12));
+        assert_eq!(archive.decompressed_size_strict(), None);
+    }
+
+    #[test]
+    fn verify_entry_crc() {
+        let mut v = include_bytes!("../tests/data/mimetype.zip").to_vec();
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        let contents = b"application/vnd.oasis.opendocument.text";
+        let crc = crc32fast::hash(contents);
+        assert!(archive.verify_entry_crc(0, crc).unwrap());
+        assert!(!archive.verify_entry_crc(0, crc ^ 1).unwrap());
+
+        // Forge the stored CRC-32 in both headers, so only the external value catches it
+        let data_start = archive.by_index(0).unwrap().data_start() as usize;
+        v[data_start] ^= 1;
+        let mut tampered = contents.to_vec();
+        tampered[0] ^= 1;
+        let forged = crc32fast::hash(&tampered).to_le_bytes();
+        v[14..18].copy_from_slice(&forged);
+        let central = archive.by_index(0).unwrap().central_header_start() as usize;
+        v[central + 16..central + 20].copy_from_slice(&forged);
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert!(!archive.verify_entry_crc(0, crc).unwrap());
+
+        // Only the stored CRC-32 changed: the data still matches
+        let mut v = include_bytes!("../tests/data/mimetype.zip").to_vec();
+        v[central + 16] ^= 1;
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert!(archive.verify_entry_crc(0, crc).unwrap());
+    }
+
+    #[cfg(all(feature = "digest", feature = "aes-crypto"))]
+    #[test]
+    fn verify_entry_digest() {
+        use sha1::{Digest, Sha1};
+
+        let mut v = include_bytes!("../tests/data/mimetype.zip").to_vec();
+        let hash = Sha1::digest(b"application/vnd.oasis.opendocument.text");
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        assert!(archive.verify_entry_digest::<Sha1>(0, &hash).unwrap());
+        assert!(!archive.verify_entry_digest::<Sha1>(0, &hash[1..]).unwrap());
+
+        // The stored CRC-32 doesn't matter
+        let central = archive.by_index(0).unwrap().central_header_start() as usize;
+        v[central + 16] ^= 1;
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert!(archive.verify_entry_digest::<Sha1>(0, &hash).unwrap());
+    }
+
+    #[test]
+    fn unicode_path_extra_field() {
+        use crate::extra_fields::ExtraField;
+        use std::io::Read;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/unicode_path_extra_field.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let mut file = archive.by_name("日本語.txt").unwrap();
+        // "日本語.txt" in Shift JIS
+        assert_eq!(file.name_raw(), b"\x93\xfa\x96\x7b\x8c\xea.txt");
+        assert!(matches!(
+            file.extra_data_fields().next(),
+            Some(ExtraField::UnicodePath(payload)) if payload.ends_with("日本語.txt".as_bytes())
+        ));
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "unicode path\n");
+        drop(file);
+
+        // The field's CRC-32 doesn't match the name in the header, so it's ignored
+        assert_eq!(archive.by_index(1).unwrap().name(), "stale.txt");
+    }
+
+    #[test]
+    fn by_name_normalized() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("a/b.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .add_directory("a/c/", SimpleFileOptions::default())
+            .unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        assert!(matches!(
+            archive.by_name("a\\b.txt"),
+            Err(ZipError::FileNotFound)
+        ));
+        for query in ["a\\b.txt", "a/b.txt", "./a/b.txt", "/a/c/../b.txt"] {
+            assert_eq!(archive.index_for_name_normalized(query), Some(0), "{query}");
+            assert_eq!(archive.by_name_normalized(query).unwrap().name(), "a/b.txt");
+        }
+        assert_eq!(archive.index_for_name_normalized("a\\c\\"), Some(1));
+    }
+
+    #[test]
+    fn from_parts() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{Read, Write};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment("comment");
+        for name in ["a.txt", "b.txt"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        let archive = writer.finish_into_readable().unwrap();
+        let index = archive.index();
+        let mut data = archive.into_inner().into_inner();
+        let central_directory_start = {
+            let mut archive = ZipArchive::new(Cursor::new(data.clone())).unwrap();
+            let start = archive.by_index(0).unwrap().central_header_start();
+            start as usize
+        };
+        data.truncate(central_directory_start);
+
+        let mut archive = ZipArchive::from_parts(index, Cursor::new(data));
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive.comment(), b"comment");
+        let mut contents = String::new();
+        archive
+            .by_name("b.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "b.txt");
+    }
+
+    #[test]
+    fn unix_uid_gid() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/unix_uid_gid.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let ids: Vec<_> = (0..archive.len())
+            .map(|i| {
+                let file = archive.by_index(i).unwrap();
+                (file.unix_uid(), file.unix_gid())
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            [(Some(1000), Some(100)), (Some(0), Some(0)), (None, None)]
+        );
+
+        // root.txt's UID is a single zero byte; an empty UID is invalid rather than zero
+        let mut v = include_bytes!("../tests/data/unix_uid_gid.zip").to_vec();
+        let field = [0x75, 0x78, 0x06, 0x00, 0x01, 0x01, 0x00, 0x02, 0x00, 0x00];
+        let empty_uid = [0x75, 0x78, 0x06, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00];
+        let mut replaced = 0;
+        for start in 0..v.len() - field.len() {
+            if v[start..start + field.len()] == field {
+                v[start..start + field.len()].copy_from_slice(&empty_uid);
+                replaced += 1;
+            }
+        }
+        assert_eq!(replaced, 2);
+        assert!(matches!(
+            ZipArchive::new(Cursor::new(v)),
+            Err(ZipError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    fn enclosed_name_rejects_relative_directory_names() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let names = [".", "..", "./", "../", "a/..", "a/../", "./a", "a/./"];
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in names {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+        }
+        let mut archive = writer.finish_into_readable().unwrap();
+        let enclosed: Vec<_> = (0..archive.len())
+            .map(|i| archive.by_index(i).unwrap().enclosed_name())
+            .collect();
+        assert_eq!(
+            enclosed,
+            [
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("./a".into()),
+                Some("a/./".into())
+            ]
+        );
+    }
+
+    #[cfg(feature = "filetime")]
+    #[test]
+    fn extract_with_options_sets_mtime() {
+        use super::ExtractOptions;
+        use crate::types::DateTime;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let modified = DateTime::from_date_and_time(2021, 7, 4, 12, 34, 56).unwrap();
+        let options = SimpleFileOptions::default().last_modified_time(modified);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("dir/file.txt", options).unwrap();
+        writer.write_all(b"contents").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let tempdir = TempDir::new("extract_with_options_sets_mtime").unwrap();
+        let options = ExtractOptions {
+            set_mtime: true,
+            ..Default::default()
+        };
+        archive
+            .extract_with_options(tempdir.path(), options)
+            .unwrap();
+        for path in ["dir", "dir/file.txt"] {
+            let metadata = std::fs::metadata(tempdir.path().join(path)).unwrap();
+            let mtime = filetime::FileTime::from_last_modification_time(&metadata);
+            // MS-DOS times have a 2-second granularity
+            assert!(
+                (mtime.unix_seconds() - modified.unix_timestamp()).abs() < 2,
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn extract_with_options_skips_unsupported() {
+        use super::ExtractOptions;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/mixed_compression_methods.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
 
-    match parse_extra_field(&mut result) {
-        Ok(..) | Err(ZipError::Io(..)) => {}
-        Err(e) => return Err(e),
-    }
+        let tempdir = TempDir::new("extract_with_options_fails_fast").unwrap();
+        assert!(archive
+            .extract_with_options(tempdir.path(), ExtractOptions::default())
+            .is_err());
 
-    let aes_enabled = result.compression_method == CompressionMethod::AES;
-    if aes_enabled && result.aes_mode.is_none() {
-        return Err(ZipError::InvalidArchive(
-            "AES encryption without AES extra data field",
+        let tempdir = TempDir::new("extract_with_options_skips_unsupported").unwrap();
+        let options = ExtractOptions {
+            skip_unsupported: true,
+            ..Default::default()
+        };
+        let skipped = archive
+            .extract_with_options(tempdir.path(), options)
+            .unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(archive.name_for_index(skipped[0].0), Some("b.txt"));
+        assert!(matches!(
+            skipped[0].1,
+            ZipError::UnsupportedCompression { method: 95, .. }
         ));
+        let read = |name| std::fs::read(tempdir.path().join(name)).unwrap();
+        assert_eq!(read("a.txt"), b"first");
+        assert_eq!(read("c.txt"), b"third");
+        assert!(!tempdir.path().join("b.txt").exists());
     }
 
-    // Account for shifted zip offsets.
-    result.header_start = result
-        .header_start
-        .checked_add(archive_offset)
-        .ok_or(ZipError::InvalidArchive("Archive header is too large"))?;
+    #[test]
+    fn extract_with_options_forbids_unsafe_names() {
+        use super::ExtractOptions;
 
-    Ok(result)
-}
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/unsafe_names.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
 
-fn parse_extra_field(file: &mut ZipFileData) -> ZipResult<()> {
-    let Some(extra_field) = &file.extra_field else {
-        return Ok(());
-    };
-    let mut reader = io::Cursor::new(extra_field.as_ref());
+        // By default, `a/../b.txt` is extracted as `b.txt`, and `C:x.txt` as a file of that name
+        #[cfg(unix)]
+        {
+            let tempdir = TempDir::new("extract_unsafe_names_lenient").unwrap();
+            archive
+                .extract_with_options(tempdir.path(), ExtractOptions::default())
+                .unwrap();
+            assert!(tempdir.path().join("b.txt").exists());
+        }
 
-    /* TODO: codify this structure into Zip64ExtraFieldBlock fields! */
-    while (reader.position() as usize) < extra_field.len() {
-        let kind = reader.read_u16_le()?;
-        let len = reader.read_u16_le()?;
-        let mut len_left = len as i64;
-        match kind {
-            // Zip64 extended information extra field
-            0x0001 => {
-                if file.uncompressed_size == spec::ZIP64_BYTES_THR {
-                    file.large_file = true;
-                    file.uncompressed_size = reader.read_u64_le()?;
-                    len_left -= 8;
-                }
-                if file.compressed_size == spec::ZIP64_BYTES_THR {
-                    file.large_file = true;
-                    file.compressed_size = reader.read_u64_le()?;
-                    len_left -= 8;
-                }
-                if file.header_start == spec::ZIP64_BYTES_THR {
-                    file.header_start = reader.read_u64_le()?;
-                    len_left -= 8;
-                }
+        for (forbid_parent_refs, message) in [
+            (true, "Parent directory reference in file name"),
+            (false, "Absolute path in file name"),
+        ] {
+            let options = || ExtractOptions {
+                forbid_parent_refs,
+                forbid_absolute: !forbid_parent_refs,
+                ..Default::default()
+            };
+            let tempdir = TempDir::new("extract_unsafe_names_strict").unwrap();
+            match archive.extract_with_options(tempdir.path(), options()) {
+                Err(ZipError::InvalidArchive(msg)) => assert_eq!(msg, message),
+                other => panic!("unexpected result: {other:?}"),
             }
-            0x9901 => {
-                // AES
-                if len != 7 {
-                    return Err(ZipError::UnsupportedArchive(
-                        "AES extra data field has an unsupported length",
-                    ));
-                }
-                let vendor_version = reader.read_u16_le()?;
-                let vendor_id = reader.read_u16_le()?;
-                let mut out = [0u8];
-                reader.read_exact(&mut out)?;
-                let aes_mode = out[0];
-                let compression_method = CompressionMethod::parse_from_u16(reader.read_u16_le()?);
+            // Nothing is extracted, not even the entries before the offending one
+            assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 0);
 
-                if vendor_id != 0x4541 {
-                    return Err(ZipError::InvalidArchive("Invalid AES vendor"));
-                }
-                let vendor_version = match vendor_version {
-                    0x0001 => AesVendorVersion::Ae1,
-                    0x0002 => AesVendorVersion::Ae2,
-                    _ => return Err(ZipError::InvalidArchive("Invalid AES vendor version")),
-                };
-                match aes_mode {
-                    0x01 => {
-                        file.aes_mode = Some((AesMode::Aes128, vendor_version, compression_method))
-                    }
-                    0x02 => {
-                        file.aes_mode = Some((AesMode::Aes192, vendor_version, compression_method))
-                    }
-                    0x03 => {
-                        file.aes_mode = Some((AesMode::Aes256, vendor_version, compression_method))
-                    }
-                    _ => return Err(ZipError::InvalidArchive("Invalid AES encryption strength")),
-                };
-                file.compression_method = compression_method;
+            // Entries that haven't been parsed yet are checked too
+            let mut lazy = ZipArchive::new_lazy(Cursor::new(
+                include_bytes!("../tests/data/unsafe_names.zip").to_vec(),
+            ))
+            .unwrap();
+            let tempdir = TempDir::new("extract_unsafe_names_lazy").unwrap();
+            match lazy.extract_with_options(tempdir.path(), options()) {
+                Err(ZipError::InvalidArchive(msg)) => assert_eq!(msg, message),
+                other => panic!("unexpected result: {other:?}"),
             }
-            0x5455 => {
-                // extended timestamp
-                // https://libzip.org/specifications/extrafld.txt
+            assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 0);
+        }
+    }
 
-                file.extra_fields.push(ExtraField::ExtendedTimestamp(
-                    ExtendedTimestamp::try_from_reader(&mut reader, len)?,
-                ));
+    #[test]
+    fn is_symlink_uses_file_type_bits() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
 
-                // the reader for ExtendedTimestamp consumes `len` bytes
-                len_left = 0;
-            }
-            _ => {
-                // Other fields are ignored
-            }
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("entry", SimpleFileOptions::default())
+            .unwrap();
+        let mut zip = writer.finish().unwrap().into_inner();
+        let central_header_start = ZipArchive::new(Cursor::new(zip.clone()))
+            .unwrap()
+            .by_index(0)
+            .unwrap()
+            .central_header_start() as usize;
+
+        for (mode, is_symlink) in [
+            (0o120777, true),
+            (0o100644, false),
+            (0o040755, false),
+            // Socket and whiteout both share bits with S_IFLNK
+            (0o140755, false),
+            (0o160000, false),
+        ] {
+            let attributes: u32 = mode << 16;
+            zip[central_header_start + 38..central_header_start + 42]
+                .copy_from_slice(&attributes.to_le_bytes());
+            let mut archive = ZipArchive::new(Cursor::new(zip.clone())).unwrap();
+            let file = archive.by_index(0).unwrap();
+            assert_eq!(file.is_symlink(), is_symlink, "{mode:o}");
+            // Directories are recognized by their name, not their mode
+            assert_eq!(file.is_file(), !is_symlink, "{mode:o}");
         }
+    }
 
-        // We could also check for < 0 to check for errors
-        if len_left > 0 {
-            reader.seek(io::SeekFrom::Current(len_left))?;
-        }
+    #[cfg(all(feature = "digest", feature = "aes-crypto"))]
+    #[test]
+    fn content_digest() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use sha1::Sha1;
+        use std::io::Write;
+
+        let archive = |files: &[(&str, &str)], method| {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            let options = SimpleFileOptions::default().compression_method(method);
+            writer.add_directory("dir/", options).unwrap();
+            for (name, contents) in files {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(contents.as_bytes()).unwrap();
+            }
+            writer.finish_into_readable().unwrap()
+        };
+        #[cfg(feature = "deflate-flate2")]
+        let other_method = CompressionMethod::Deflated;
+        #[cfg(not(feature = "deflate-flate2"))]
+        let other_method = CompressionMethod::Stored;
+
+        let digest = archive(&[("a", "1"), ("dir/b", "2")], CompressionMethod::Stored)
+            .content_digest::<Sha1>()
+            .unwrap();
+        let reordered = archive(&[("dir/b", "2"), ("a", "1")], other_method)
+            .content_digest::<Sha1>()
+            .unwrap();
+        assert_eq!(digest, reordered);
+        // Moving a byte from the contents to the name must change the hash
+        let changed = archive(&[("a1", ""), ("dir/b", "2")], CompressionMethod::Stored)
+            .content_digest::<Sha1>()
+            .unwrap();
+        assert_ne!(digest, changed);
     }
-    Ok(())
-}
 
-/// Methods for retrieving information on zip files
-impl<'a> ZipFile<'a> {
-    fn get_reader(&mut self) -> ZipResult<&mut ZipFileReader<'a>> {
-        if let ZipFileReader::NoReader = self.reader {
-            let data = &self.data;
-            let crypto_reader = self.crypto_reader.take().expect("Invalid reader state");
-            self.reader = make_reader(data.compression_method, data.crc32, crypto_reader)?;
-        }
-        Ok(&mut self.reader)
+    #[test]
+    fn explicit_and_implied_directories() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("a/", options).unwrap();
+        writer.start_file("a/b/c/file.txt", options).unwrap();
+        writer.add_directory("a/b/c/", options).unwrap();
+        writer.add_directory("d/e/", options).unwrap();
+        writer.start_file("top.txt", options).unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+
+        assert_eq!(archive.explicit_directories(), [0, 2, 3]);
+        assert_eq!(archive.implied_directories(), ["a/b/", "d/"]);
     }
 
-    pub(crate) fn get_raw_reader(&mut self) -> &mut dyn Read {
-        if let ZipFileReader::NoReader = self.reader {
-            let crypto_reader = self.crypto_reader.take().expect("Invalid reader state");
-            self.reader = ZipFileReader::Raw(crypto_reader.into_inner())
-        }
-        &mut self.reader
+    #[test]
+    fn trailing_backslash_is_not_a_directory() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("weird\\", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .add_directory("dir/", SimpleFileOptions::default())
+            .unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let file = archive.by_name("weird\\").unwrap();
+        assert!(!file.is_dir());
+        assert!(file.is_file());
+        drop(file);
+        assert!(archive.by_name("dir/").unwrap().is_dir());
     }
 
-    /// Get the version of the file
-    pub fn version_made_by(&self) -> (u8, u8) {
-        (
-            self.data.version_made_by / 10,
-            self.data.version_made_by % 10,
-        )
+    #[test]
+    fn add_directory_after_trailing_backslash() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("foo\\", SimpleFileOptions::default())
+            .unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+        assert_eq!(archive.file_names().collect::<Vec<_>>(), ["foo\\/"]);
+        assert!(archive.by_index(0).unwrap().is_dir());
     }
 
-    /// Get the name of the file
-    ///
-    /// # Warnings
-    ///
-    /// It is dangerous to use this name directly when extracting an archive.
-    /// It may contain an absolute path (`/etc/shadow`), or break out of the
-    /// current directory (`../runtime`). Carelessly writing to these paths
-    /// allows an attacker to craft a ZIP archive that will overwrite critical
-    /// files.
-    ///
-    /// You can use the [`ZipFile::enclosed_name`] method to validate the name
-    /// as a safe path.
-    pub fn name(&self) -> &str {
-        &self.data.file_name
+    #[test]
+    fn new_expecting() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["a.txt", "b.txt"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+        }
+        let zip = writer.finish().unwrap().into_inner();
+
+        assert_eq!(
+            ZipArchive::new_expecting(Cursor::new(&zip), 2)
+                .unwrap()
+                .len(),
+            2
+        );
+        for expected in [0, 1, 3] {
+            assert!(matches!(
+                ZipArchive::new_expecting(Cursor::new(&zip), expected),
+                Err(ZipError::InvalidArchive(_))
+            ));
+        }
     }
 
-    /// Get the name of the file, in the raw (internal) byte representation.
-    ///
-    /// The encoding of this data is currently undefined.
-    pub fn name_raw(&self) -> &[u8] {
-        &self.data.file_name_raw
+    #[test]
+    fn verify_crc32() {
+        use crate::write::SimpleFileOptions;
+        use crate::CompressionMethod;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file(
+                "a.txt",
+                SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+            )
+            .unwrap();
+        writer.write_all(b"Hello, world!").unwrap();
+        let mut zip = writer.finish().unwrap().into_inner();
+
+        let mut archive = ZipArchive::new(Cursor::new(zip.clone())).unwrap();
+        assert!(archive.by_index(0).unwrap().verify_crc32().unwrap());
+        assert!(archive.by_index_raw(0).unwrap().verify_crc32().unwrap());
+        let crc_pos = archive.by_index(0).unwrap().central_header_start() as usize + 16;
+
+        zip[crc_pos] ^= 0xff;
+        let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+        assert!(!archive.by_index(0).unwrap().verify_crc32().unwrap());
+        assert!(!archive.by_index_raw(0).unwrap().verify_crc32().unwrap());
     }
 
-    /// Get the name of the file in a sanitized form. It truncates the name to the first NULL byte,
-    /// removes a leading '/' and removes '..' parts.
-    #[deprecated(
-        since = "0.5.7",
-        note = "by stripping `..`s from the path, the meaning of paths can change.
-                `mangled_name` can be used if this behaviour is desirable"
-    )]
-    pub fn sanitized_name(&self) -> PathBuf {
-        self.mangled_name()
+    #[test]
+    fn by_index_dual() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{Read, Write};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("a.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(&b"Hello, world!\n".repeat(100)).unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let mut raw = Vec::new();
+        archive
+            .by_index_raw(0)
+            .unwrap()
+            .read_to_end(&mut raw)
+            .unwrap();
+
+        let mut dual = archive.by_index_dual(0).unwrap();
+        assert_eq!(dual.raw(), raw);
+        for _ in 0..2 {
+            let mut file = dual.decompressed().unwrap();
+            assert_eq!(file.name(), "a.txt");
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents).unwrap();
+            assert_eq!(contents, b"Hello, world!\n".repeat(100));
+        }
     }
 
-    /// Rewrite the path, ignoring any path components with special meaning.
-    ///
-    /// - Absolute paths are made relative
-    /// - [`ParentDir`]s are ignored
-    /// - Truncates the filename at a NULL byte
-    ///
-    /// This is appropriate if you need to be able to extract *something* from
-    /// any archive, but will easily misrepresent trivial paths like
-    /// `foo/../bar` as `foo/bar` (instead of `bar`). Because of this,
-    /// [`ZipFile::enclosed_name`] is the better option in most scenarios.
-    ///
-    /// [`ParentDir`]: `Component::ParentDir`
-    pub fn mangled_name(&self) -> PathBuf {
-        self.data.file_name_sanitized()
+    #[test]
+    fn compression_method_raw() {
+        use crate::CompressionMethod;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/unknown_compression_method.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let file = archive.by_index_raw(0).unwrap();
+        assert_eq!(file.compression_method_raw(), 200);
+        #[allow(deprecated)]
+        let unsupported = CompressionMethod::Unsupported(200);
+        assert_eq!(file.compression(), unsupported);
+        drop(file);
+        assert!(matches!(
+            archive.by_index(0),
+            Err(ZipError::UnsupportedCompression { method: 200, .. })
+        ));
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/xz.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(
+            archive.by_index_raw(0).unwrap().compression_method_raw(),
+            95
+        );
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/aes_archive.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(
+            archive.by_index_raw(0).unwrap().compression_method_raw(),
+            99
+        );
+
+        #[cfg(feature = "aes-crypto")]
+        {
+            use crate::write::SimpleFileOptions;
+            use crate::{AesMode, ZipWriter};
+            use std::io::Write;
+
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            writer
+                .start_file(
+                    "secret.txt",
+                    SimpleFileOptions::default()
+                        .compression_method(CompressionMethod::Stored)
+                        .with_aes_encryption(AesMode::Aes256, "password"),
+                )
+                .unwrap();
+            writer.write_all(b"Top secret").unwrap();
+            let mut archive = writer.finish_into_readable().unwrap();
+            assert_eq!(
+                archive.by_index_raw(0).unwrap().compression_method_raw(),
+                99
+            );
+            let data = archive.into_inner().into_inner();
+            let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+            let file = archive.by_index_raw(0).unwrap();
+            assert_eq!(file.compression_method_raw(), 99);
+            assert_eq!(file.compression(), CompressionMethod::Stored);
+        }
     }
 
-    /// Ensure the file path is safe to use as a [`Path`].
-    ///
-    /// - It can't contain NULL bytes
-    /// - It can't resolve to a path outside the current directory
-    ///   > `foo/../bar` is fine, `foo/../../bar` is not.
-    /// - It can't be an absolute path
-    ///
-    /// This will read well-formed ZIP files correctly, and is resistant
-    /// to path-based exploits. It is recommended over
-    /// [`ZipFile::mangled_name`].
-    pub fn enclosed_name(&self) -> Option<PathBuf> {
-        self.data.enclosed_name()
+    #[test]
+    fn zip64_sentinel_without_extra_field() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/zip64_sentinel_without_extra_field.zip"
+        ));
+        assert!(matches!(
+            ZipArchive::new(Cursor::new(v.clone())),
+            Err(ZipError::InvalidArchive(_))
+        ));
+
+        // The central directory follows the entry's 30-byte local header, name and data
+        let mut reader = Cursor::new(v);
+        reader.set_position(30 + 5 + 5);
+        assert!(matches!(
+            super::central_header_to_zip_file(&mut reader, 0),
+            Err(ZipError::InvalidArchive(
+                "zip64 size sentinel without extra field"
+            ))
+        ));
     }
 
-    /// Get the comment of the file
-    pub fn comment(&self) -> &str {
-        &self.data.file_comment
+    #[test]
+    fn extract_entry_checked() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("a.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"Hello, world!").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let mut out = Vec::new();
+        archive.extract_entry_checked(0, &mut out, 13).unwrap();
+        assert_eq!(out, b"Hello, world!");
+        for expected_len in [0, 12, 14] {
+            assert!(matches!(
+                archive.extract_entry_checked(0, &mut Vec::new(), expected_len),
+                Err(ZipError::InvalidArchive(_))
+            ));
+        }
     }
 
-    /// Get the compression method used to store the file
-    pub fn compression(&self) -> CompressionMethod {
-        self.data.compression_method
+    #[test]
+    fn file_metadata() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let options = SimpleFileOptions::default().unix_permissions(0o640);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("dir/", options).unwrap();
+        writer.start_file("dir/a.txt", options).unwrap();
+        writer.write_all(b"Hello, world!").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        assert!(archive.file_metadata("missing").is_none());
+        for name in ["dir/", "dir/a.txt"] {
+            let metadata = archive.file_metadata(name).unwrap();
+            let file = archive.by_name(name).unwrap();
+            assert_eq!(metadata.name, file.name());
+            assert_eq!(metadata.size, file.size());
+            assert_eq!(metadata.compressed_size, file.compressed_size());
+            assert_eq!(metadata.crc32, file.crc32());
+            assert_eq!(metadata.compression, file.compression());
+            assert_eq!(metadata.last_modified, file.last_modified());
+            assert_eq!(metadata.is_dir, file.is_dir());
+            assert_eq!(metadata.unix_mode, file.unix_mode());
+        }
+    }
+
+    #[test]
+    fn eocd_candidates() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
+        let archive_len = v.len() as u64;
+        let candidates = ZipArchive::eocd_candidates(&mut Cursor::new(v)).unwrap();
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert!(!candidate.zip64);
+        assert_eq!(candidate.position, archive_len - 22);
+        assert_eq!(candidate.number_of_files, 1);
+        assert_eq!(
+            candidate.directory_start,
+            Some(candidate.central_directory_offset)
+        );
+        assert!(candidate.rejection.is_none());
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_demo.zip"));
+        let mut reader = Cursor::new(v);
+        let candidates = ZipArchive::eocd_candidates(&mut reader).unwrap();
+        let archive = ZipArchive::new(reader).unwrap();
+        assert!(!candidates[0].zip64);
+        let zip64: Vec<_> = candidates.iter().filter(|c| c.zip64).collect();
+        assert_eq!(zip64.len(), 1);
+        assert_eq!(zip64[0].number_of_files, archive.len() as u64);
+        assert!(zip64[0].rejection.is_none());
+
+        // A locator whose ZIP64 record is missing is reported rather than ignored
+        let mut v = include_bytes!("../tests/data/zip64_demo.zip").to_vec();
+        let zip64_end = candidates.iter().find(|c| c.zip64).unwrap().position as usize;
+        v[zip64_end] = 0;
+        let candidates = ZipArchive::eocd_candidates(&mut Cursor::new(v)).unwrap();
+        let zip64: Vec<_> = candidates.iter().filter(|c| c.zip64).collect();
+        assert_eq!(zip64.len(), 1);
+        assert_eq!(zip64[0].directory_start, None);
+        assert!(matches!(
+            zip64[0].rejection,
+            Some(ZipError::InvalidArchive(_))
+        ));
     }
 
-    /// Get the size of the file, in bytes, in the archive
-    pub fn compressed_size(&self) -> u64 {
-        self.data.compressed_size
-    }
+    #[test]
+    fn extract_file_to() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("plain.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"Hello, world!").unwrap();
+        writer
+            .start_file(
+                "secret.txt",
+                SimpleFileOptions::default().with_deprecated_encryption(b"password"),
+            )
+            .unwrap();
+        writer.write_all(b"Top secret").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
 
-    /// Get the size of the file, in bytes, when uncompressed
-    pub fn size(&self) -> u64 {
-        self.data.uncompressed_size
-    }
+        let mut out = Vec::new();
+        assert_eq!(archive.extract_file_to("plain.txt", &mut out).unwrap(), 13);
+        assert_eq!(out, b"Hello, world!");
 
-    /// Get the time the file was last modified
-    pub fn last_modified(&self) -> Option<DateTime> {
-        self.data.last_modified_time
-    }
-    /// Returns whether the file is actually a directory
-    pub fn is_dir(&self) -> bool {
-        is_dir(self.name())
+        let mut out = Vec::new();
+        assert!(archive.extract_file_to("secret.txt", &mut out).is_err());
+        assert_eq!(
+            archive
+                .extract_file_to_decrypt("secret.txt", b"password", &mut out)
+                .unwrap(),
+            10
+        );
+        assert_eq!(out, b"Top secret");
+        assert!(matches!(
+            archive.extract_file_to("missing.txt", &mut out),
+            Err(ZipError::FileNotFound)
+        ));
     }
 
-    /// Returns whether the file is actually a symbolic link
-    pub fn is_symlink(&self) -> bool {
-        self.unix_mode()
-            .is_some_and(|mode| mode & S_IFLNK == S_IFLNK)
-    }
+    #[test]
+    fn aes_nested_method() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/aes_nested_method.zip"));
+        assert!(matches!(
+            ZipArchive::new(Cursor::new(v.clone())),
+            Err(ZipError::InvalidArchive(_))
+        ));
 
-    /// Returns whether the file is a normal file (i.e. not a directory or symlink)
-    pub fn is_file(&self) -> bool {
-        !self.is_dir() && !self.is_symlink()
+        // The central directory follows the entry's 30-byte local header, name, extra field and
+        // data
+        let mut reader = Cursor::new(v);
+        reader.set_position(30 + 5 + 11 + 20);
+        assert!(matches!(
+            super::central_header_to_zip_file(&mut reader, 0),
+            Err(ZipError::InvalidArchive(
+                "AES field specifies AES as inner method"
+            ))
+        ));
     }
 
-    /// Get unix mode for the file
-    pub fn unix_mode(&self) -> Option<u32> {
-        self.data.unix_mode()
-    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_manifest_and_metadata() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, DateTime, ZipWriter};
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .last_modified_time(DateTime::from_date_and_time(2024, 5, 17, 9, 30, 0).unwrap());
+        writer.start_file("a.txt", options).unwrap();
+        writer.write_all(b"hello").unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+
+        let manifest = archive.manifest();
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"name":"a.txt","uncompressed_size":5,"compressed_size":5,"crc32":907060870,"compression":0,"is_dir":false}]"#
+        );
+        let parsed: Vec<super::ManifestEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, manifest);
 
-    /// Get the CRC32 hash of the original file
-    pub fn crc32(&self) -> u32 {
-        self.data.crc32
-    }
+        let metadata = archive.file_metadata("a.txt").unwrap();
+        let json = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(json["last_modified"], "2024-05-17T09:30:00");
+        let parsed: super::ZipEntryMetadata = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, metadata);
 
-    /// Get the extra data of the zip header for this file
-    pub fn extra_data(&self) -> Option<&[u8]> {
-        self.data.extra_field.as_ref().map(|v| v.deref().deref())
+        assert!(serde_json::from_str::<DateTime>(r#""2024-13-01T00:00:00""#).is_err());
+        assert!(serde_json::from_str::<DateTime>(r#""2024-05-17 09:30:00""#).is_err());
     }
 
-    /// Get the starting offset of the data of the compressed file
-    pub fn data_start(&self) -> u64 {
-        *self.data.data_start.get().unwrap_or(&0)
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_archive_index() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{Read, Write};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment("comment");
+        for name in ["a.txt", "b.txt"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        writer
+            .add_directory("dir/", SimpleFileOptions::default())
+            .unwrap();
+        let archive = writer.finish_into_readable().unwrap();
+        let json = serde_json::to_string(&archive.index()).unwrap();
+        let data = archive.into_inner().into_inner();
+
+        let index: super::ArchiveIndex = serde_json::from_str(&json).unwrap();
+        let mut archive = ZipArchive::from_parts(index, Cursor::new(data));
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.comment(), b"comment");
+        assert_eq!(archive.index_for_name("dir/"), Some(2));
+        let mut contents = String::new();
+        archive
+            .by_name("b.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "b.txt");
     }
 
-    /// Get the starting offset of the zip header for this file
-    pub fn header_start(&self) -> u64 {
-        self.data.header_start
-    }
-    /// Get the starting offset of the zip header in the central directory for this file
-    pub fn central_header_start(&self) -> u64 {
-        self.data.central_header_start
+    #[cfg(feature = "deflate-flate2")]
+    #[test]
+    fn is_compression_beneficial() {
+        use crate::write::SimpleFileOptions;
+        use crate::{CompressionMethod, ZipWriter};
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("repetitive.txt", deflated).unwrap();
+        writer.write_all(&b"abcd".repeat(1000)).unwrap();
+        writer.start_file("incompressible.bin", deflated).unwrap();
+        writer.write_all(&[0x5a, 0x1f, 0xc3]).unwrap();
+        writer
+            .start_file(
+                "stored.txt",
+                deflated.compression_method(CompressionMethod::Stored),
+            )
+            .unwrap();
+        writer.write_all(b"stored").unwrap();
+        writer.start_file("empty.txt", deflated).unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let mut beneficial = |name| archive.by_name(name).unwrap().is_compression_beneficial();
+        assert_eq!(beneficial("repetitive.txt"), Some(true));
+        assert_eq!(beneficial("incompressible.bin"), Some(false));
+        assert_eq!(beneficial("stored.txt"), Some(false));
+        assert_eq!(beneficial("empty.txt"), None);
     }
 
-    /// iterate through all extra fields
-    pub fn extra_data_fields(&self) -> impl Iterator<Item = &ExtraField> {
-        self.data.extra_fields.iter()
+    #[test]
+    fn extract_entry_tapped() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let contents = b"tapped contents ".repeat(10_000);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("big.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(&contents).unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let mut out = Vec::new();
+        let mut seen = Vec::new();
+        let mut chunks = 0;
+        let written = archive
+            .extract_entry_tapped(0, &mut out, |chunk| {
+                chunks += 1;
+                seen.extend_from_slice(chunk);
+            })
+            .unwrap();
+        assert_eq!(written, contents.len() as u64);
+        assert_eq!(out, contents);
+        assert_eq!(seen, contents);
+        assert!(chunks > 1);
     }
-}
 
-impl<'a> Read for ZipFile<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.get_reader()?.read(buf)
-    }
-}
+    #[test]
+    fn zip64_extra_field_with_every_value() {
+        use std::io::Read;
 
-impl<'a> Drop for ZipFile<'a> {
-    fn drop(&mut self) {
-        // self.data is Owned, this reader is constructed by a streaming reader.
-        // In this case, we want to exhaust the reader so that the next file is accessible.
-        if let Cow::Owned(_) = self.data {
-            // Get the inner `Take` reader so all decryption, decompression and CRC calculation is skipped.
-            match &mut self.reader {
-                ZipFileReader::NoReader => {
-                    let innerreader = self.crypto_reader.take();
-                    let _ = copy(
-                        &mut innerreader.expect("Invalid reader state").into_inner(),
-                        &mut sink(),
-                    );
-                }
-                reader => {
-                    let innerreader = std::mem::replace(reader, ZipFileReader::NoReader);
-                    innerreader.drain();
-                }
-            };
-        }
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/zip64_full_extra_field.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+
+        let mut file = archive.by_name("small.txt").unwrap();
+        assert_eq!(file.size(), 15);
+        assert_eq!(file.compressed_size(), 15);
+        assert_eq!(file.extra_data_fields().count(), 0);
+        let zip64 = file.zip64_extended_information().unwrap();
+        assert_eq!(zip64.uncompressed_size(), Some(15));
+        assert_eq!(zip64.compressed_size(), Some(15));
+        assert_eq!(zip64.header_start(), Some(0));
+        assert_eq!(zip64.disk_start(), Some(0));
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "small contents\n");
+        drop(file);
+
+        // Only the header offset overflowed, but the field holds every value, so the offset is
+        // the third one rather than the first
+        let mut file = archive.by_name("partial.txt").unwrap();
+        assert_eq!(file.header_start(), 54);
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "partial contents\n");
     }
-}
 
-/// Read ZipFile structures from a non-seekable reader.
-///
-/// This is an alternative method to read a zip file. If possible, use the ZipArchive functions
-/// as some information will be missing when reading this manner.
-///
-/// Reads a file header from the start of the stream. Will return `Ok(Some(..))` if a file is
-/// present at the start of the stream. Returns `Ok(None)` if the start of the central directory
-/// is encountered. No more files should be read after this.
-///
-/// The Drop implementation of ZipFile ensures that the reader will be correctly positioned after
-/// the structure is done.
-///
-/// Missing fields are:
-/// * `comment`: set to an empty string
-/// * `data_start`: set to 0
-/// * `external_attributes`: `unix_mode()`: will return None
-pub fn read_zipfile_from_stream<'a, R: Read>(reader: &'a mut R) -> ZipResult<Option<ZipFile<'_>>> {
-    // We can't use the typical ::parse() method, as we follow separate code paths depending on the
-    // "magic" value (since the magic value will be from the central directory header if we've
-    // finished iterating over all the actual files).
-    /* TODO: smallvec? */
-    let mut block = [0u8; mem::size_of::<ZipLocalEntryBlock>()];
-    reader.read_exact(&mut block)?;
-    let block: Box<[u8]> = block.into();
+    #[test]
+    fn append_raw_to() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::{Read, Write};
+
+        let mut archives = Vec::new();
+        for (name, contents) in [("a.txt", "hello\n"), ("b.txt", "hey\n")] {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+            archives.push(writer.finish_into_readable().unwrap());
+        }
 
-    let signature = spec::Magic::from_first_le_bytes(&block);
+        let mut combined = Cursor::new(Vec::new());
+        let indexes: Vec<_> = archives
+            .iter_mut()
+            .map(|archive| archive.append_raw_to(&mut combined).unwrap())
+            .collect();
+        assert!(indexes[1].shared.files[0].header_start > 0);
 
-    match signature {
-        spec::Magic::LOCAL_FILE_HEADER_SIGNATURE => (),
-        spec::Magic::CENTRAL_DIRECTORY_HEADER_SIGNATURE => return Ok(None),
-        _ => return Err(ZipError::InvalidArchive("Invalid local file header")),
+        for (index, (name, contents)) in indexes
+            .into_iter()
+            .zip([("a.txt", "hello\n"), ("b.txt", "hey\n")])
+        {
+            let mut archive = ZipArchive::from_parts(index, Cursor::new(combined.get_ref()));
+            let mut s = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut s)
+                .unwrap();
+            assert_eq!(s, contents);
+        }
     }
 
-    let block = ZipLocalEntryBlock::interpret(block)?;
+    #[test]
+    fn custom_decoder() {
+        use std::io::Read;
 
-    let mut result = ZipFileData::from_local_block(block, reader)?;
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/custom_method.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert!(matches!(
+            archive.by_index(0).err(),
+            Some(ZipError::UnsupportedCompression { method: 200, .. })
+        ));
 
-    match parse_extra_field(&mut result) {
-        Ok(..) | Err(ZipError::Io(..)) => {}
-        Err(e) => return Err(e),
+        // The data of method 200 is stored as is, so the decoder passes it through
+        let mut archive = archive.with_decoder(200, Box::new(|reader| reader));
+        let mut content = String::new();
+        archive
+            .by_index(0)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "Decoded by a custom decoder\n");
+        assert!(archive.by_index_raw(0).unwrap().verify_crc32().unwrap());
     }
 
-    let limit_reader = (reader as &'a mut dyn Read).take(result.compressed_size);
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn brotli() {
+        use crate::CompressionMethod;
+        use std::io::Read;
 
-    let result_crc32 = result.crc32;
-    let result_compression_method = result.compression_method;
-    let crypto_reader = make_crypto_reader(
-        result_compression_method,
-        result_crc32,
-        result.last_modified_time,
-        result.using_data_descriptor,
-        limit_reader,
-        None,
-        None,
-        #[cfg(feature = "aes-crypto")]
-        result.compressed_size,
-    )?;
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/brotli.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        assert_eq!(file.compression(), CompressionMethod::Brotli);
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "Hello from Brotli! ".repeat(50));
+    }
 
-    Ok(Some(ZipFile {
-        data: Cow::Owned(result),
-        crypto_reader: None,
-        reader: make_reader(result_compression_method, result_crc32, crypto_reader)?,
-    }))
-}
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4() {
+        use super::read_zipfile_from_stream;
+        use crate::CompressionMethod;
+        use std::io::Read;
 
-#[cfg(test)]
-mod test {
-    use crate::ZipArchive;
-    use std::io::Cursor;
-    use tempdir::TempDir;
+        // lz4.txt holds the same data as stored.txt, recompressed as an LZ4 frame
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/lz4.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v.clone())).unwrap();
+        let mut stored = Vec::new();
+        archive
+            .by_name("stored.txt")
+            .unwrap()
+            .read_to_end(&mut stored)
+            .unwrap();
+        let mut file = archive.by_name("lz4.txt").unwrap();
+        assert_eq!(file.compression(), CompressionMethod::XZ);
+        let mut decompressed = Vec::new();
+        file.read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, stored);
+
+        // Dropping a partly read entry must consume the rest of its data
+        let mut reader = Cursor::new(v);
+        let mut file = read_zipfile_from_stream(&mut reader).unwrap().unwrap();
+        file.read_exact(&mut [0; 5]).unwrap();
+        drop(file);
+        let mut file = read_zipfile_from_stream(&mut reader).unwrap().unwrap();
+        assert_eq!(file.name(), "stored.txt");
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, stored);
+    }
 
+    #[cfg(feature = "ppmd")]
     #[test]
-    fn invalid_offset() {
-        use super::ZipArchive;
+    fn ppmd() {
+        use crate::CompressionMethod;
+        use std::io::Read;
 
+        // Order 6, 16 MiB, restart on memory exhaustion, without an end marker
         let mut v = Vec::new();
-        v.extend_from_slice(include_bytes!("../tests/data/invalid_offset.zip"));
-        let reader = ZipArchive::new(Cursor::new(v));
-        assert!(reader.is_err());
+        v.extend_from_slice(include_bytes!("../tests/data/ppmd.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        assert_eq!(file.compression(), CompressionMethod::Ppmd);
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        assert_eq!(
+            content,
+            "The quick brown fox jumps over the lazy dog.\n".repeat(40)
+        );
+        drop(file);
+
+        let limits = super::ArchiveLimits {
+            max_ppmd_memory: 8 << 20,
+            ..Default::default()
+        };
+        let mut archive = ZipArchive::with_limits(limits, archive.into_inner()).unwrap();
+        let err = archive
+            .by_index(0)
+            .unwrap()
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
     }
 
     #[test]
-    fn invalid_offset2() {
-        use super::ZipArchive;
+    fn unsupported_compression() {
+        use crate::CompressionMethod;
 
         let mut v = Vec::new();
-        v.extend_from_slice(include_bytes!("../tests/data/invalid_offset2.zip"));
-        let reader = ZipArchive::new(Cursor::new(v));
-        assert!(reader.is_err());
+        v.extend_from_slice(include_bytes!("../tests/data/xz.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        assert_eq!(
+            archive.by_index_raw(0).unwrap().compression(),
+            CompressionMethod::XZ
+        );
+        let err = archive.by_index(0).err().unwrap();
+        assert!(matches!(
+            err,
+            ZipError::UnsupportedCompression {
+                method: 95,
+                name: Some("XZ")
+            }
+        ));
+        assert_eq!(err.to_string(), "unsupported compression method 95");
     }
 
     #[test]
-    fn zip64_with_leading_junk() {
-        use super::ZipArchive;
+    #[cfg(feature = "rayon")]
+    fn read_all_parallel() {
+        use crate::result::ZipError;
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+        use std::io::Write;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("dir/", options).unwrap();
+        for i in 0..8 {
+            writer.start_file(format!("dir/{i}"), options).unwrap();
+            writer
+                .write_all(format!("contents {i}").as_bytes())
+                .unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+        let mut archive = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+
+        let contents = archive.read_all_parallel(1024).unwrap();
+        assert_eq!(contents.len(), 8);
+        for i in 0..8 {
+            assert_eq!(
+                contents[&format!("dir/{i}")],
+                format!("contents {i}").as_bytes()
+            );
+        }
+        assert!(matches!(
+            archive.read_all_parallel(8 * 10 - 1),
+            Err(ZipError::InvalidArchive(_))
+        ));
+
+        let mut lazy = ZipArchive::new_lazy(Cursor::new(bytes)).unwrap();
+        assert_eq!(lazy.read_all_parallel(1024).unwrap(), contents);
+    }
+
+    #[test]
+    fn local_extra_bytes() {
+        use crate::write::{ExtendedFileOptions, FileOptions};
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut options = FileOptions::<ExtendedFileOptions>::default();
+        options.add_extra_data(0xbeef, b"local", false).unwrap();
+        options.add_extra_data(0xcafe, b"", true).unwrap();
+        writer.start_file("file", options).unwrap();
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            archive.local_extra_bytes(0).unwrap(),
+            b"\xef\xbe\x05\x00local"
+        );
+        assert_ne!(
+            archive.by_index(0).unwrap().extra_data().unwrap(),
+            b"\xef\xbe\x05\x00local"
+        );
+    }
+
+    #[test]
+    fn uncompressed_size_mismatch() {
+        use std::io::Read;
 
         let mut v = Vec::new();
-        v.extend_from_slice(include_bytes!("../tests/data/zip64_demo.zip"));
-        let reader = ZipArchive::new(Cursor::new(v)).unwrap();
-        assert_eq!(reader.len(), 1);
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/uncompressed_size_mismatch.zip"
+        ));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let mut file = archive.by_index(0).unwrap();
+        let err = file.read_to_end(&mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("Decompressed size mismatch"));
     }
 
     #[test]
-    fn zip_contents() {
-        use super::ZipArchive;
+    fn os2_extended_attributes() {
+        use crate::ExtraField;
 
         let mut v = Vec::new();
-        v.extend_from_slice(include_bytes!("../tests/data/mimetype.zip"));
-        let mut reader = ZipArchive::new(Cursor::new(v)).unwrap();
-        assert_eq!(reader.comment(), b"");
-        assert_eq!(reader.by_index(0).unwrap().central_header_start(), 77);
+        v.extend_from_slice(include_bytes!("../tests/data/os2_extended_attributes.zip"));
+        let mut archive = ZipArchive::new(Cursor::new(v)).unwrap();
+        let file = archive.by_index(0).unwrap();
+        let fields: Vec<_> = file.extra_data_fields().collect();
+        assert!(matches!(
+            fields[..],
+            [ExtraField::Os2ExtendedAttributes(ref payload)] if payload == b"\x10\x00\x00\x00attrs"
+        ));
     }
 
     #[test]
@@ -1544,6 +7453,82 @@ mod test {
         }
     }
 
+    #[test]
+    #[cfg(feature = "deflate-flate2")]
+    fn zip_read_streaming_data_descriptor() {
+        use super::read_zipfile_from_stream;
+        use crate::CompressionMethod;
+        use std::io::Read;
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/data_descriptor.zip"));
+        let mut reader = Cursor::new(v.clone());
+        let mut file = read_zipfile_from_stream(&mut reader).unwrap().unwrap();
+        assert_eq!(file.name(), "hello.txt");
+        assert_eq!(file.compression(), CompressionMethod::Deflated);
+        assert_eq!(file.size(), 0);
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Hello World\n");
+        assert_eq!(file.size(), 12);
+        assert_eq!(file.compressed_size(), 14);
+        assert_eq!(file.crc32(), 0xb095e5e3);
+        drop(file);
+        assert!(read_zipfile_from_stream(&mut reader).unwrap().is_none());
+
+        // Dropping the entry unread still leaves the stream after its data descriptor
+        let mut reader = Cursor::new(v.clone());
+        drop(read_zipfile_from_stream(&mut reader).unwrap().unwrap());
+        assert!(read_zipfile_from_stream(&mut reader).unwrap().is_none());
+
+        // The data descriptor's signature is optional
+        let mut unsigned = v.clone();
+        unsigned.drain(0x51..0x55);
+        let mut reader = Cursor::new(unsigned);
+        let mut file = read_zipfile_from_stream(&mut reader).unwrap().unwrap();
+        contents.clear();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Hello World\n");
+        assert_eq!(file.crc32(), 0xb095e5e3);
+        drop(file);
+        assert!(read_zipfile_from_stream(&mut reader).unwrap().is_none());
+
+        // The data is checked against the data descriptor
+        let mut corrupt = v.clone();
+        corrupt[0x55] ^= 1;
+        let mut reader = Cursor::new(corrupt);
+        let mut file = read_zipfile_from_stream(&mut reader).unwrap().unwrap();
+        assert!(file.read_to_end(&mut Vec::new()).is_err());
+        assert_eq!(file.crc32(), 0);
+        drop(file);
+
+        // The raw data can't be copied without knowing its size
+        let mut reader = Cursor::new(v.clone());
+        let file = read_zipfile_from_stream(&mut reader).unwrap().unwrap();
+        let mut writer = crate::ZipWriter::new(Cursor::new(Vec::new()));
+        assert!(matches!(
+            writer.raw_copy_file(file),
+            Err(ZipError::UnsupportedArchive(_))
+        ));
+
+        // Encrypted entries need a password, which streams don't support
+        let mut encrypted = v.clone();
+        encrypted[6] |= 1;
+        let mut reader = Cursor::new(encrypted);
+        assert!(matches!(
+            read_zipfile_from_stream(&mut reader),
+            Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED))
+        ));
+
+        // Without Deflate's end marker, the end of the data can't be found
+        v[8] = 0;
+        let mut reader = Cursor::new(v);
+        assert!(matches!(
+            read_zipfile_from_stream(&mut reader),
+            Err(ZipError::UnsupportedArchive(_))
+        ));
+    }
+
     #[test]
     fn zip_clone() {
         use super::ZipArchive;
@@ -1662,6 +7647,24 @@ mod test {
         Ok(())
     }
 
+    #[cfg(feature = "deflate64")]
+    #[test]
+    fn test_entry() {
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!("../tests/data/files_and_dirs.zip"));
+        let mut reader = ZipArchive::new(Cursor::new(v)).unwrap();
+        for i in 0..reader.len() {
+            reader.test_entry(i).unwrap();
+        }
+
+        let mut v = Vec::new();
+        v.extend_from_slice(include_bytes!(
+            "../tests/data/raw_deflate64_index_out_of_bounds.zip"
+        ));
+        let mut reader = ZipArchive::new(Cursor::new(v)).unwrap();
+        reader.test_entry(0).expect_err("Invalid file");
+    }
+
     #[cfg(feature = "deflate64")]
     #[test]
     fn deflate64_not_enough_space() {
@@ -1692,6 +7695,10 @@ mod test {
         let tempdir = TempDir::new("test_is_symlink")?;
         reader.extract(&tempdir).unwrap();
         assert!(tempdir.path().join("bar").is_symlink());
+        assert_eq!(
+            std::fs::read_link(tempdir.path().join("bar"))?,
+            tempdir.path().join("foo")
+        );
         Ok(())
     }
 }
@@ -0,0 +1,159 @@
+//! Opening an archive from a reader that may or may not support seeking.
+
+use super::{read_zipfile_from_stream, ZipArchive, ZipFile};
+use crate::result::ZipResult;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// An archive opened with [`open`](crate::open), read either through its central directory or
+/// as a stream of local file headers, depending on whether its reader can seek.
+///
+/// When the reader can't seek, only what's in the local file headers is available:
+///
+/// - entries can only be read in order, once each, with [`Archive::next_entry`];
+/// - entries that are encrypted, or that use a data descriptor and aren't compressed with
+///   Deflate, can't be read;
+/// - file comments, the archive comment and external attributes (and so Unix permissions) are
+///   missing.
+///
+/// Use [`Archive::as_zip_archive`] to get at everything else [`ZipArchive`] offers when the
+/// reader can seek.
+#[derive(Debug)]
+pub struct Archive<R> {
+    inner: ArchiveInner<R>,
+}
+
+#[derive(Debug)]
+enum ArchiveInner<R> {
+    Seekable {
+        archive: ZipArchive<R>,
+        next_entry: usize,
+    },
+    Streaming(R),
+}
+
+/// Open an archive, reading its central directory if `reader` can seek and falling back to
+/// reading it as a stream from the current position if it can't, such as when it's a
+/// [`File`](std::fs::File) that's actually a pipe. See [`Archive`] for what's unavailable in
+/// streaming mode.
+///
+/// Readers that don't implement [`Seek`] at all can be wrapped in [`Unseekable`].
+pub fn open<R: Read + Seek>(mut reader: R) -> ZipResult<Archive<R>> {
+    let inner = match reader.stream_position() {
+        Ok(_) => ArchiveInner::Seekable {
+            archive: ZipArchive::new(reader)?,
+            next_entry: 0,
+        },
+        Err(_) => ArchiveInner::Streaming(reader),
+    };
+    Ok(Archive { inner })
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Whether the archive is being read through its central directory, rather than as a
+    /// stream.
+    pub fn is_seekable(&self) -> bool {
+        matches!(self.inner, ArchiveInner::Seekable { .. })
+    }
+
+    /// Get the underlying [`ZipArchive`], if the reader can seek.
+    pub fn as_zip_archive(&mut self) -> Option<&mut ZipArchive<R>> {
+        match &mut self.inner {
+            ArchiveInner::Seekable { archive, .. } => Some(archive),
+            ArchiveInner::Streaming(_) => None,
+        }
+    }
+
+    /// Read the next entry in the archive, or `None` once there are no more.
+    ///
+    /// In streaming mode, the previous entry's data must have been read or dropped first, which
+    /// the borrow checker ensures.
+    pub fn next_entry(&mut self) -> ZipResult<Option<ZipFile<'_>>> {
+        match &mut self.inner {
+            ArchiveInner::Seekable {
+                archive,
+                next_entry,
+            } => {
+                if *next_entry >= archive.len() {
+                    return Ok(None);
+                }
+                *next_entry += 1;
+                archive.by_index(*next_entry - 1).map(Some)
+            }
+            ArchiveInner::Streaming(reader) => read_zipfile_from_stream(reader),
+        }
+    }
+
+    /// Unwrap and return the inner reader.
+    pub fn into_inner(self) -> R {
+        match self.inner {
+            ArchiveInner::Seekable { archive, .. } => archive.into_inner(),
+            ArchiveInner::Streaming(reader) => reader,
+        }
+    }
+}
+
+/// Wrapper for a reader that can't seek, so it can be passed to [`open`](crate::open). Every
+/// seek fails with [`io::ErrorKind::Unsupported`].
+#[derive(Debug)]
+pub struct Unseekable<R>(pub R);
+
+impl<R: Read> Read for Unseekable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R> Seek for Unseekable<R> {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "This reader can't seek",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{open, Unseekable};
+    use crate::write::SimpleFileOptions;
+    use crate::ZipWriter;
+    use std::io::{Cursor, Read, Write};
+
+    fn names_and_contents<R: Read + std::io::Seek>(
+        archive: &mut super::Archive<R>,
+    ) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        while let Some(mut file) = archive.next_entry().unwrap() {
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).unwrap();
+            entries.push((file.name().to_string(), contents));
+        }
+        entries
+    }
+
+    #[test]
+    fn seekable_and_streaming() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["a.txt", "b.txt"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        let zip = writer.finish().unwrap().into_inner();
+        let expected = vec![
+            ("a.txt".to_string(), "a.txt".to_string()),
+            ("b.txt".to_string(), "b.txt".to_string()),
+        ];
+
+        let mut archive = open(Cursor::new(zip.clone())).unwrap();
+        assert!(archive.is_seekable());
+        assert_eq!(names_and_contents(&mut archive), expected);
+        assert!(archive.as_zip_archive().is_some());
+
+        let mut archive = open(Unseekable(zip.as_slice())).unwrap();
+        assert!(!archive.is_seekable());
+        assert_eq!(names_and_contents(&mut archive), expected);
+        assert!(archive.as_zip_archive().is_none());
+    }
+}
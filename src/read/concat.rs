@@ -0,0 +1,120 @@
+//! A reader that presents several readers as one contiguous stream.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Reader over a sequence of sources, such as the pieces of an archive that was split for
+/// transfer (`.z01`, `.z02`, ..., `.zip`), presented as if they were concatenated into one stream.
+///
+/// This lets [`ZipArchive`](crate::ZipArchive) open an archive split into pieces without any
+/// multi-disk support, as long as its offsets are relative to the start of the first piece.
+#[derive(Debug)]
+pub struct ConcatReader<R> {
+    sources: Vec<R>,
+    /// The position in the concatenated stream at which each source starts, followed by the
+    /// total length
+    starts: Vec<u64>,
+    pos: u64,
+}
+
+impl<R: Seek> ConcatReader<R> {
+    /// Concatenate the given sources, in order. This seeks to the end of each source to find
+    /// its length, so the sources mustn't change length afterwards.
+    pub fn new(mut sources: Vec<R>) -> io::Result<Self> {
+        let mut starts = Vec::with_capacity(sources.len() + 1);
+        let mut total: u64 = 0;
+        starts.push(total);
+        for source in sources.iter_mut() {
+            let len = source.seek(SeekFrom::End(0))?;
+            total = total.checked_add(len).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Total length overflows u64")
+            })?;
+            starts.push(total);
+        }
+        Ok(ConcatReader {
+            sources,
+            starts,
+            pos: 0,
+        })
+    }
+}
+
+impl<R> ConcatReader<R> {
+    /// Total length of all the sources
+    pub fn len(&self) -> u64 {
+        *self.starts.last().unwrap()
+    }
+
+    /// Whether all the sources are empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Unwrap and return the sources
+    pub fn into_inner(self) -> Vec<R> {
+        self.sources
+    }
+}
+
+impl<R: Read + Seek> Read for ConcatReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len() || buf.is_empty() {
+            return Ok(0);
+        }
+        // The last source starting at or before the position; this skips empty sources
+        let index =
+            self.starts[..self.sources.len()].partition_point(|&start| start <= self.pos) - 1;
+        let local_pos = self.pos - self.starts[index];
+        let remaining = self.starts[index + 1] - self.pos;
+        let len = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let source = &mut self.sources[index];
+        source.seek(SeekFrom::Start(local_pos))?;
+        let n = source.read(&mut buf[..len])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R> Seek for ConcatReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = new_pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConcatReader;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    #[test]
+    fn reads_across_sources() {
+        let sources = vec![
+            Cursor::new(b"hello".to_vec()),
+            Cursor::new(Vec::new()),
+            Cursor::new(b" wor".to_vec()),
+            Cursor::new(b"ld".to_vec()),
+        ];
+        let mut reader = ConcatReader::new(sources).unwrap();
+        assert_eq!(reader.len(), 11);
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "hello world");
+
+        reader.seek(SeekFrom::End(-7)).unwrap();
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"o wor");
+        assert_eq!(reader.stream_position().unwrap(), 9);
+        assert!(reader.seek(SeekFrom::Current(-10)).is_err());
+    }
+}
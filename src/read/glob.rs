@@ -0,0 +1,191 @@
+//! Shell-style glob matching of file names within an archive.
+//!
+//! Names are matched as-is, using the forward-slash separator of the ZIP format:
+//!
+//! - `?` matches any single character other than `/`
+//! - `*` matches any sequence of characters not containing `/`
+//! - `**` matches any sequence of characters, including `/`; `**/` may also match nothing at the
+//!   start of a name or after a `/`, so `**/*.md` matches `README.md` as well as
+//!   `docs/README.md`
+//! - `[abc]`, `[a-z]` and `[!a-z]` match a single character in (or not in) the set
+//!
+//! Any other character, including a `[` with no matching `]`, matches itself.
+
+pub(crate) struct Pattern(Box<str>);
+
+impl Pattern {
+    pub(crate) fn new(pattern: &str) -> Self {
+        Self(pattern.into())
+    }
+
+    /// Matches without recursion: on a mismatch, the most recent `*` takes one more character
+    /// of the name, or if it can't (because it would cross a `/`), the most recent `**` does.
+    /// An earlier wildcard never has to be revisited, since a later one can absorb whatever it
+    /// would have (which is why `**/` only matches nothing where a component starts), so this
+    /// takes O(pattern length × name length) time.
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let pattern = &*self.0;
+        let (mut p, mut n) = (0, 0);
+        // Where to resume after the last `*` or `**`: the pattern position after it, and the
+        // next name position for it to match up to
+        let mut star: Option<(usize, usize)> = None;
+        let mut globstar: Option<(usize, usize)> = None;
+        while p < pattern.len() || n < name.len() {
+            let rest = &pattern[p..];
+            if let Some(after) = rest.strip_prefix("**") {
+                star = None;
+                // `**/` may match nothing at all, slash included, where a component starts
+                if after.starts_with('/') && (n == 0 || name[..n].ends_with('/')) {
+                    globstar = Some((p + 2, n));
+                    p += 3;
+                } else {
+                    globstar = next_position(name, n, false).map(|next| (p + 2, next));
+                    p += 2;
+                }
+                continue;
+            }
+            if rest.starts_with('*') {
+                star = next_position(name, n, true).map(|next| (p + 1, next));
+                p += 1;
+                continue;
+            }
+            if let Some(c) = name[n..].chars().next() {
+                let matched = match rest.chars().next() {
+                    Some('?') => (c != '/').then_some(1),
+                    Some('[') => match class_matches(&rest[1..], c) {
+                        Some((contains, len)) => (c != '/' && contains).then_some(1 + len),
+                        None => (c == '[').then_some(1),
+                    },
+                    Some(literal) => (c == literal).then_some(literal.len_utf8()),
+                    None => None,
+                };
+                if let Some(len) = matched {
+                    p += len;
+                    n += c.len_utf8();
+                    continue;
+                }
+            }
+            if let Some((resume, next)) = star {
+                star = next_position(name, next, true).map(|after| (resume, after));
+                (p, n) = (resume, next);
+            } else if let Some((resume, next)) = globstar {
+                globstar = next_position(name, next, false).map(|after| (resume, after));
+                (p, n) = (resume, next);
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The position after the character of `name` at `n`, for a wildcard that has matched up to `n`
+/// to match it too, or `None` if there's no such character or it's a `/` that can't be crossed.
+fn next_position(name: &str, n: usize, stop_at_slash: bool) -> Option<usize> {
+    let c = name[n..].chars().next()?;
+    (!stop_at_slash || c != '/').then_some(n + c.len_utf8())
+}
+
+/// Parses a character class following a `[`, and checks whether it contains `c`. Returns that,
+/// and the length of the class including the closing `]`, or `None` if it isn't closed.
+fn class_matches(class: &str, c: char) -> Option<(bool, usize)> {
+    let (negated, mut i) = match class.strip_prefix('!') {
+        Some(_) => (true, 1),
+        None => (false, 0),
+    };
+    let mut contains = false;
+    // A `]` right at the start is part of the set rather than closing it
+    let start = i;
+    loop {
+        let mut chars = class[i..].chars();
+        let lo = chars.next()?;
+        if lo == ']' && i > start {
+            return Some((contains != negated, i + 1));
+        }
+        let mut after = chars.clone();
+        match (after.next(), after.next()) {
+            (Some('-'), Some(hi)) if hi != ']' => {
+                contains |= lo <= c && c <= hi;
+                i += lo.len_utf8() + 1 + hi.len_utf8();
+            }
+            _ => {
+                contains |= lo == c;
+                i += lo.len_utf8();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Pattern;
+
+    #[test]
+    fn wildcards() {
+        let pattern = Pattern::new("docs/*.md");
+        assert!(pattern.matches("docs/README.md"));
+        assert!(!pattern.matches("docs/guide/intro.md"));
+        assert!(!pattern.matches("README.md"));
+
+        let pattern = Pattern::new("docs/**/*.md");
+        assert!(pattern.matches("docs/README.md"));
+        assert!(pattern.matches("docs/guide/intro.md"));
+
+        let pattern = Pattern::new("**/*.md");
+        assert!(pattern.matches("README.md"));
+        assert!(pattern.matches("docs/guide/intro.md"));
+        assert!(!pattern.matches("docs/guide/intro.txt"));
+
+        let pattern = Pattern::new("docs/**/intro.md");
+        assert!(pattern.matches("docs/intro.md"));
+        assert!(pattern.matches("docs/guide/intro.md"));
+        assert!(!Pattern::new("docs**/intro.md").matches("docsintro.md"));
+
+        let pattern = Pattern::new("src/**");
+        assert!(pattern.matches("src/"));
+        assert!(pattern.matches("src/read/glob.rs"));
+        assert!(!pattern.matches("tests/src/lib.rs"));
+
+        let pattern = Pattern::new("file?.txt");
+        assert!(pattern.matches("file1.txt"));
+        assert!(!pattern.matches("file10.txt"));
+        assert!(!pattern.matches("file/.txt"));
+    }
+
+    #[test]
+    fn character_classes() {
+        let pattern = Pattern::new("file[0-9a].txt");
+        assert!(pattern.matches("file5.txt"));
+        assert!(pattern.matches("filea.txt"));
+        assert!(!pattern.matches("fileb.txt"));
+
+        let pattern = Pattern::new("file[!0-9].txt");
+        assert!(!pattern.matches("file5.txt"));
+        assert!(pattern.matches("fileb.txt"));
+
+        let pattern = Pattern::new("[]]");
+        assert!(pattern.matches("]"));
+
+        let pattern = Pattern::new("file[1");
+        assert!(pattern.matches("file[1"));
+        assert!(!pattern.matches("file1"));
+    }
+
+    #[test]
+    fn multibyte_characters() {
+        let pattern = Pattern::new("caf?/[à-é]*");
+        assert!(pattern.matches("café/été.txt"));
+        assert!(!pattern.matches("café/ôté.txt"));
+        assert!(Pattern::new("**/ü").matches("ä/ö/ü"));
+    }
+
+    #[test]
+    fn backtracking_is_not_exponential() {
+        let name = "a".repeat(10_000);
+        assert!(!Pattern::new("*a*a*a*a*a*a*a*a*b").matches(&name));
+        assert!(!Pattern::new("**a**a**a**a**a**a**b").matches(&name));
+        assert!(Pattern::new("*a*a*a*a*a*a*a*a").matches(&name));
+        let name = "a/".repeat(5_000);
+        assert!(!Pattern::new("**/a*a/**/b").matches(&name));
+    }
+}
@@ -1,52 +1,160 @@
 use lzma_rs::decompress::{Options, Stream, UnpackedSize};
 use std::collections::VecDeque;
-use std::io::{copy, Error, Read, Result, Write};
+use std::io::{Error, ErrorKind, Read, Result, Write};
 
 const COMPRESSED_BYTES_TO_BUFFER: usize = 4096;
 
-const OPTIONS: Options = Options {
-    unpacked_size: UnpackedSize::ReadFromHeader,
-    memlimit: None,
-    allow_incomplete: true,
-};
+/// Length of the LZMA properties that follow the ZIP-specific header
+const PROPERTIES_SIZE: u16 = 5;
 
+/// Decodes LZMA data as stored in a ZIP file: a 2-byte LZMA SDK version and a 2-byte properties
+/// size, followed by the LZMA properties and the compressed stream.
+///
+/// Unlike a `.lzma` file, there's no uncompressed size after the properties. The stream either
+/// ends with an end-of-stream marker (general purpose flag bit 1), or just stops when the size
+/// given in the ZIP headers has been decoded.
 #[derive(Debug)]
 pub struct LzmaDecoder<R> {
     compressed_reader: R,
-    stream: Stream<VecDeque<u8>>,
+    uncompressed_size: Option<u64>,
+    stream: Option<Stream<VecDeque<u8>>>,
+    /// Whether the decoder has stopped accepting compressed data
+    finished: bool,
+    /// Decompressed data left over once the compressed data has all been read
+    remaining: VecDeque<u8>,
 }
 
 impl<R: Read> LzmaDecoder<R> {
-    pub fn new(inner: R) -> Self {
+    /// `uncompressed_size` should be `None` if it isn't known, in which case the stream must
+    /// have an end-of-stream marker. If it's known, it's used even if there's also a marker.
+    pub fn new(inner: R, uncompressed_size: Option<u64>) -> Self {
         LzmaDecoder {
             compressed_reader: inner,
-            stream: Stream::new_with_options(&OPTIONS, VecDeque::new()),
+            uncompressed_size,
+            stream: None,
+            finished: false,
+            remaining: VecDeque::new(),
         }
     }
 
+    /// Read the ZIP-specific header on first use, and create the stream decoder.
+    fn stream(&mut self) -> Result<&mut Stream<VecDeque<u8>>> {
+        if self.stream.is_none() {
+            let mut header = [0u8; 4];
+            self.compressed_reader.read_exact(&mut header)?;
+            let properties_size = u16::from_le_bytes([header[2], header[3]]);
+            if properties_size != PROPERTIES_SIZE {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Unsupported LZMA properties size",
+                ));
+            }
+            let options = Options {
+                unpacked_size: UnpackedSize::UseProvided(self.uncompressed_size),
+                memlimit: None,
+                allow_incomplete: false,
+            };
+            self.stream = Some(Stream::new_with_options(&options, VecDeque::new()));
+        }
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    /// Feed compressed data to the decoder. Once the decoder has reached the declared size or the
+    /// end-of-stream marker, it stops accepting data, and the rest is ignored.
+    fn write_compressed(&mut self, mut compressed: &[u8]) -> Result<()> {
+        while !compressed.is_empty() && !self.finished {
+            let written = self.stream()?.write(compressed)?;
+            if written == 0 {
+                self.finished = true;
+            }
+            compressed = &compressed[written..];
+        }
+        Ok(())
+    }
+
+    /// Decode the compressed data that's left, and check the stream ended properly.
     pub fn finish(mut self) -> Result<VecDeque<u8>> {
-        copy(&mut self.compressed_reader, &mut self.stream)?;
-        self.stream.finish().map_err(Error::from)
+        self.finish_stream()?;
+        Ok(self.remaining)
+    }
+
+    /// Read the rest of the compressed data, then finish decoding the stream and move whatever
+    /// hasn't been read yet to `remaining`.
+    fn finish_stream(&mut self) -> Result<()> {
+        if self.stream.is_none() && !self.finished {
+            self.stream()?;
+        }
+        let mut next_compressed = [0u8; COMPRESSED_BYTES_TO_BUFFER];
+        loop {
+            let compressed_bytes_read = self.compressed_reader.read(&mut next_compressed)?;
+            if compressed_bytes_read == 0 {
+                break;
+            }
+            self.write_compressed(&next_compressed[..compressed_bytes_read])?;
+        }
+        if let Some(stream) = self.stream.take() {
+            self.remaining = stream.finish().map_err(Error::from)?;
+        }
+        self.finished = true;
+        Ok(())
     }
 }
 
 impl<R: Read> Read for LzmaDecoder<R> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        let mut bytes_read = self.stream.get_output_mut().unwrap().read(buf)?;
-        while bytes_read < buf.len() {
+        loop {
+            if self.stream.is_none() && !self.finished {
+                self.stream()?;
+            }
+            let bytes_read = match &mut self.stream {
+                Some(stream) => stream.get_output_mut().unwrap().read(buf)?,
+                None => self.remaining.read(buf)?,
+            };
+            if bytes_read > 0 || buf.is_empty() || self.stream.is_none() {
+                return Ok(bytes_read);
+            }
             let mut next_compressed = [0u8; COMPRESSED_BYTES_TO_BUFFER];
-            let compressed_bytes_read = self.compressed_reader.read(&mut next_compressed)?;
+            let compressed_bytes_read = if self.finished {
+                0
+            } else {
+                self.compressed_reader.read(&mut next_compressed)?
+            };
             if compressed_bytes_read == 0 {
-                break;
+                self.finish_stream()?;
+            } else {
+                self.write_compressed(&next_compressed[..compressed_bytes_read])?;
             }
-            self.stream
-                .write_all(&next_compressed[..compressed_bytes_read])?;
-            bytes_read += self
-                .stream
-                .get_output_mut()
-                .unwrap()
-                .read(&mut buf[bytes_read..])?;
         }
-        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LzmaDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn unknown_size_with_end_marker() {
+        // Raw LZMA1 stream with an end-of-stream marker, as written by Python's zipfile module
+        let zip = include_bytes!("../../tests/data/lzma_variants.zip");
+        let compressed_size = u32::from_le_bytes(zip[18..22].try_into().unwrap()) as usize;
+        let data_start = 30 + "end_marker.txt".len();
+        let compressed = &zip[data_start..data_start + compressed_size];
+
+        let mut decoder = LzmaDecoder::new(compressed, None);
+        let mut content = Vec::new();
+        decoder.read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"Hello world\n".repeat(100));
+    }
+
+    #[test]
+    fn truncated() {
+        let zip = include_bytes!("../../tests/data/lzma_variants.zip");
+        let compressed_size = u32::from_le_bytes(zip[18..22].try_into().unwrap()) as usize;
+        let data_start = 30 + "end_marker.txt".len();
+        let compressed = &zip[data_start..data_start + compressed_size / 2];
+
+        let mut decoder = LzmaDecoder::new(compressed, Some(1200));
+        assert!(decoder.read_to_end(&mut Vec::new()).is_err());
     }
 }
@@ -0,0 +1,168 @@
+use ppmd_rust::{Ppmd8Decoder, RestoreMethod, PPMD8_MIN_ORDER};
+use std::io::{Chain, Cursor, Error, ErrorKind, Read, Result};
+
+/// The compressed data after the first 4 bytes of the stream, which are read ahead so that
+/// creating the decoder can't fail after taking the compressed reader. The reader is only moved
+/// in once the decoder has been created.
+struct Rest<R>(Option<R>);
+
+impl<R: Read> Read for Rest<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match &mut self.0 {
+            Some(reader) => reader.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+type StreamDecoder<R> = Ppmd8Decoder<Chain<Cursor<[u8; 4]>, Rest<R>>>;
+
+enum State<R: Read> {
+    Header(R),
+    Decoding(Box<StreamDecoder<R>>),
+}
+
+/// Decodes PPMd data as stored in a ZIP file: a 2-byte header holding the model order, memory
+/// size and restoration method, followed by a PPMd variant I (revision 1) stream.
+///
+/// The stream doesn't have to end with an end marker, so decoding stops once the size given in
+/// the ZIP headers has been decoded. If that size isn't known, the stream must have a marker.
+pub struct PpmdDecoder<R: Read> {
+    state: State<R>,
+    remaining: Option<u64>,
+    max_memory: u32,
+}
+
+impl<R: Read> PpmdDecoder<R> {
+    /// Create a decoder that fails if the header asks for more than `max_memory` bytes for the
+    /// model.
+    pub fn new(inner: R, uncompressed_size: Option<u64>, max_memory: u32) -> Self {
+        PpmdDecoder {
+            state: State::Header(inner),
+            remaining: uncompressed_size,
+            max_memory,
+        }
+    }
+
+    /// Read the header on first use, and create the stream decoder.
+    fn decoder(&mut self) -> Result<&mut StreamDecoder<R>> {
+        if let State::Header(reader) = &mut self.state {
+            let mut header = [0u8; 2];
+            reader.read_exact(&mut header)?;
+            let params = u16::from_le_bytes(header);
+            let order = u32::from(params & 0x0F) + 1;
+            let mem_size = (u32::from((params >> 4) & 0xFF) + 1) << 20;
+            let restore_method = match params >> 12 {
+                0 => RestoreMethod::Restart,
+                1 => RestoreMethod::CutOff,
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Unsupported PPMd restoration method",
+                    ))
+                }
+            };
+            if order < PPMD8_MIN_ORDER {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid PPMd model order",
+                ));
+            }
+            if mem_size > self.max_memory {
+                return Err(Error::new(
+                    ErrorKind::OutOfMemory,
+                    "PPMd model needs more memory than the archive limits allow",
+                ));
+            }
+            let mut start = [0u8; 4];
+            reader.read_exact(&mut start)?;
+            if start == [0xFF; 4] {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid PPMd range coder state",
+                ));
+            }
+            let decoder = Ppmd8Decoder::new(
+                Cursor::new(start).chain(Rest(None)),
+                order,
+                mem_size,
+                restore_method,
+            )
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+            let State::Header(reader) =
+                std::mem::replace(&mut self.state, State::Decoding(Box::new(decoder)))
+            else {
+                unreachable!()
+            };
+            if let State::Decoding(decoder) = &mut self.state {
+                decoder.get_mut().get_mut().1 .0 = Some(reader);
+            }
+        }
+        match &mut self.state {
+            State::Decoding(decoder) => Ok(decoder),
+            State::Header(_) => unreachable!(),
+        }
+    }
+
+    /// Get the compressed reader back.
+    pub fn into_inner(self) -> R {
+        match self.state {
+            State::Header(reader) => reader,
+            State::Decoding(decoder) => {
+                let (_, Rest(reader)) = decoder.into_inner().into_inner();
+                reader.expect("Reader is moved in when the decoder is created")
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for PpmdDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = match self.remaining {
+            Some(remaining) => buf.len().min(remaining.try_into().unwrap_or(usize::MAX)),
+            None => buf.len(),
+        };
+        if len == 0 {
+            return Ok(0);
+        }
+        let bytes_read = self.decoder()?.read(&mut buf[..len])?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= bytes_read as u64;
+        }
+        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PpmdDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn invalid_restoration_method() {
+        // Restoration method 2 (freeze) isn't supported by PPMd variant I revision 1
+        let compressed = [0x05, 0x20, 0, 0, 0, 0];
+        let mut decoder = PpmdDecoder::new(&compressed[..], Some(10), u32::MAX);
+        assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+        assert_eq!(decoder.into_inner(), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn keeps_reader_when_decoder_fails_to_start() {
+        // Order 6, 16 MiB, with a range coder that can't be initialized
+        let compressed = [0xF5, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2];
+        let mut decoder = PpmdDecoder::new(&compressed[..], Some(10), u32::MAX);
+        assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+        assert_eq!(decoder.into_inner(), [1, 2]);
+    }
+
+    #[test]
+    fn memory_limit() {
+        // Order 6, 16 MiB
+        let compressed = [0xF5, 0x00, 0, 0, 0, 0];
+        let mut decoder = PpmdDecoder::new(&compressed[..], Some(10), 8 << 20);
+        let err = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+        assert_eq!(decoder.into_inner(), [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Random access to the contents of a stored file.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Reader over the contents of a file that's stored without compression or encryption, which
+/// can also seek within them. Returned by
+/// [`ZipArchive::by_index_seekable`](crate::ZipArchive::by_index_seekable).
+///
+/// Positions are relative to the start of the file's data, and seeking past its end stops at
+/// the end. Since the data can be read out of order, its CRC-32 isn't checked.
+#[derive(Debug)]
+pub struct SeekableZipFile<'a, R> {
+    reader: &'a mut R,
+    data_start: u64,
+    size: u64,
+    pos: u64,
+}
+
+impl<'a, R> SeekableZipFile<'a, R> {
+    pub(crate) fn new(reader: &'a mut R, data_start: u64, size: u64) -> Self {
+        SeekableZipFile {
+            reader,
+            data_start,
+            size,
+            pos: 0,
+        }
+    }
+
+    /// Size of the file's contents
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Whether the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl<'a, R: Read + Seek> Read for SeekableZipFile<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let remaining = self.size - self.pos;
+        let len = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        self.reader
+            .seek(SeekFrom::Start(self.data_start + self.pos))?;
+        let n = self.reader.read(&mut buf[..len])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'a, R> Seek for SeekableZipFile<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        let new_pos = new_pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.pos = new_pos.min(self.size);
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::write::SimpleFileOptions;
+    use crate::{CompressionMethod, ZipWriter};
+    use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn seek_within_stored_file() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("mimetype", stored).unwrap();
+        writer.write_all(b"application/epub+zip").unwrap();
+        writer.start_file("other", stored).unwrap();
+        writer.write_all(b"after").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        let mut file = archive.by_index_seekable(0).unwrap();
+        assert_eq!(file.len(), 20);
+        let mut buf = [0; 4];
+        file.seek(SeekFrom::Start(12)).unwrap();
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"epub");
+        file.seek(SeekFrom::Current(-12)).unwrap();
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"icat");
+        assert_eq!(file.seek(SeekFrom::End(-3)).unwrap(), 17);
+        let mut rest = Vec::new();
+        file.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"zip");
+        assert_eq!(file.seek(SeekFrom::Current(100)).unwrap(), 20);
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+        assert!(file.seek(SeekFrom::Current(-21)).is_err());
+    }
+
+    #[cfg(feature = "_deflate-any")]
+    #[test]
+    fn compressed_file_is_not_seekable() {
+        use crate::result::ZipError;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("a.txt", deflated).unwrap();
+        writer.write_all(b"Hello, world!").unwrap();
+        let mut archive = writer.finish_into_readable().unwrap();
+
+        assert!(matches!(
+            archive.by_index_seekable(0),
+            Err(ZipError::UnsupportedArchive(_))
+        ));
+    }
+}
@@ -179,10 +179,7 @@ impl ZipStreamFileMetadata {
 
     /// Returns whether the file is actually a directory
     pub fn is_dir(&self) -> bool {
-        self.name()
-            .chars()
-            .next_back()
-            .map_or(false, |c| c == '/' || c == '\\')
+        self.name().ends_with('/')
     }
 
     /// Returns whether the file is a regular file
@@ -225,23 +222,6 @@ mod test {
         }
     }
 
-    #[derive(Default, Debug, Eq, PartialEq)]
-    struct CounterVisitor(u64, u64);
-    impl ZipStreamVisitor for CounterVisitor {
-        fn visit_file(&mut self, _file: &mut ZipFile<'_>) -> ZipResult<()> {
-            self.0 += 1;
-            Ok(())
-        }
-
-        fn visit_additional_metadata(
-            &mut self,
-            _metadata: &ZipStreamFileMetadata,
-        ) -> ZipResult<()> {
-            self.1 += 1;
-            Ok(())
-        }
-    }
-
     #[test]
     fn invalid_offset() {
         ZipStreamReader::new(io::Cursor::new(include_bytes!(
@@ -341,6 +321,39 @@ mod test {
         reader.visit(&mut V::default()).unwrap();
     }
 
+    #[test]
+    fn trailing_backslash_is_not_a_directory() {
+        use crate::write::SimpleFileOptions;
+        use crate::ZipWriter;
+
+        let mut writer = ZipWriter::new(io::Cursor::new(Vec::new()));
+        writer
+            .start_file("weird\\", SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .add_directory("dir/", SimpleFileOptions::default())
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        struct V;
+        impl ZipStreamVisitor for V {
+            fn visit_file(&mut self, _file: &mut ZipFile<'_>) -> ZipResult<()> {
+                Ok(())
+            }
+            fn visit_additional_metadata(
+                &mut self,
+                metadata: &ZipStreamFileMetadata,
+            ) -> ZipResult<()> {
+                assert_eq!(metadata.is_dir(), metadata.name() == "dir/");
+                Ok(())
+            }
+        }
+
+        ZipStreamReader::new(io::Cursor::new(bytes))
+            .visit(&mut V)
+            .unwrap();
+    }
+
     /// test case to ensure we don't preemptively over allocate based on the
     /// declared number of files in the CDE of an invalid zip when the number of
     /// files declared is more than the alleged offset in the CDE
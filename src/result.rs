@@ -26,6 +26,14 @@ pub enum ZipError {
     /// unsupported Zip archive: {0}
     UnsupportedArchive(&'static str),
 
+    /// unsupported compression method {method}
+    UnsupportedCompression {
+        /// The compression method number, as stored in the archive
+        method: u16,
+        /// The name of the compression method, if it's one listed in APPNOTE.TXT
+        name: Option<&'static str>,
+    },
+
     /// specified file not found in archive
     FileNotFound,
 
@@ -54,6 +62,7 @@ impl From<ZipError> for io::Error {
             ZipError::Io(err) => err.kind(),
             ZipError::InvalidArchive(_) => io::ErrorKind::InvalidData,
             ZipError::UnsupportedArchive(_) => io::ErrorKind::Unsupported,
+            ZipError::UnsupportedCompression { .. } => io::ErrorKind::Unsupported,
             ZipError::FileNotFound => io::ErrorKind::NotFound,
             ZipError::InvalidPassword => io::ErrorKind::InvalidInput,
         };
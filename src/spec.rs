@@ -1,12 +1,13 @@
+//! Low-level details of the ZIP format, for tools that parse archives themselves but want to
+//! match how this crate reads them.
+
 #![macro_use]
 
 use crate::result::{ZipError, ZipResult};
-use memchr::memmem::FinderRev;
-use std::borrow::Cow;
+use memchr::memmem::{Finder, FinderRev};
 use std::io;
 use std::io::prelude::*;
 use std::mem;
-use std::path::{Component, Path, MAIN_SEPARATOR};
 
 /// "Magic" header values used in the zip spec to locate metadata records.
 ///
@@ -54,6 +55,7 @@ impl Magic {
     pub const CENTRAL_DIRECTORY_END_SIGNATURE: Self = Self::literal(0x06054b50);
     pub const ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE: Self = Self::literal(0x06064b50);
     pub const ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE: Self = Self::literal(0x07064b50);
+    pub const DATA_DESCRIPTOR_SIGNATURE: Self = Self::literal(0x08074b50);
 }
 
 /// Similar to [`Magic`], but used for extra field tags as per section 4.5.3 of APPNOTE.TXT.
@@ -94,8 +96,37 @@ impl ExtraFieldMagic {
 }
 
 /// This should be equal to `0xFFFFFFFF`.
-pub const ZIP64_BYTES_THR: u64 = u32::MAX as u64;
-pub const ZIP64_ENTRY_THR: usize = u16::MAX as usize;
+pub(crate) const ZIP64_BYTES_THR: u64 = u32::MAX as u64;
+pub(crate) const ZIP64_ENTRY_THR: usize = u16::MAX as usize;
+
+/// Compute how many bytes of other data precede an archive, such as a self-extractor stub, from
+/// the values in its end of central directory record.
+///
+/// `cde_start_pos` is where the end of central directory record was actually found, and the
+/// central directory should end right before it; the difference from where the record says the
+/// central directory starts is taken to be the length of the leading data, and is added to
+/// every offset in the archive. Returns `None` if the central directory's size and offset add up
+/// to more than `cde_start_pos`, which means the record is invalid.
+///
+/// ```
+/// use zip::spec::compute_archive_offset;
+///
+/// // A 100-byte central directory at offset 1000, whose end record is at 1100: no leading data
+/// assert_eq!(compute_archive_offset(1100, 100, 1000), Some(0));
+/// // The same archive after a 512-byte stub
+/// assert_eq!(compute_archive_offset(1612, 100, 1000), Some(512));
+/// assert_eq!(compute_archive_offset(1000, 100, 1000), None);
+/// ```
+pub const fn compute_archive_offset(
+    cde_start_pos: u64,
+    central_directory_size: u64,
+    central_directory_offset: u64,
+) -> Option<u64> {
+    match cde_start_pos.checked_sub(central_directory_size) {
+        Some(directory_end) => directory_end.checked_sub(central_directory_offset),
+        None => None,
+    }
+}
 
 pub(crate) trait Block: Sized + Copy {
     const MAGIC: Magic;
@@ -243,6 +274,8 @@ pub(crate) struct Zip32CentralDirectoryEnd {
     pub central_directory_size: u32,
     pub central_directory_offset: u32,
     pub zip_file_comment: Box<[u8]>,
+    /// Whether the file ended before the declared length of the comment
+    pub comment_truncated: bool,
 }
 
 impl Zip32CentralDirectoryEnd {
@@ -255,6 +288,7 @@ impl Zip32CentralDirectoryEnd {
             central_directory_size,
             central_directory_offset,
             zip_file_comment,
+            ..
         } = self;
         let block = Zip32CDEBlock {
             magic: Zip32CDEBlock::MAGIC,
@@ -272,7 +306,12 @@ impl Zip32CentralDirectoryEnd {
         Ok((block, zip_file_comment))
     }
 
-    pub fn parse<T: Read>(reader: &mut T) -> ZipResult<Zip32CentralDirectoryEnd> {
+    /// Parse the record and its comment. If `allow_truncated_comment` is set, a comment that's
+    /// cut short by the end of the file is accepted, rather than making parsing fail.
+    pub fn parse<T: Read>(
+        reader: &mut T,
+        allow_truncated_comment: bool,
+    ) -> ZipResult<Zip32CentralDirectoryEnd> {
         let Zip32CDEBlock {
             // magic,
             disk_number,
@@ -285,8 +324,14 @@ impl Zip32CentralDirectoryEnd {
             ..
         } = Zip32CDEBlock::parse(reader)?;
 
-        let mut zip_file_comment = vec![0u8; zip_file_comment_length as usize].into_boxed_slice();
-        reader.read_exact(&mut zip_file_comment)?;
+        let mut zip_file_comment = Vec::with_capacity(zip_file_comment_length as usize);
+        reader
+            .take(zip_file_comment_length as u64)
+            .read_to_end(&mut zip_file_comment)?;
+        let comment_truncated = zip_file_comment.len() < zip_file_comment_length as usize;
+        if comment_truncated && !allow_truncated_comment {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
 
         Ok(Zip32CentralDirectoryEnd {
             disk_number,
@@ -295,12 +340,33 @@ impl Zip32CentralDirectoryEnd {
             number_of_files,
             central_directory_size,
             central_directory_offset,
-            zip_file_comment,
+            zip_file_comment: zip_file_comment.into_boxed_slice(),
+            comment_truncated,
         })
     }
 
+    /// Find and parse the last end of central directory record in the file, returning it and its
+    /// position.
+    ///
+    /// If `allow_truncated_comment` is set and no record with a complete comment can be found,
+    /// the search is repeated accepting a record whose comment is cut short by the end of the
+    /// file.
     pub fn find_and_parse<T: Read + Seek>(
         reader: &mut T,
+        allow_truncated_comment: bool,
+    ) -> ZipResult<(Zip32CentralDirectoryEnd, u64)> {
+        let result = Self::find_and_parse_impl(reader, false);
+        if result.is_err() && allow_truncated_comment {
+            if let Ok(found) = Self::find_and_parse_impl(reader, true) {
+                return Ok(found);
+            }
+        }
+        result
+    }
+
+    fn find_and_parse_impl<T: Read + Seek>(
+        reader: &mut T,
+        allow_truncated_comment: bool,
     ) -> ZipResult<(Zip32CentralDirectoryEnd, u64)> {
         let file_length = reader.seek(io::SeekFrom::End(0))?;
 
@@ -339,7 +405,7 @@ impl Zip32CentralDirectoryEnd {
                 let cde_start_pos = window_start + offset as u64;
                 reader.seek(io::SeekFrom::Start(cde_start_pos))?;
                 /* Drop any headers that don't parse. */
-                if let Ok(cde) = Self::parse(reader) {
+                if let Ok(cde) = Self::parse(reader, allow_truncated_comment) {
                     return Ok((cde, cde_start_pos));
                 }
             }
@@ -503,9 +569,14 @@ pub(crate) struct Zip64CentralDirectoryEnd {
 }
 
 impl Zip64CentralDirectoryEnd {
-    pub fn parse<T: Read>(reader: &mut T) -> ZipResult<Zip64CentralDirectoryEnd> {
+    /// Size of the fixed fields that follow `record_size`, which doesn't count itself or the magic
+    const FIXED_RECORD_SIZE: u64 = 44;
+
+    /// Parse the record, then skip any extensible data after the fixed fields, so that the reader
+    /// ends up just past the record as `record_size` describes it.
+    pub fn parse<T: Read + Seek>(reader: &mut T) -> ZipResult<Zip64CentralDirectoryEnd> {
         let Zip64CDEBlock {
-            // record_size,
+            record_size,
             version_made_by,
             version_needed_to_extract,
             disk_number,
@@ -516,6 +587,13 @@ impl Zip64CentralDirectoryEnd {
             central_directory_offset,
             ..
         } = Zip64CDEBlock::parse(reader)?;
+        if record_size > Self::FIXED_RECORD_SIZE {
+            let extensible_data_len = i64::try_from(record_size - Self::FIXED_RECORD_SIZE)
+                .map_err(|_| {
+                    ZipError::InvalidArchive("Invalid ZIP64 central directory end record size")
+                })?;
+            reader.seek(io::SeekFrom::Current(extensible_data_len))?;
+        }
         Ok(Self {
             version_made_by,
             version_needed_to_extract,
@@ -624,8 +702,7 @@ impl Zip64CentralDirectoryEnd {
         } = self;
         Zip64CDEBlock {
             magic: Zip64CDEBlock::MAGIC,
-            /* currently unused */
-            record_size: 44,
+            record_size: Self::FIXED_RECORD_SIZE,
             version_made_by,
             version_needed_to_extract,
             disk_number,
@@ -642,58 +719,59 @@ impl Zip64CentralDirectoryEnd {
     }
 }
 
-pub(crate) fn is_dir(filename: &str) -> bool {
-    filename
-        .chars()
-        .next_back()
-        .map_or(false, |c| c == '/' || c == '\\')
-}
-
-/// Converts a path to the ZIP format (forward-slash-delimited and normalized).
-pub(crate) fn path_to_string<T: AsRef<Path>>(path: T) -> Box<str> {
-    let mut maybe_original = None;
-    if let Some(original) = path.as_ref().to_str() {
-        if (MAIN_SEPARATOR == '/' || !original[1..].contains(MAIN_SEPARATOR))
-            && !original.ends_with('.')
-            && !original.starts_with(['.', MAIN_SEPARATOR])
-            && !original.starts_with(['.', '.', MAIN_SEPARATOR])
-            && !original.contains([MAIN_SEPARATOR, MAIN_SEPARATOR])
-            && !original.contains([MAIN_SEPARATOR, '.', MAIN_SEPARATOR])
-            && !original.contains([MAIN_SEPARATOR, '.', '.', MAIN_SEPARATOR])
-        {
-            if original.starts_with(MAIN_SEPARATOR) {
-                maybe_original = Some(&original[1..]);
-            } else {
-                maybe_original = Some(original);
-            }
+/// Find the position of the first occurrence of `magic` in the reader that starts within
+/// `[search_lower_bound, search_upper_bound)`.
+pub(crate) fn find_next_magic<T: Read + Seek>(
+    reader: &mut T,
+    magic: Magic,
+    search_lower_bound: u64,
+    search_upper_bound: u64,
+) -> ZipResult<Option<u64>> {
+    const WINDOW_SIZE: usize = 2048;
+    /* TODO: use static_assertions!() */
+    debug_assert!(WINDOW_SIZE > mem::size_of::<Magic>());
+
+    let sig_bytes = magic.to_le_bytes();
+    let finder = Finder::new(&sig_bytes);
+
+    let mut window_start = search_lower_bound;
+    let mut window = [0u8; WINDOW_SIZE];
+    while window_start < search_upper_bound {
+        reader.seek(io::SeekFrom::Start(window_start))?;
+        /* Read past the upper bound by the width of the pattern, so that a match starting just
+         * before it is still found. */
+        let end = (window_start + WINDOW_SIZE as u64)
+            .min(search_upper_bound + mem::size_of::<Magic>() as u64 - 1);
+        let cur_window = &mut window[..(end - window_start) as usize];
+        let cur_len = read_up_to(reader, cur_window)?;
+        if let Some(offset) = finder.find(&cur_window[..cur_len]) {
+            let found = window_start + offset as u64;
+            return Ok((found < search_upper_bound).then_some(found));
         }
+        if cur_len < cur_window.len() {
+            /* We've hit the end of the file. */
+            break;
+        }
+        /* NB: To catch matches across window boundaries, we need to make our blocks overlap by
+         * the width of the pattern to match. */
+        window_start = end + 1 - mem::size_of::<Magic>() as u64;
     }
-    let mut recreate = maybe_original.is_none();
-    let mut normalized_components = Vec::new();
+    Ok(None)
+}
 
-    for component in path.as_ref().components() {
-        match component {
-            Component::Normal(os_str) => match os_str.to_str() {
-                Some(valid_str) => normalized_components.push(Cow::Borrowed(valid_str)),
-                None => {
-                    recreate = true;
-                    normalized_components.push(os_str.to_string_lossy());
-                }
-            },
-            Component::ParentDir => {
-                recreate = true;
-                normalized_components.pop();
-            }
-            _ => {
-                recreate = true;
-            }
+/// Like [`Read::read_exact`], but stops early at the end of the reader, returning how many bytes
+/// were read.
+fn read_up_to<T: Read>(reader: &mut T, mut buf: &mut [u8]) -> io::Result<usize> {
+    let len = buf.len();
+    while !buf.is_empty() {
+        match reader.read(buf) {
+            Ok(0) => break,
+            Ok(n) => buf = &mut buf[n..],
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
     }
-    if recreate {
-        normalized_components.join("/").into()
-    } else {
-        maybe_original.unwrap().into()
-    }
+    Ok(len - buf.len())
 }
 
 #[cfg(test)]
@@ -720,6 +798,46 @@ mod test {
         to_and_from_le![(magic, Magic), (file_name_length, u16)];
     }
 
+    #[test]
+    fn find_next_magic_across_windows() {
+        let mut data = vec![0u8; 5000];
+        let magic = Magic::CENTRAL_DIRECTORY_HEADER_SIGNATURE;
+        /* Straddle the boundary between the first and second search windows. */
+        data[2046..2050].copy_from_slice(&magic.to_le_bytes());
+        data[4000..4004].copy_from_slice(&magic.to_le_bytes());
+        let mut c = Cursor::new(data);
+        assert_eq!(find_next_magic(&mut c, magic, 0, 5000).unwrap(), Some(2046));
+        assert_eq!(
+            find_next_magic(&mut c, magic, 2047, 5000).unwrap(),
+            Some(4000)
+        );
+        assert_eq!(find_next_magic(&mut c, magic, 2047, 4000).unwrap(), None);
+        assert_eq!(find_next_magic(&mut c, magic, 4001, 5000).unwrap(), None);
+    }
+
+    #[test]
+    fn zip64_central_directory_end_skips_extensible_data() {
+        let mut block = Zip64CentralDirectoryEnd {
+            version_made_by: 45,
+            version_needed_to_extract: 45,
+            disk_number: 0,
+            disk_with_central_directory: 0,
+            number_of_files_on_this_disk: 0,
+            number_of_files: 0,
+            central_directory_size: 0,
+            central_directory_offset: 0,
+        }
+        .block();
+        block.record_size += 16;
+        let mut c = Cursor::new(Vec::new());
+        block.write(&mut c).unwrap();
+        c.write_all(&[0xAA; 16]).unwrap();
+        c.write_all(b"next").unwrap();
+        c.set_position(0);
+        Zip64CentralDirectoryEnd::parse(&mut c).unwrap();
+        assert_eq!(c.position(), 56 + 16);
+    }
+
     /// Demonstrate that a block object can be safely written to memory and deserialized back out.
     #[test]
     fn block_serde() {
@@ -1,5 +1,6 @@
 //! Types that specify what is contained in a ZIP.
 use crate::cp437::FromCp437;
+use crate::read::PathPolicy;
 use crate::write::{FileOptionExtension, FileOptions};
 use path::{Component, Path, PathBuf};
 use std::fmt;
@@ -14,14 +15,16 @@ use crate::result::{ZipError, ZipResult};
 use crate::spec::{self, Block};
 
 pub(crate) mod ffi {
+    /// Mask for the file type bits of a Unix mode
+    pub const S_IFMT: u32 = 0o0170000;
     pub const S_IFDIR: u32 = 0o0040000;
     pub const S_IFREG: u32 = 0o0100000;
     pub const S_IFLNK: u32 = 0o0120000;
 }
 
-use crate::extra_fields::ExtraField;
+use crate::extra_fields::{ExtraField, Zip64ExtendedInformation};
+use crate::path::is_dir;
 use crate::result::DateTimeRangeError;
-use crate::spec::is_dir;
 use crate::types::ffi::S_IFDIR;
 use crate::CompressionMethod;
 #[cfg(feature = "time")]
@@ -34,6 +37,7 @@ pub(crate) struct ZipRawValues {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum System {
     Dos = 0,
@@ -188,6 +192,73 @@ impl fmt::Display for DateTime {
     }
 }
 
+/// Serializes a `OnceLock` as the value it holds, if it's been set
+#[cfg(feature = "serde")]
+mod serde_once_lock {
+    use std::sync::OnceLock;
+
+    pub(super) fn serialize<S: serde::Serializer>(
+        value: &OnceLock<u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&value.get(), serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OnceLock<u64>, D::Error> {
+        let value: Option<u64> = serde::Deserialize::deserialize(deserializer)?;
+        Ok(value.map(OnceLock::from).unwrap_or_default())
+    }
+}
+
+/// Serialized as an ISO 8601 string without a time zone, such as `"2024-05-17T09:30:00"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for DateTime {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DateTime {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let s = String::deserialize(deserializer)?;
+        let invalid = || {
+            D::Error::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &"a date and time as YYYY-MM-DDTHH:MM:SS",
+            )
+        };
+        let bytes = s.as_bytes();
+        if bytes.len() != 19
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || bytes[10] != b'T'
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return Err(invalid());
+        }
+        let field = |range: std::ops::Range<usize>| {
+            s.get(range)
+                .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|digits| digits.parse::<u16>().ok())
+                .ok_or_else(invalid)
+        };
+        let year = field(0..4)?;
+        let [month, day, hour, minute, second] = [5..7, 8..10, 11..13, 14..16, 17..19]
+            .map(|range| field(range).map(|value| value as u8));
+        DateTime::from_date_and_time(year, month?, day?, hour?, minute?, second?)
+            .map_err(|_| invalid())
+    }
+}
+
 impl DateTime {
     /// Converts an msdos (u16, u16) pair to a DateTime object
     ///
@@ -355,6 +426,24 @@ impl DateTime {
     pub const fn second(&self) -> u8 {
         self.second
     }
+
+    /// Seconds since the Unix epoch, treating this as a UTC time. MS-DOS times don't record a
+    /// time zone, and are usually local times.
+    #[cfg(feature = "filetime")]
+    pub(crate) fn unix_timestamp(&self) -> i64 {
+        // Days since 1970-01-01 in the proleptic Gregorian calendar, counting years from March
+        // so the leap day comes last
+        let (year, month) = match self.month {
+            1 | 2 => (self.year as i64 - 1, self.month as i64 + 9),
+            _ => (self.year as i64, self.month as i64 - 3),
+        };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * month + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
 }
 
 #[cfg(feature = "time")]
@@ -393,17 +482,26 @@ pub const DEFAULT_VERSION: u8 = 45;
 
 /// Structure representing a ZIP file.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ZipFileData {
     /// Compatibility of the file attribute information
     pub system: System,
     /// Specification version
     pub version_made_by: u8,
+    /// The `version made by` field exactly as stored, with the host system in the upper byte
+    pub version_made_by_raw: u16,
+    /// The `version needed to extract` field exactly as stored
+    pub version_needed_raw: u16,
+    /// Number of the disk on which the file starts, in a multi-disk archive
+    pub disk_start: u32,
     /// True if the file is encrypted.
     pub encrypted: bool,
     /// True if the file uses a data-descriptor section
     pub using_data_descriptor: bool,
     /// Compression method used to store the file
     pub compression_method: crate::compression::CompressionMethod,
+    /// The compression method field exactly as stored, which is 99 for AES-encrypted files
+    pub compression_method_raw: u16,
     /// Compression level to store the file
     pub compression_level: Option<i64>,
     /// Last modified time. This will only have a 2 second precision.
@@ -418,6 +516,9 @@ pub struct ZipFileData {
     pub file_name: Box<str>,
     /// Raw file name. To be used when file_name was incorrectly decoded.
     pub file_name_raw: Box<[u8]>,
+    /// True if the raw file name starts with a UTF-8 byte order mark, which isn't part of
+    /// `file_name`.
+    pub file_name_had_bom: bool,
     /// Extra field usually used for storage expansion
     pub extra_field: Option<Arc<Vec<u8>>>,
     /// Extra field only written to central directory
@@ -433,6 +534,7 @@ pub struct ZipFileData {
     /// Note that when this is not known, it is set to 0
     pub central_header_start: u64,
     /// Specifies where the compressed data of the file starts
+    #[cfg_attr(feature = "serde", serde(with = "serde_once_lock"))]
     pub data_start: OnceLock<u64>,
     /// External file attributes
     pub external_attributes: u32,
@@ -445,6 +547,8 @@ pub struct ZipFileData {
 
     /// extra fields, see <https://libzip.org/specifications/extrafld.txt>
     pub extra_fields: Vec<ExtraField>,
+    /// ZIP64 extended information field, if the file had one
+    pub zip64_extended_information: Option<Zip64ExtendedInformation>,
 }
 
 impl ZipFileData {
@@ -453,6 +557,10 @@ impl ZipFileData {
         is_dir(&self.file_name)
     }
 
+    pub(crate) fn is_empty_file(&self) -> bool {
+        !self.is_dir() && self.uncompressed_size == 0
+    }
+
     pub fn file_name_sanitized(&self) -> PathBuf {
         let no_null_filename = match self.file_name.find('\0') {
             Some(index) => &self.file_name[0..index],
@@ -481,20 +589,54 @@ impl ZipFileData {
     }
 
     pub(crate) fn enclosed_name(&self) -> Option<PathBuf> {
+        self.safe_path_with(PathPolicy::default())
+    }
+
+    pub(crate) fn safe_path_with(&self, policy: PathPolicy) -> Option<PathBuf> {
+        // Names like `.`, `./` and `a/..` refer to the extraction directory itself
+        self.resolve_path(policy)
+            .and_then(|(path, depth)| (depth > 0).then_some(path))
+    }
+
+    /// Whether the name safely resolves to the extraction directory itself, like `./` or `a/..`.
+    pub(crate) fn names_root(&self) -> bool {
+        self.resolve_path(PathPolicy::default())
+            .is_some_and(|(_, depth)| depth == 0)
+    }
+
+    /// The name as a path and the number of components it nests below the extraction directory,
+    /// or `None` if it can't be used as a path under `policy`.
+    fn resolve_path(&self, policy: PathPolicy) -> Option<(PathBuf, usize)> {
         if self.file_name.contains('\0') {
             return None;
         }
-        let path = PathBuf::from(self.file_name.to_string());
+        let path = if policy.backslash_as_separator {
+            PathBuf::from(self.file_name.replace('\\', "/"))
+        } else {
+            PathBuf::from(self.file_name.to_string())
+        };
         let mut depth = 0usize;
         for component in path.components() {
             match component {
                 Component::Prefix(_) | Component::RootDir => return None,
                 Component::ParentDir => depth = depth.checked_sub(1)?,
-                Component::Normal(_) => depth += 1,
+                Component::Normal(name) => {
+                    let name = name.to_str()?;
+                    if policy
+                        .max_component_len
+                        .is_some_and(|max_len| name.len() > max_len)
+                    {
+                        return None;
+                    }
+                    if policy.reject_windows_reserved && is_windows_reserved(name) {
+                        return None;
+                    }
+                    depth += 1
+                }
                 Component::CurDir => (),
             }
         }
-        Some(path)
+        Some((path, depth))
     }
 
     /// Get unix mode for the file
@@ -544,7 +686,7 @@ impl ZipFileData {
         } else {
             10
         };
-        let misc_feature_version: u16 = if self.large_file {
+        let misc_feature_version: u16 = if self.zip64_extra_field_block().is_some() {
             45
         } else if self
             .unix_mode()
@@ -566,6 +708,12 @@ impl ZipFileData {
             .map(|v| v.len())
             .unwrap_or_default()
     }
+    /// The size this entry counts toward [`ArchiveLimits::max_metadata_size`].
+    ///
+    /// [`ArchiveLimits::max_metadata_size`]: crate::read::ArchiveLimits::max_metadata_size
+    pub(crate) fn metadata_size(&self) -> u64 {
+        (self.file_name_raw.len() + self.extra_field_len() + self.file_comment.len()) as u64
+    }
     #[inline(always)]
     pub(crate) fn central_extra_field_len(&self) -> usize {
         self.central_extra_field
@@ -594,10 +742,14 @@ impl ZipFileData {
         let file_name_raw: Box<[u8]> = file_name.bytes().collect();
         let mut local_block = ZipFileData {
             system: System::Unix,
-            version_made_by: DEFAULT_VERSION,
+            version_made_by: 0,
+            version_made_by_raw: 0,
+            version_needed_raw: 0,
+            disk_start: 0,
             encrypted: options.encrypt_with.is_some(),
             using_data_descriptor: false,
             compression_method,
+            compression_method_raw: 0,
             compression_level: options.compression_level,
             last_modified_time: Some(options.last_modified_time),
             crc32: raw_values.crc32,
@@ -605,6 +757,7 @@ impl ZipFileData {
             uncompressed_size: raw_values.uncompressed_size,
             file_name, // Never used for saving, but used as map key in insert_file_data()
             file_name_raw,
+            file_name_had_bom: false,
             extra_field,
             central_extra_field: options.extended_options.central_extra_data().cloned(),
             file_comment: String::with_capacity(0).into_boxed_str(),
@@ -615,13 +768,27 @@ impl ZipFileData {
             large_file: options.large_file,
             aes_mode,
             extra_fields: Vec::new(),
+            zip64_extended_information: None,
             extra_data_start,
             aes_extra_data_start,
         };
-        local_block.version_made_by = local_block.version_needed() as u8;
+        // AES entries store 99 as their method, and the actual one in the AES extra field
+        local_block.compression_method_raw = match aes_mode {
+            Some(_) => CompressionMethod::AES.serialize_to_u16(),
+            None => compression_method.serialize_to_u16(),
+        };
+        local_block.update_versions();
         local_block
     }
 
+    /// Set the version fields to the values that will be written for this file. These depend on
+    /// its sizes and position, so this must be called again whenever they change.
+    pub(crate) fn update_versions(&mut self) {
+        self.version_needed_raw = self.version_needed();
+        self.version_made_by = self.version_made_by.max(self.version_needed_raw as u8);
+        self.version_made_by_raw = (self.system as u16) << 8 | self.version_made_by as u16;
+    }
+
     pub(crate) fn from_local_block<R: std::io::Read>(
         block: ZipLocalEntryBlock,
         reader: &mut R,
@@ -642,23 +809,13 @@ impl ZipFileData {
         } = block;
 
         let encrypted: bool = flags & 1 == 1;
-        if encrypted {
-            return Err(ZipError::UnsupportedArchive(
-                "Encrypted files are not supported",
-            ));
-        }
 
-        /* FIXME: these were previously incorrect: add testing! */
         /* flags & (1 << 3) != 0 */
         let using_data_descriptor: bool = flags & (1 << 3) == 1 << 3;
-        if using_data_descriptor {
-            return Err(ZipError::UnsupportedArchive(
-                "The file length is not available in the local header",
-            ));
-        }
 
         /* flags & (1 << 1) != 0 */
         let is_utf8: bool = flags & (1 << 11) != 0;
+        let compression_method_raw = compression_method;
         let compression_method = crate::CompressionMethod::parse_from_u16(compression_method);
         let file_name_length: usize = file_name_length.into();
         let extra_field_length: usize = extra_field_length.into();
@@ -668,19 +825,20 @@ impl ZipFileData {
         let mut extra_field = vec![0u8; extra_field_length];
         reader.read_exact(&mut extra_field)?;
 
-        let file_name: Box<str> = match is_utf8 {
-            true => String::from_utf8_lossy(&file_name_raw).into(),
-            false => file_name_raw.clone().from_cp437().into(),
-        };
+        let (file_name, file_name_had_bom) = Self::decode_file_name(&file_name_raw, is_utf8);
 
         let system: u8 = (version_made_by >> 8).try_into().unwrap();
         Ok(ZipFileData {
             system: System::from(system),
             /* NB: this strips the top 8 bits! */
             version_made_by: version_made_by as u8,
+            version_made_by_raw: version_made_by,
+            version_needed_raw: version_made_by,
+            disk_start: 0,
             encrypted,
             using_data_descriptor,
             compression_method,
+            compression_method_raw,
             compression_level: None,
             last_modified_time: DateTime::try_from_msdos(last_mod_date, last_mod_time).ok(),
             crc32,
@@ -688,6 +846,7 @@ impl ZipFileData {
             uncompressed_size: uncompressed_size.into(),
             file_name,
             file_name_raw: file_name_raw.into(),
+            file_name_had_bom,
             extra_field: Some(Arc::new(extra_field)),
             central_extra_field: None,
             file_comment: String::with_capacity(0).into_boxed_str(), // file comment is only available in the central directory
@@ -703,11 +862,27 @@ impl ZipFileData {
             large_file: false,
             aes_mode: None,
             extra_fields: Vec::new(),
+            zip64_extended_information: None,
             extra_data_start: None,
             aes_extra_data_start: 0,
         })
     }
 
+    /// Decode a raw file name as UTF-8 if `is_utf8` is set and as CP437 otherwise. Also returns
+    /// whether the name had a leading UTF-8 byte order mark, which some writers add without
+    /// setting the UTF-8 flag; it's stripped, and the rest of the name is decoded as UTF-8 if
+    /// it's valid.
+    pub(crate) fn decode_file_name(file_name_raw: &[u8], is_utf8: bool) -> (Box<str>, bool) {
+        const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+        match file_name_raw.strip_prefix(UTF8_BOM) {
+            Some(rest) if is_utf8 || std::str::from_utf8(rest).is_ok() => {
+                (String::from_utf8_lossy(rest).into(), true)
+            }
+            _ if is_utf8 => (String::from_utf8_lossy(file_name_raw).into(), false),
+            _ => (file_name_raw.from_cp437().into(), false),
+        }
+    }
+
     fn is_utf8(&self) -> bool {
         std::str::from_utf8(&self.file_name_raw).is_ok()
     }
@@ -795,7 +970,7 @@ impl ZipFileData {
             extra_field_length: zip64_extra_field_length
                 + extra_field_len
                 + central_extra_field_len,
-            file_comment_length: self.file_comment.as_bytes().len().try_into().unwrap(),
+            file_comment_length: self.file_comment.len().try_into().unwrap(),
             disk_number: 0,
             internal_file_attributes: 0,
             external_file_attributes: self.external_attributes,
@@ -850,6 +1025,22 @@ impl ZipFileData {
     }
 }
 
+/// Whether a path component can't be used as a file name on Windows: a reserved device name
+/// (with or without an extension), a name ending in `.` or a space, or one containing a character
+/// Windows doesn't allow.
+fn is_windows_reserved(name: &str) -> bool {
+    const RESERVED: [&str; 22] = [
+        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+        "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+    ];
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED.iter().any(|r| stem.eq_ignore_ascii_case(r))
+        || name.ends_with(['.', ' '])
+        || name
+            .chars()
+            .any(|c| c.is_ascii_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*'))
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(packed)]
 pub(crate) struct ZipCentralEntryBlock {
@@ -996,6 +1187,7 @@ impl Zip64ExtraFieldBlock {
 /// According to the [specification](https://www.winzip.com/win/en/aes_info.html#winzip11) AE-2
 /// does not make use of the CRC check.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum AesVendorVersion {
     Ae1 = 0x0001,
@@ -1003,8 +1195,9 @@ pub enum AesVendorVersion {
 }
 
 /// AES variant used.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(fuzzing, derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum AesMode {
     /// 128-bit AES encryption.
@@ -1054,9 +1247,13 @@ mod test {
         let data = ZipFileData {
             system: System::Dos,
             version_made_by: 0,
+            version_made_by_raw: 0,
+            version_needed_raw: 0,
+            disk_start: 0,
             encrypted: false,
             using_data_descriptor: false,
             compression_method: crate::compression::CompressionMethod::Stored,
+            compression_method_raw: 0,
             compression_level: None,
             last_modified_time: None,
             crc32: 0,
@@ -1064,6 +1261,7 @@ mod test {
             uncompressed_size: 0,
             file_name: file_name.clone().into_boxed_str(),
             file_name_raw: file_name.into_bytes().into_boxed_slice(),
+            file_name_had_bom: false,
             extra_field: None,
             central_extra_field: None,
             file_comment: String::with_capacity(0).into_boxed_str(),
@@ -1076,10 +1274,57 @@ mod test {
             aes_mode: None,
             aes_extra_data_start: 0,
             extra_fields: Vec::new(),
+            zip64_extended_information: None,
         };
         assert_eq!(data.file_name_sanitized(), PathBuf::from("path/etc/passwd"));
     }
 
+    #[test]
+    fn update_versions() {
+        use super::*;
+        use crate::write::SimpleFileOptions;
+
+        let mut data = ZipFileData::initialize_local_block(
+            "file.txt",
+            &SimpleFileOptions::default(),
+            ZipRawValues {
+                crc32: 0,
+                compressed_size: 0,
+                uncompressed_size: 0,
+            },
+            0,
+            None,
+            0,
+            CompressionMethod::Stored,
+            None,
+            None,
+        );
+        assert_eq!(data.version_needed_raw, 10);
+        assert_eq!(data.version_made_by_raw, 0x030A);
+
+        // Starting beyond the ZIP32 limit takes a ZIP64 extra field
+        data.header_start = spec::ZIP64_BYTES_THR;
+        data.update_versions();
+        let block = data.block(0);
+        assert_eq!(data.version_needed_raw, 45);
+        assert_eq!(data.version_needed_raw, { block.version_to_extract });
+        assert_eq!(data.version_made_by_raw, { block.version_made_by });
+    }
+
+    #[cfg(feature = "filetime")]
+    #[test]
+    fn datetime_unix_timestamp() {
+        use super::DateTime;
+        let timestamp = |year, month, day, hour, minute, second| {
+            DateTime::from_date_and_time(year, month, day, hour, minute, second)
+                .unwrap()
+                .unix_timestamp()
+        };
+        assert_eq!(timestamp(1980, 1, 1, 0, 0, 0), 315532800);
+        assert_eq!(timestamp(2000, 2, 29, 12, 30, 58), 951827458);
+        assert_eq!(timestamp(2024, 12, 31, 23, 59, 58), 1735689598);
+    }
+
     #[test]
     #[allow(clippy::unusual_byte_groupings)]
     fn datetime_default() {
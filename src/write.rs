@@ -3,7 +3,7 @@
 #[cfg(feature = "aes-crypto")]
 use crate::aes::AesWriter;
 use crate::compression::CompressionMethod;
-use crate::read::{find_content, ZipArchive, ZipFile, ZipFileReader};
+use crate::read::{find_content, ArchiveLimits, Config, ZipArchive, ZipFile, ZipFileReader};
 use crate::result::{ZipError, ZipResult};
 use crate::spec::{self, Block};
 #[cfg(feature = "aes-crypto")]
@@ -16,7 +16,7 @@ use crate::write::ffi::S_IFLNK;
 use core::num::NonZeroU64;
 use crc32fast::Hasher;
 use indexmap::IndexMap;
-use std::borrow::ToOwned;
+use std::borrow::{Cow, ToOwned};
 use std::default::Default;
 use std::io;
 use std::io::prelude::*;
@@ -132,10 +132,10 @@ pub(crate) mod zip_writer {
 }
 #[doc(inline)]
 pub use self::sealed::FileOptionExtension;
+use crate::path::path_to_string;
 use crate::result::ZipError::InvalidArchive;
-#[cfg(feature = "lzma")]
+#[cfg(any(feature = "lzma", feature = "brotli", feature = "ppmd"))]
 use crate::result::ZipError::UnsupportedArchive;
-use crate::spec::path_to_string;
 use crate::unstable::LittleEndianWriteExt;
 use crate::write::GenericZipWriter::{Closed, Storer};
 use crate::zipcrypto::ZipCryptoKeys;
@@ -423,10 +423,10 @@ impl<'k> FileOptions<'k, ExtendedFileOptions> {
     /// Removes the extra data fields.
     #[must_use]
     pub fn clear_extra_data(mut self) -> Self {
-        if self.extended_options.extra_data.len() > 0 {
+        if !self.extended_options.extra_data.is_empty() {
             self.extended_options.extra_data = Arc::new(vec![]);
         }
-        if self.extended_options.central_extra_data.len() > 0 {
+        if !self.extended_options.central_extra_data.is_empty() {
             self.extended_options.central_extra_data = Arc::new(vec![]);
         }
         self
@@ -505,10 +505,34 @@ impl ZipWriterStats {
 
 impl<A: Read + Write + Seek> ZipWriter<A> {
     /// Initializes the archive from an existing ZIP archive, making it ready for append.
-    pub fn new_append(mut readwriter: A) -> ZipResult<ZipWriter<A>> {
+    ///
+    /// The archive belongs to the caller, so its central directory isn't subject to the limits
+    /// that [`Config`] applies to untrusted archives by default; use
+    /// [`ZipWriter::new_append_with_config`] to apply them.
+    pub fn new_append(readwriter: A) -> ZipResult<ZipWriter<A>> {
+        let config = Config {
+            max_entries: usize::MAX,
+            limits: ArchiveLimits {
+                max_metadata_size: u64::MAX,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Self::new_append_with_config(config, readwriter)
+    }
+
+    /// Initializes the archive from an existing ZIP archive, making it ready for append, and
+    /// reads its central directory with the given [`Config`].
+    pub fn new_append_with_config(config: Config, mut readwriter: A) -> ZipResult<ZipWriter<A>> {
         let (footer, cde_start_pos) =
-            spec::Zip32CentralDirectoryEnd::find_and_parse(&mut readwriter)?;
-        let metadata = ZipArchive::get_metadata(&mut readwriter, &footer, cde_start_pos)?;
+            spec::Zip32CentralDirectoryEnd::find_and_parse(&mut readwriter, config.lenient)?;
+        let metadata = ZipArchive::get_metadata(
+            config,
+            &mut readwriter,
+            &footer,
+            cde_start_pos,
+            &mut |_, _| {},
+        )?;
 
         Ok(ZipWriter {
             inner: Storer(MaybeEncrypted::Unencrypted(readwriter)),
@@ -660,10 +684,13 @@ impl<A: Read + Write + Seek> ZipWriter<A> {
     ///```
     pub fn finish_into_readable(mut self) -> ZipResult<ZipArchive<A>> {
         let central_start = self.finalize()?;
+        let cde_start_pos = self.inner.get_plain().stream_position()?
+            - (mem::size_of::<spec::Zip32CDEBlock>() + self.comment.len()) as u64;
         let inner = mem::replace(&mut self.inner, Closed).unwrap();
         let comment = mem::take(&mut self.comment);
         let files = mem::take(&mut self.files);
-        let archive = ZipArchive::from_finalized_writer(files, comment, inner, central_start)?;
+        let archive =
+            ZipArchive::from_finalized_writer(files, comment, inner, central_start, cde_start_pos)?;
         Ok(archive)
     }
 }
@@ -784,7 +811,7 @@ impl<W: Write + Seek> ZipWriter<W> {
                 _ => (options.compression_method, None),
             };
 
-            let mut file = ZipFileData::initialize_local_block(
+            let file = ZipFileData::initialize_local_block(
                 name,
                 &options,
                 raw_values,
@@ -795,7 +822,6 @@ impl<W: Write + Seek> ZipWriter<W> {
                 aes_mode,
                 extra_field,
             );
-            file.version_made_by = file.version_made_by.max(file.version_needed() as u8);
             let index = self.insert_file_data(file)?;
             let file = &mut self.files[index];
             let writer = self.inner.get_plain();
@@ -945,6 +971,7 @@ impl<W: Write + Seek> ZipWriter<W> {
                 }
             }
 
+            file.update_versions();
             update_aes_extra_data(writer, file)?;
             update_local_file_header(writer, file)?;
             writer.seek(SeekFrom::Start(file_end))?;
@@ -1126,7 +1153,7 @@ impl<W: Write + Seek> ZipWriter<W> {
     /// Add a new file using the already compressed data from a ZIP file being read and renames it, this
     /// allows faster copies of the `ZipFile` since there is no need to decompress and compress it again.
     /// Any `ZipFile` metadata is copied and not checked, for example the file CRC.
-
+    ///
     /// ```no_run
     /// use std::fs::File;
     /// use std::io::{Read, Seek, Write};
@@ -1154,6 +1181,12 @@ impl<W: Write + Seek> ZipWriter<W> {
         S: Into<Box<str>> + ToOwned<Owned = SToOwned>,
         SToOwned: Into<Box<str>>,
     {
+        // A streamed entry's sizes and CRC-32 aren't known until it has been decompressed
+        if file.data.using_data_descriptor && matches!(file.data, Cow::Owned(_)) {
+            return Err(ZipError::UnsupportedArchive(
+                "Entries read from a stream with a data descriptor can't be copied raw",
+            ));
+        }
         let mut options = SimpleFileOptions::default()
             .large_file(file.compressed_size().max(file.size()) > spec::ZIP64_BYTES_THR)
             .last_modified_time(
@@ -1224,6 +1257,9 @@ impl<W: Write + Seek> ZipWriter<W> {
 
     /// Add a directory entry.
     ///
+    /// A `/` is appended to `name` unless it already ends with one. Only `/` separates path
+    /// components in a ZIP file, so a name ending in `\` gets one too: `"foo\\"` becomes `"foo\\/"`.
+    ///
     /// As directories have no content, you must not call [`ZipWriter::write`] before adding a new file.
     pub fn add_directory<S, T: FileOptionExtension>(
         &mut self,
@@ -1242,9 +1278,10 @@ impl<W: Write + Seek> ZipWriter<W> {
 
         let name_as_string = name.into();
         // Append a slash to the filename if it does not end with it.
-        let name_with_slash = match name_as_string.chars().last() {
-            Some('/') | Some('\\') => name_as_string,
-            _ => name_as_string + "/",
+        let name_with_slash = if name_as_string.ends_with('/') {
+            name_as_string
+        } else {
+            name_as_string + "/"
         };
 
         self.start_entry(name_with_slash, options, None)?;
@@ -1359,7 +1396,8 @@ impl<W: Write + Seek> ZipWriter<W> {
 
         let mut version_needed = MIN_VERSION as u16;
         let central_start = writer.stream_position()?;
-        for file in self.files.values() {
+        for file in self.files.values_mut() {
+            file.update_versions();
             write_central_directory_header(writer, file)?;
             version_needed = version_needed.max(file.version_needed());
         }
@@ -1399,6 +1437,7 @@ impl<W: Write + Seek> ZipWriter<W> {
             number_of_files,
             central_directory_size: central_size.min(spec::ZIP64_BYTES_THR) as u32,
             central_directory_offset: central_start.min(spec::ZIP64_BYTES_THR) as u32,
+            comment_truncated: false,
         };
 
         footer.write(writer)?;
@@ -1578,6 +1617,14 @@ impl<W: Write + Seek> GenericZipWriter<W> {
                 CompressionMethod::Lzma => {
                     Err(UnsupportedArchive("LZMA isn't supported for compression"))
                 }
+                #[cfg(feature = "brotli")]
+                CompressionMethod::Brotli => {
+                    Err(UnsupportedArchive("Brotli isn't supported for compression"))
+                }
+                #[cfg(feature = "ppmd")]
+                CompressionMethod::Ppmd => {
+                    Err(UnsupportedArchive("PPMd isn't supported for compression"))
+                }
                 CompressionMethod::Unsupported(..) => {
                     Err(ZipError::UnsupportedArchive("Unsupported compression"))
                 }
@@ -1799,11 +1846,7 @@ fn validate_extra_data(header_id: u16, data: &[u8]) -> ZipResult<()> {
 
     #[cfg(not(feature = "unreserved"))]
     {
-        if header_id <= 31
-            || EXTRA_FIELD_MAPPING
-                .iter()
-                .any(|&mapped| mapped == header_id)
-        {
+        if header_id <= 31 || EXTRA_FIELD_MAPPING.contains(&header_id) {
             return Err(ZipError::Io(io::Error::new(
                 io::ErrorKind::Other,
                 format!(
@@ -2321,6 +2364,38 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn new_append_with_config() {
+        use crate::read::Config;
+
+        let mut writer = ZipWriter::new(io::Cursor::new(Vec::new()));
+        for name in ["a", "b", "c"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+        }
+        let zip = writer.finish().unwrap();
+
+        let config = Config {
+            max_entries: 2,
+            ..Default::default()
+        };
+        assert!(matches!(
+            ZipWriter::new_append_with_config(config, zip.clone()),
+            Err(crate::result::ZipError::InvalidArchive(_))
+        ));
+        let config = Config {
+            max_entries: 3,
+            ..Default::default()
+        };
+        let mut writer = ZipWriter::new_append_with_config(config, zip).unwrap();
+        writer
+            .start_file("d", SimpleFileOptions::default())
+            .unwrap();
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(archive.len(), 4);
+    }
+
     #[test]
     fn remove_shallow_copy_keeps_original() -> ZipResult<()> {
         let mut writer = ZipWriter::new(io::Cursor::new(Vec::new()));
@@ -2411,7 +2486,7 @@ mod test {
         let mut zip = zip.finish_into_readable().unwrap();
         let file = zip.by_index(0).unwrap();
         assert_eq!(file.name(), "sleep");
-        assert_eq!(file.data_start(), page_size.into());
+        assert_eq!(file.data_start(), u64::from(page_size));
     }
 
     #[test]
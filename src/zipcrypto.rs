@@ -27,7 +27,7 @@ impl Debug for ZipCryptoKeys {
             use std::hash::Hasher;
             let mut t = DefaultHasher::new();
             self.hash(&mut t);
-            return f.write_fmt(format_args!("ZipCryptoKeys(hash {})", t.finish()));
+            f.write_fmt(format_args!("ZipCryptoKeys(hash {})", t.finish()))
         }
         #[cfg(any(test, fuzzing))]
         return f.write_fmt(format_args!(
@@ -89,9 +89,17 @@ pub struct ZipCryptoReader<R> {
     keys: ZipCryptoKeys,
 }
 
+/// The check against which the last byte of the decrypted 12-byte encryption header is compared,
+/// to detect a wrong password before decrypting any data.
 pub enum ZipCryptoValidator {
+    /// The high byte of the CRC-32 of the plaintext
     PkzipCrc32(u32),
+    /// The high byte of the MS-DOS modification time, which Info-ZIP uses when the CRC-32 isn't
+    /// known before the data is written, i.e. when the file uses a data descriptor
     InfoZipMsdosTime(u16),
+    /// Either of the above, for files without a data descriptor; some writers use the
+    /// modification time for these too
+    PkzipCrc32OrMsdosTime { crc32: u32, last_mod_time: u16 },
 }
 
 impl<R: std::io::Read> ZipCryptoReader<R> {
@@ -142,6 +150,16 @@ impl<R: std::io::Read> ZipCryptoReader<R> {
                     return Err(ZipError::InvalidPassword);
                 }
             }
+            ZipCryptoValidator::PkzipCrc32OrMsdosTime {
+                crc32,
+                last_mod_time,
+            } => {
+                if (crc32 >> 24) as u8 != header_buf[11]
+                    && (last_mod_time >> 8) as u8 != header_buf[11]
+                {
+                    return Err(ZipError::InvalidPassword);
+                }
+            }
         }
 
         Ok(ZipCryptoReaderValid { reader: self })
@@ -234,3 +252,66 @@ static CRCTABLE: [u32; 256] = [
     0xbdbdf21c, 0xcabac28a, 0x53b39330, 0x24b4a3a6, 0xbad03605, 0xcdd70693, 0x54de5729, 0x23d967bf,
     0xb3667a2e, 0xc4614ab8, 0x5d681b02, 0x2a6f2b94, 0xb40bbe37, 0xc30c8ea1, 0x5a05df1b, 0x2d02ef8d,
 ];
+
+#[cfg(test)]
+mod test {
+    use super::{ZipCryptoKeys, ZipCryptoReader, ZipCryptoValidator};
+    use crate::result::ZipError;
+
+    const PASSWORD: &[u8] = b"password";
+    const CRC32: u32 = 0x12345678;
+    const LAST_MOD_TIME: u16 = 0xabcd;
+
+    /// An encryption header whose last byte decrypts to `check_byte`
+    fn header(check_byte: u8) -> Vec<u8> {
+        let mut keys = ZipCryptoKeys::derive(PASSWORD);
+        let mut header = [0; 12];
+        header[11] = check_byte;
+        header.iter().map(|&byte| keys.encrypt_byte(byte)).collect()
+    }
+
+    fn validate(header: &[u8], validator: ZipCryptoValidator) -> Result<(), ZipError> {
+        ZipCryptoReader::new(header, PASSWORD)
+            .validate(validator)
+            .map(|_| ())
+    }
+
+    #[test]
+    fn crc32_validator() {
+        validate(&header(0x12), ZipCryptoValidator::PkzipCrc32(CRC32)).unwrap();
+        assert!(matches!(
+            validate(&header(0xab), ZipCryptoValidator::PkzipCrc32(CRC32)),
+            Err(ZipError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn msdos_time_validator() {
+        validate(
+            &header(0xab),
+            ZipCryptoValidator::InfoZipMsdosTime(LAST_MOD_TIME),
+        )
+        .unwrap();
+        assert!(matches!(
+            validate(
+                &header(0x12),
+                ZipCryptoValidator::InfoZipMsdosTime(LAST_MOD_TIME)
+            ),
+            Err(ZipError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn crc32_or_msdos_time_validator() {
+        let validator = || ZipCryptoValidator::PkzipCrc32OrMsdosTime {
+            crc32: CRC32,
+            last_mod_time: LAST_MOD_TIME,
+        };
+        validate(&header(0x12), validator()).unwrap();
+        validate(&header(0xab), validator()).unwrap();
+        assert!(matches!(
+            validate(&header(0x00), validator()),
+            Err(ZipError::InvalidPassword)
+        ));
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "bzip2")]
+
+use std::io::{self, Read};
+use zip::ZipArchive;
+
+#[test]
+fn truncated_bzip2_stream_is_an_error() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/bzip2_truncated.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+
+    let mut file = archive
+        .by_name("data.bin")
+        .expect("couldn't find file in archive");
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)
+        .expect_err("a truncated bzip2 stream should fail to decompress");
+}
+
+#[test]
+fn corrupt_bzip2_stream_is_an_error() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/bzip2_corrupt.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+
+    let mut file = archive
+        .by_name("data.bin")
+        .expect("couldn't find file in archive");
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)
+        .expect_err("a corrupt bzip2 stream should fail to decompress");
+}
+
+#[test]
+fn bzip2_block_size() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/bzip2_truncated.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+    assert_eq!(archive.bzip2_block_size(0).unwrap(), Some(900_000));
+
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/mimetype.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+    assert_eq!(archive.bzip2_block_size(0).unwrap(), None);
+}
@@ -103,6 +103,20 @@ fn append() {
     }
 }
 
+// This test asserts that an archive split into pieces can be read back through a `ConcatReader`.
+#[test]
+fn split_archive() {
+    let file = &mut Cursor::new(Vec::new());
+    write_test_archive(file, CompressionMethod::Stored, true);
+    let bytes = file.get_ref();
+    let pieces = bytes
+        .chunks(bytes.len() / 3 + 1)
+        .map(|chunk| Cursor::new(chunk.to_vec()))
+        .collect();
+    let reader = zip::ConcatReader::new(pieces).unwrap();
+    check_test_archive(reader).expect("couldn't read split archive");
+}
+
 // Write a test zip archive to buffer.
 fn write_test_archive(file: &mut Cursor<Vec<u8>>, method: CompressionMethod, shallow_copy: bool) {
     let mut zip = ZipWriter::new(file);
@@ -19,3 +19,42 @@ fn decompress_lzma() {
         .expect("couldn't read encrypted and compressed file");
     assert_eq!("Hello world\n", String::from_utf8(content).unwrap());
 }
+
+#[test]
+fn decompress_lzma_end_marker_and_known_size() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/lzma_variants.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+
+    for name in ["end_marker.txt", "known_size.txt"] {
+        let mut file = archive.by_name(name).unwrap();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"Hello world\n".repeat(100), "{name}");
+    }
+}
+
+#[test]
+fn skip_lzma_entries_in_stream() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/lzma_variants.zip"));
+    let mut stream = io::Cursor::new(v);
+
+    // Dropping an entry without reading it has to drain its data, whichever way it ends
+    let file = zip::read::read_zipfile_from_stream(&mut stream)
+        .unwrap()
+        .unwrap();
+    assert_eq!(file.name(), "end_marker.txt");
+    drop(file);
+    let mut file = zip::read::read_zipfile_from_stream(&mut stream)
+        .unwrap()
+        .unwrap();
+    assert_eq!(file.name(), "known_size.txt");
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).unwrap();
+    assert_eq!(content, b"Hello world\n".repeat(100));
+    drop(file);
+    assert!(zip::read::read_zipfile_from_stream(&mut stream)
+        .unwrap()
+        .is_none());
+}
@@ -17,7 +17,7 @@
 // 00000020  6e 74 20 62 6c 61 20 62  6c 61 20 62 6c 61        |nt bla bla bla|
 // 0000002e
 
-use std::io;
+use std::io::{self, Read};
 use zip::ZipArchive;
 
 #[test]
@@ -27,6 +27,10 @@ fn correctly_handle_zip_with_garbage_after_comment() {
     let archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
 
     assert_eq!(archive.comment(), "short.".as_bytes());
+
+    let mut comment = Vec::new();
+    archive.comment_reader().read_to_end(&mut comment).unwrap();
+    assert_eq!(comment, archive.comment());
 }
 
 /// Ensure that a file which has the signature misaligned with the window size is still
@@ -55,6 +55,63 @@ fn encrypting_file() {
     file.read_to_end(&mut buf).unwrap();
     assert_eq!(buf, b"test");
 }
+#[test]
+fn extract_decrypt() {
+    use std::io::Write;
+    use zip::unstable::write::FileOptionsExt;
+    let mut archive = zip::write::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    archive.start_file("plain", options).unwrap();
+    archive.write_all(b"plain text").unwrap();
+    archive
+        .start_file("secret", options.with_deprecated_encryption(b"password"))
+        .unwrap();
+    archive.write_all(b"secret text").unwrap();
+    let mut archive = zip::ZipArchive::new(archive.finish().unwrap()).unwrap();
+
+    let dir = tempdir::TempDir::new("extract_decrypt").unwrap();
+    let mut options = zip::read::ExtractOptions::default();
+    options.password = Some(b"password");
+    archive.extract_with_options(dir.path(), options).unwrap();
+    assert_eq!(
+        std::fs::read(dir.path().join("plain")).unwrap(),
+        b"plain text"
+    );
+    assert_eq!(
+        std::fs::read(dir.path().join("secret")).unwrap(),
+        b"secret text"
+    );
+
+    let dir = tempdir::TempDir::new("extract_decrypt").unwrap();
+    assert!(matches!(
+        archive.extract(dir.path()),
+        Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED))
+    ));
+}
+
+#[test]
+fn encrypted_entry_indices() {
+    use std::io::Write;
+    use zip::unstable::write::FileOptionsExt;
+    let mut archive = zip::write::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    archive.start_file("plain", options).unwrap();
+    archive.write_all(b"test").unwrap();
+    archive
+        .start_file("secret", options.with_deprecated_encryption(b"password"))
+        .unwrap();
+    archive.write_all(b"test").unwrap();
+    let archive = zip::ZipArchive::new(archive.finish().unwrap()).unwrap();
+    assert!(archive.has_encrypted_entries());
+    assert_eq!(archive.encrypted_entry_indices(), vec![1]);
+
+    let archive = zip::ZipArchive::new(Cursor::new(ZIP_CRYPTO_FILE)).unwrap();
+    assert!(archive.has_encrypted_entries());
+    let archive = zip::ZipArchive::new(Cursor::new(include_bytes!("data/mimetype.zip"))).unwrap();
+    assert!(!archive.has_encrypted_entries());
+    assert!(archive.encrypted_entry_indices().is_empty());
+}
+
 #[test]
 fn encrypted_file() {
     use std::io::Read;
@@ -8,12 +8,40 @@ fn test_extended_timestamp() {
     let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
 
     for field in archive.by_name("test.txt").unwrap().extra_data_fields() {
+        // `ExtraField` is non-exhaustive, so other fields need an arm of their own
+        #[allow(clippy::single_match)]
         match field {
             zip::ExtraField::ExtendedTimestamp(ts) => {
                 assert!(ts.ac_time().is_none());
                 assert!(ts.cr_time().is_none());
                 assert_eq!(*ts.mod_time().unwrap(), 1714635025);
             }
+            _ => {}
         }
     }
 }
+
+#[test]
+fn test_extended_timestamp_from_local_header() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("../tests/data/extended_timestamp_local.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+
+    let times = |archive: &mut ZipArchive<_>, index| {
+        let ts = archive.extended_timestamp(index).unwrap().unwrap();
+        (
+            ts.mod_time().copied(),
+            ts.ac_time().copied(),
+            ts.cr_time().copied(),
+        )
+    };
+    assert_eq!(
+        times(&mut archive, 0),
+        (Some(1714635025), Some(1714635100), Some(1714600000))
+    );
+    assert_eq!(
+        times(&mut archive, 1),
+        (Some(1714635025), None, Some(1714600000))
+    );
+    assert_eq!(times(&mut archive, 2), (Some(1714635025), None, None));
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "zstd")]
+
+use std::io::{self, Read};
+use zip::ZipArchive;
+
+#[test]
+fn decompress_multiple_frames() {
+    let mut v = Vec::new();
+    v.extend_from_slice(include_bytes!("data/zstd_multiple_frames.zip"));
+    let mut archive = ZipArchive::new(io::Cursor::new(v)).expect("couldn't open test zip file");
+
+    let mut file = archive
+        .by_name("frames.txt")
+        .expect("couldn't find file in archive");
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)
+        .expect("couldn't read both zstd frames");
+    let mut expected = b"first frame\n".repeat(20);
+    expected.extend_from_slice(&b"second frame\n".repeat(20));
+    assert_eq!(content, expected);
+}